@@ -0,0 +1,111 @@
+//! Find files that are embedded as attachments inside a PDF document.
+//!
+//! Attachments are stored in the `/EmbeddedFiles` name tree of the document catalog's `/Names`
+//! dictionary. Each leaf of the tree is a file specification whose `/EF` dictionary holds the
+//! embedded file stream.
+
+use crate::pdf::decode_text_string;
+use hayro::Pdf;
+use hayro_syntax::object::dict::keys;
+use hayro_syntax::object::{Array, Dict, Name, Object, Stream};
+use std::sync::Arc;
+
+/// Guard against malformed (cyclic) name trees.
+const MAX_NAME_TREE_DEPTH: usize = 32;
+
+/// A file embedded inside a PDF document.
+#[derive(Clone)]
+pub struct Attachment {
+    /// File name from the attachment's file specification.
+    pub name: String,
+    /// Decoded contents of the embedded file.
+    pub data: Arc<Vec<u8>>,
+}
+impl Attachment {
+    /// `true` if the attachment looks like a PDF file that we can open in a new tab.
+    pub fn is_pdf(&self) -> bool {
+        self.data.starts_with(b"%PDF") || self.name.to_ascii_lowercase().ends_with(".pdf")
+    }
+}
+
+/// Find the document catalog, this is the dictionary with `/Type /Catalog`.
+pub fn find_catalog(pdf: &Pdf) -> Option<Dict<'_>> {
+    pdf.objects().into_iter().find_map(|object| match object {
+        Object::Dict(dict)
+            if dict
+                .get::<Name>(keys::TYPE)
+                .is_some_and(|kind| &*kind == b"Catalog") =>
+        {
+            Some(dict)
+        }
+        _ => None,
+    })
+}
+
+/// List all files embedded in the PDF document.
+pub fn find_attachments(pdf: &Pdf) -> Vec<Attachment> {
+    let Some(embedded_files) = find_catalog(pdf)
+        .and_then(|catalog| catalog.get::<Dict>(keys::NAMES))
+        .and_then(|names| names.get::<Dict>(keys::EMBEDDED_FILES))
+    else {
+        return Vec::new();
+    };
+
+    let mut attachments = Vec::new();
+    walk_name_tree(&embedded_files, 0, &mut |key, file_spec| {
+        if let Some(attachment) = read_file_spec(key, &file_spec) {
+            attachments.push(attachment);
+        }
+    });
+    attachments
+}
+
+/// Visit every leaf in a name tree. Leaves are stored as `[key1 value1 key2 value2 ...]` arrays
+/// in `/Names` while intermediate nodes list their children in `/Kids`.
+fn walk_name_tree<'a>(node: &Dict<'a>, depth: usize, visit: &mut dyn FnMut(String, Dict<'a>)) {
+    if depth > MAX_NAME_TREE_DEPTH {
+        log::warn!("Name tree is too deep, ignoring remaining entries");
+        return;
+    }
+    if let Some(names) = node.get::<Array>(keys::NAMES) {
+        let mut entries = names.iter::<Object>();
+        while let (Some(key), Some(value)) = (entries.next(), entries.next()) {
+            let (Some(key), Some(value)) = (key.into_string(), value.into_dict()) else {
+                continue;
+            };
+            visit(decode_text_string(&key.get()), value);
+        }
+    }
+    if let Some(kids) = node.get::<Array>(keys::KIDS) {
+        for kid in kids.iter::<Dict>() {
+            walk_name_tree(&kid, depth + 1, visit);
+        }
+    }
+}
+
+/// Read the embedded file referenced by a file specification dictionary.
+fn read_file_spec(key: String, file_spec: &Dict<'_>) -> Option<Attachment> {
+    let stream = file_spec.get::<Dict>(keys::EF).and_then(|ef| {
+        ef.get::<Stream>(keys::UF)
+            .or_else(|| ef.get::<Stream>(keys::F))
+    })?;
+    let data = match stream.decoded() {
+        Ok(data) => data.to_vec(),
+        Err(e) => {
+            log::warn!("Failed to decode embedded file {key:?}: {e:?}");
+            return None;
+        }
+    };
+    // Prefer the unicode file name, then the legacy file name and finally the name tree key:
+    let name = file_spec
+        .get::<hayro_syntax::object::String>(keys::UF)
+        .or_else(|| file_spec.get::<hayro_syntax::object::String>(keys::F))
+        .map(|name| decode_text_string(&name.get()))
+        .filter(|name| !name.is_empty())
+        .unwrap_or(key);
+
+    Some(Attachment {
+        name,
+        data: Arc::new(data),
+    })
+}
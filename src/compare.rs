@@ -0,0 +1,70 @@
+//! Compare two versions of a document by overlaying their rendered pages.
+//!
+//! The page from the other version is rendered at exactly the same pixel size as the page being
+//! shown and the pixels are then compared one by one. This is simple and works for any content but
+//! means that reflowed text shows up as changed even when the words are the same.
+
+use hayro::{InterpreterSettings, RenderSettings, render};
+use hayro_syntax::page::Page;
+
+/// Channels that differ less than this are considered equal, this hides anti-aliasing noise.
+const DIFFERENCE_THRESHOLD: u8 = 24;
+/// Color of pixels that are only dark in the shown page.
+const REMOVED_COLOR: [u8; 3] = [220, 40, 40];
+/// Color of pixels that are only dark in the other version.
+const ADDED_COLOR: [u8; 3] = [30, 160, 60];
+
+/// Render `other` stretched to `width` by `height` pixels so that it can be compared with another
+/// page, returns `RGBA` pixel data.
+pub fn render_matching(
+    other: &Page,
+    interpreter_settings: &InterpreterSettings,
+    width: u16,
+    height: u16,
+) -> Vec<u8> {
+    let (other_width, other_height) = other.render_dimensions();
+    let pixmap = render(
+        other,
+        interpreter_settings,
+        &RenderSettings {
+            x_scale: f32::from(width) / other_width.max(1.),
+            y_scale: f32::from(height) / other_height.max(1.),
+            width: Some(width),
+            height: Some(height),
+        },
+    );
+    pixmap.take_u8()
+}
+
+/// Replace the `RGBA` pixels in `shown` with a visualization of how they differ from `other`.
+///
+/// Unchanged content is faded, content that is darker in `shown` is marked as removed and content
+/// that is darker in `other` is marked as added. Returns the number of pixels that differ.
+pub fn highlight_differences(shown: &mut [u8], other: &[u8]) -> usize {
+    let mut changed = 0;
+    for (pixel, other) in shown.chunks_exact_mut(4).zip(other.chunks_exact(4)) {
+        let differs = pixel[..3]
+            .iter()
+            .zip(&other[..3])
+            .any(|(a, b)| a.abs_diff(*b) > DIFFERENCE_THRESHOLD);
+        if differs {
+            changed += 1;
+            let color = if luminance(pixel) < luminance(other) {
+                REMOVED_COLOR
+            } else {
+                ADDED_COLOR
+            };
+            pixel[..3].copy_from_slice(&color);
+        } else {
+            for channel in &mut pixel[..3] {
+                // Move 75% of the way towards white:
+                *channel = 255 - (255 - *channel) / 4;
+            }
+        }
+    }
+    changed
+}
+
+fn luminance(pixel: &[u8]) -> u32 {
+    (2126 * u32::from(pixel[0]) + 7152 * u32::from(pixel[1]) + 722 * u32::from(pixel[2])) / 10_000
+}
@@ -0,0 +1,46 @@
+//! Files that are persisted between runs, stored as JSON in the platform's config directory.
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::path::PathBuf;
+
+/// Folder inside the platform config directory where our files are stored.
+const APP_FOLDER: &str = "pdf-reader-gpui";
+
+/// Directory for persisted files, `None` if the platform doesn't have a config directory.
+pub fn config_dir() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join(APP_FOLDER))
+}
+
+/// Load a value that was previously stored with [`save_json`]. Returns `None` if the file doesn't
+/// exist or can't be parsed.
+pub fn load_json<T: DeserializeOwned>(file_name: &str) -> Option<T> {
+    let path = config_dir()?.join(file_name);
+    let data = match std::fs::read(&path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(e) => {
+            log::warn!("Failed to read {}: {e}", path.display());
+            return None;
+        }
+    };
+    serde_json::from_slice(&data)
+        .inspect_err(|e| log::warn!("Failed to parse {}: {e}", path.display()))
+        .ok()
+}
+
+/// Store a value in the config directory, errors are logged.
+pub fn save_json<T: Serialize>(file_name: &str, value: &T) {
+    let Some(dir) = config_dir() else {
+        log::warn!("No config directory, can't save {file_name}");
+        return;
+    };
+    let path = dir.join(file_name);
+    let result = std::fs::create_dir_all(&dir).and_then(|()| {
+        let data = serde_json::to_vec_pretty(value).map_err(std::io::Error::other)?;
+        std::fs::write(&path, data)
+    });
+    if let Err(e) = result {
+        log::error!("Failed to save {}: {e}", path.display());
+    }
+}
@@ -1,11 +1,30 @@
 //! Utilities for using ELM like architecture where UI updates are done in response to messages.
 
 use gpui::{AsyncWindowContext, Context, WeakEntity, Window};
+use std::fmt;
 
 pub trait Update<M>: Sized {
     fn update(&mut self, window: &mut Window, cx: &mut Context<Self>, msg: M);
 }
 
+/// Why a message couldn't be delivered by [`MsgSender`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SendError {
+    /// The view that should handle the message was dropped.
+    ViewDropped,
+    /// The window that contained the view was closed.
+    WindowClosed,
+}
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendError::ViewDropped => write!(f, "the view that handles messages was dropped"),
+            SendError::WindowClosed => write!(f, "the window was closed"),
+        }
+    }
+}
+impl std::error::Error for SendError {}
+
 pub struct MsgSender<T> {
     window_and_cx: AsyncWindowContext,
     weak: WeakEntity<T>,
@@ -35,21 +54,37 @@ impl<T: 'static> MsgSender<T> {
             .spawn(async move |window: &mut AsyncWindowContext| f(window, this).await)
     }
 
-    pub fn send<M>(&mut self, msg: M)
+    pub fn send<M>(&mut self, msg: M) -> Result<(), SendError>
     where
         T: Update<M>,
     {
-        _ = self
-            .window_and_cx
+        self.send_batch([msg])
+    }
+    /// Like [`Self::send`] but for messages that can be lost if the view is gone.
+    pub fn send_ignoring_errors<M>(&mut self, msg: M)
+    where
+        T: Update<M>,
+    {
+        _ = self.send(msg);
+    }
+
+    /// Handle several messages in a single update of the view so that it is only re-rendered once.
+    pub fn send_batch<M>(&mut self, msgs: impl IntoIterator<Item = M>) -> Result<(), SendError>
+    where
+        T: Update<M>,
+    {
+        self.window_and_cx
             .window_handle()
             .update(&mut self.window_and_cx, |_, window, cx| {
-                let Some(view) = self.weak.upgrade() else {
-                    return;
-                };
-                _ = view.update(cx, |view, cx| {
-                    T::update(view, window, cx, msg);
+                let view = self.weak.upgrade().ok_or(SendError::ViewDropped)?;
+                view.update(cx, |view, cx| {
+                    for msg in msgs {
+                        T::update(view, window, cx, msg);
+                    }
                 });
-            });
+                Ok(())
+            })
+            .map_err(|_| SendError::WindowClosed)?
     }
 }
 impl<T> Clone for MsgSender<T> {
@@ -0,0 +1,270 @@
+//! Post-processing that is applied to the pixels of rendered pages.
+//!
+//! Each kind of post-processing is a [`PixelFilter`] and a [`FilterChain`] applies several of them
+//! in order. Embedders can implement their own filters and add them to the chain.
+
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+
+/// Compare values behind a `dyn` reference, implemented for every type that is [`PartialEq`].
+pub trait DynEq: Any {
+    /// `true` if `other` has the same type as `self` and is equal to it.
+    fn dyn_eq(&self, other: &dyn Any) -> bool;
+}
+impl<T: PartialEq + Any> DynEq for T {
+    fn dyn_eq(&self, other: &dyn Any) -> bool {
+        other.downcast_ref::<T>().is_some_and(|other| self == other)
+    }
+}
+
+/// Modifies the pixels of a rendered page.
+///
+/// Filters must be [`PartialEq`] so that the page cache only re-renders when the filters actually
+/// change.
+pub trait PixelFilter: DynEq + fmt::Debug + Send + Sync {
+    /// Short name that can be shown in the UI.
+    fn name(&self) -> &str;
+    /// Modify a non-premultiplied `RGBA` pixel in place. This is called in the same loop that
+    /// converts the pixels for GPUI, so it should be cheap.
+    fn apply(&self, pixel: &mut [u8; 4]);
+}
+
+/// An ordered list of filters, the first filter is applied first.
+///
+/// Chains are equal if they have equal filters in the same order.
+#[derive(Clone, Debug, Default)]
+pub struct FilterChain {
+    filters: Vec<Arc<dyn PixelFilter>>,
+}
+impl FilterChain {
+    pub fn new(filters: Vec<Arc<dyn PixelFilter>>) -> Self {
+        Self { filters }
+    }
+    pub fn filters(&self) -> &[Arc<dyn PixelFilter>] {
+        &self.filters
+    }
+    pub fn push(&mut self, filter: Arc<dyn PixelFilter>) {
+        self.filters.push(filter);
+    }
+    /// `true` if [`Self::apply`] wouldn't change any pixels.
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+    /// Apply every filter in order to a single `RGBA` pixel.
+    pub fn apply(&self, pixel: &mut [u8; 4]) {
+        for filter in &self.filters {
+            filter.apply(pixel);
+        }
+    }
+}
+impl PartialEq for FilterChain {
+    fn eq(&self, other: &Self) -> bool {
+        self.filters.len() == other.filters.len()
+            && self
+                .filters
+                .iter()
+                .zip(&other.filters)
+                .all(|(a, b)| (**a).dyn_eq(&**b as &dyn Any))
+    }
+}
+
+/// The built-in filters, used by the settings to store the order they are applied in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterKind {
+    HighContrast,
+    InvertColors,
+    ColorFilter,
+}
+impl FilterKind {
+    /// Every kind in the default order.
+    pub const ALL: [FilterKind; 3] = [
+        FilterKind::HighContrast,
+        FilterKind::InvertColors,
+        FilterKind::ColorFilter,
+    ];
+    pub fn label(self) -> &'static str {
+        match self {
+            FilterKind::HighContrast => "High contrast",
+            FilterKind::InvertColors => "Invert colors",
+            FilterKind::ColorFilter => "Tint",
+        }
+    }
+}
+
+/// Colors used by [`HighContrast::limited_palette`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HighContrastPalette {
+    #[default]
+    BlackOnWhite,
+    WhiteOnBlack,
+    YellowOnBlack,
+}
+impl HighContrastPalette {
+    /// Foreground (text) and background colors as RGB.
+    fn colors(self) -> ([u8; 3], [u8; 3]) {
+        match self {
+            HighContrastPalette::BlackOnWhite => ([0, 0, 0], [255, 255, 255]),
+            HighContrastPalette::WhiteOnBlack => ([255, 255, 255], [0, 0, 0]),
+            HighContrastPalette::YellowOnBlack => ([255, 255, 0], [0, 0, 0]),
+        }
+    }
+}
+
+/// An accessibility mode for low-vision users that boosts contrast and can reduce the page to two
+/// colors. [`Self::enabled`] is used by the settings to decide if the filter should be added to
+/// the [`FilterChain`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HighContrast {
+    pub enabled: bool,
+    /// Contrast multiplier around mid gray, `1` keeps the original contrast.
+    pub contrast: f32,
+    /// Map every pixel to either the foreground or the background color of [`Self::palette`].
+    pub limited_palette: bool,
+    /// Pixels with a luminance in `0..=1` below this use the foreground color of the palette.
+    pub threshold: f32,
+    pub palette: HighContrastPalette,
+}
+impl Default for HighContrast {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            contrast: 1.8,
+            limited_palette: false,
+            threshold: 0.6,
+            palette: HighContrastPalette::default(),
+        }
+    }
+}
+impl PixelFilter for HighContrast {
+    fn name(&self) -> &str {
+        "High contrast"
+    }
+    fn apply(&self, pixel: &mut [u8; 4]) {
+        let stretch =
+            |value: f32| ((value / 255. - 0.5) * self.contrast + 0.5).clamp(0., 1.) * 255.;
+        let [r, g, b] = [pixel[0], pixel[1], pixel[2]].map(|c| stretch(f32::from(c)));
+        if self.limited_palette {
+            let (foreground, background) = self.palette.colors();
+            let luminance = (0.2126 * r + 0.7152 * g + 0.0722 * b) / 255.;
+            let color = if luminance < self.threshold {
+                foreground
+            } else {
+                background
+            };
+            pixel[..3].copy_from_slice(&color);
+        } else {
+            pixel[0] = r.round() as u8;
+            pixel[1] = g.round() as u8;
+            pixel[2] = b.round() as u8;
+        }
+    }
+}
+
+/// How [`InvertColors`] changes the colors of a page.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InvertMode {
+    /// Invert every channel, colors turn into their complementary colors.
+    Simple,
+    /// Invert the lightness but keep hue and saturation so that images and colored text still look
+    /// natural.
+    #[default]
+    KeepHue,
+}
+
+/// Dark mode for reading at night, turns white pages black. [`Self::enabled`] is used by the
+/// settings to decide if the filter should be added to the [`FilterChain`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InvertColors {
+    pub enabled: bool,
+    /// Also invert while the system uses a dark theme.
+    pub follow_system: bool,
+    pub mode: InvertMode,
+}
+impl PixelFilter for InvertColors {
+    fn name(&self) -> &str {
+        "Invert colors"
+    }
+    fn apply(&self, pixel: &mut [u8; 4]) {
+        match self.mode {
+            InvertMode::Simple => {
+                for channel in &mut pixel[..3] {
+                    *channel = 255 - *channel;
+                }
+            }
+            InvertMode::KeepHue => {
+                // Shifting every channel by the same amount keeps hue and chroma, and moving
+                // `max + min` to `510 - (max + min)` inverts the lightness:
+                let max = pixel[..3].iter().copied().max().unwrap_or(0);
+                let min = pixel[..3].iter().copied().min().unwrap_or(0);
+                let shift = 255 - i16::from(max) - i16::from(min);
+                for channel in &mut pixel[..3] {
+                    *channel = (i16::from(*channel) + shift) as u8;
+                }
+            }
+        }
+    }
+}
+
+/// Preset tints for reading comfort.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorFilter {
+    /// Keep the original colors.
+    #[default]
+    None,
+    /// Warm brown tones like old paper.
+    Sepia,
+    /// Reduce blue light, for reading late in the evening.
+    NightLight,
+    Grayscale,
+}
+impl ColorFilter {
+    pub const ALL: [ColorFilter; 4] = [
+        ColorFilter::None,
+        ColorFilter::Sepia,
+        ColorFilter::NightLight,
+        ColorFilter::Grayscale,
+    ];
+    pub fn label(self) -> &'static str {
+        match self {
+            ColorFilter::None => "No tint",
+            ColorFilter::Sepia => "Sepia",
+            ColorFilter::NightLight => "Night light",
+            ColorFilter::Grayscale => "Grayscale",
+        }
+    }
+}
+impl PixelFilter for ColorFilter {
+    fn name(&self) -> &str {
+        self.label()
+    }
+    fn apply(&self, pixel: &mut [u8; 4]) {
+        // Rows are the weights of the input red, green and blue for each output channel:
+        let matrix = match self {
+            ColorFilter::None => return,
+            ColorFilter::Sepia => [
+                [0.393, 0.769, 0.189],
+                [0.349, 0.686, 0.168],
+                [0.272, 0.534, 0.131],
+            ],
+            ColorFilter::NightLight => [[1., 0., 0.], [0., 0.88, 0.], [0., 0., 0.62]],
+            ColorFilter::Grayscale => [
+                [0.2126, 0.7152, 0.0722],
+                [0.2126, 0.7152, 0.0722],
+                [0.2126, 0.7152, 0.0722],
+            ],
+        };
+        let input = [pixel[0], pixel[1], pixel[2]].map(f32::from);
+        for (channel, weights) in pixel.iter_mut().zip(&matrix) {
+            let value = weights.iter().zip(&input).map(|(w, c)| w * c).sum::<f32>();
+            *channel = value.round().min(255.) as u8;
+        }
+    }
+}
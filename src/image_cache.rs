@@ -0,0 +1,117 @@
+//! Strategies for keeping rasterized PDF pages (and their GPU textures) alive.
+//!
+//! Pages are shown using [`gpui::ImageSource::Custom`] which never consults a
+//! [`gpui::ImageCache`], so the bounded strategy is implemented by the PDF page cache itself using
+//! [`RetainedImages`].
+
+use gpui::{App, ImageCacheError, RenderImage, Resource, Window};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// Set this environment variable to enable [`NoGpuiImageCache::diagnostics`] by default.
+const IMAGE_DIAGNOSTICS_ENV: &str = "PDF_READER_IMAGE_CACHE_DIAGNOSTICS";
+
+/// An image cache that never caches anything, used to bypass GPUI's inbuilt image cache.
+pub struct NoGpuiImageCache {
+    /// Log every page image that is handed to GPUI and every page texture that is dropped from its
+    /// atlas.
+    pub diagnostics: bool,
+}
+impl NoGpuiImageCache {
+    pub fn new(diagnostics: bool) -> Self {
+        Self { diagnostics }
+    }
+}
+impl Default for NoGpuiImageCache {
+    /// Diagnostics are enabled if the [`IMAGE_DIAGNOSTICS_ENV`] environment variable is set.
+    fn default() -> Self {
+        Self::new(std::env::var_os(IMAGE_DIAGNOSTICS_ENV).is_some())
+    }
+}
+impl gpui::ImageCache for NoGpuiImageCache {
+    fn load(
+        &mut self,
+        _resource: &Resource,
+        _window: &mut Window,
+        _cx: &mut App,
+    ) -> Option<Result<Arc<RenderImage>, ImageCacheError>> {
+        None
+    }
+}
+
+/// How page images are handled once their page leaves the range that the PDF page cache keeps.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum ImageCacheStrategy {
+    /// Free page images immediately which also frees their GPU textures.
+    #[default]
+    Bypass,
+    /// Keep up to `capacity` recently evicted page images so that scrolling back to them doesn't
+    /// require rasterizing and uploading them again.
+    Bounded { capacity: usize },
+}
+impl ImageCacheStrategy {
+    pub const DEFAULT_CAPACITY: usize = 8;
+
+    /// Number of evicted page images that should be retained.
+    pub fn capacity(self) -> usize {
+        match self {
+            Self::Bypass => 0,
+            Self::Bounded { capacity } => capacity,
+        }
+    }
+}
+
+/// A small least-recently-used store of page images keyed by page index.
+pub struct RetainedImages {
+    capacity: usize,
+    /// Least recently used image is first.
+    entries: VecDeque<(usize, Arc<RenderImage>)>,
+}
+impl RetainedImages {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.entries
+            .drain(..self.entries.len().saturating_sub(capacity));
+    }
+    /// Keep an evicted image, forgetting the least recently used image if we are at capacity.
+    pub fn insert(&mut self, index: usize, image: Arc<RenderImage>) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.entries.retain(|(other, _)| *other != index);
+        if self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((index, image));
+    }
+    /// Forget the least recently used image and return it together with its page index.
+    pub fn pop_least_recent(&mut self) -> Option<(usize, Arc<RenderImage>)> {
+        self.entries.pop_front()
+    }
+    /// Every retained image, least recently used first.
+    pub fn images(&self) -> impl Iterator<Item = &Arc<RenderImage>> {
+        self.entries.iter().map(|(_, image)| image)
+    }
+    /// Take back a previously evicted image.
+    pub fn take(&mut self, index: usize) -> Option<Arc<RenderImage>> {
+        let position = self.entries.iter().position(|(other, _)| *other == index)?;
+        self.entries.remove(position).map(|(_, image)| image)
+    }
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -1,30 +1,80 @@
 pub mod assets;
+pub mod attachments;
+pub mod compare;
+pub mod config;
 pub mod elm;
+pub mod filters;
+pub mod image_cache;
+pub mod links;
+pub mod logging;
+pub mod measure;
+pub mod outline;
 pub mod pdf;
+pub mod preferences;
+pub mod progress;
 pub mod prompt;
+pub mod recent;
+pub mod search;
+pub mod session;
+pub mod settings;
+pub mod tables;
 pub mod tabs;
+pub mod watch;
 
 use crate::assets::Assets;
+use crate::attachments::Attachment;
 use crate::elm::{MsgSender, Update};
-use crate::prompt::{NoDisplayHandle, prompt_load_pdf_file};
-use crate::tabs::TabsView;
+use crate::filters::{ColorFilter, FilterChain};
+use crate::image_cache::{ImageCacheStrategy, NoGpuiImageCache, RetainedImages};
+use crate::links::{LinkAction, LinkOpening};
+use crate::measure::{MeasureUnit, Measurement};
+use crate::outline::OutlineItem;
+use crate::pdf::{LoadError, PageRotation};
+use crate::preferences::PreferencesView;
+use crate::progress::{CancellationToken, Cancelled, Progress, for_each_page};
+use crate::prompt::{
+    NoDisplayHandle, print_file, prompt_load_pdf_file, prompt_load_pdf_files, prompt_pick_folder,
+    prompt_save_file, prompt_save_text_file,
+};
+use crate::recent::RecentFiles;
+use crate::search::{SearchHit, TextIndex};
+use crate::session::{Session, SessionTab};
+use crate::settings::{CanvasColor, LastTabClosed, PerformanceSettings, Settings};
+use crate::tabs::{SmoothScrollSettings, SmoothScrollState, TabsView};
+use crate::watch::FileWatcher;
+use gpui::prelude::FluentBuilder;
 use gpui::{
-    App, AppContext, Application, AsyncWindowContext, Context, Entity, FocusHandle,
-    ImageCacheError, ImageSource, InteractiveElement, IntoElement, KeyBinding, ObjectFit,
-    ParentElement, Pixels, Render, RenderImage, Resource, ScrollHandle, SharedString, Size, Styled,
-    StyledImage, Task, WeakEntity, Window, WindowOptions, div, img, px, size,
+    AnyElement, App, AppContext, Application, AsyncWindowContext, Bounds, ClipboardItem, Context,
+    Corner, DispatchPhase, Div, Entity, ExternalPaths, FocusHandle, Focusable, FutureExt,
+    ImageSource, InteractiveElement, IntoElement, KeyBinding, KeyContext, MouseButton,
+    MouseDownEvent, MouseMoveEvent, MouseUpEvent, ObjectFit, ParentElement, PathBuilder, Pixels,
+    Point, PromptLevel, Render, RenderImage, ScrollDelta, ScrollHandle, ScrollWheelEvent,
+    SharedString, Size, StatefulInteractiveElement, Styled, StyledImage, Subscription, Task,
+    Timeout, WeakEntity, Window, WindowBounds, WindowHandle, WindowOptions, anchored, canvas,
+    deferred, div, img, point, px, size,
 };
 use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::indicator::Indicator;
+use gpui_component::input::{self, InputEvent, InputState, TextInput};
+use gpui_component::notification::Notification;
 use gpui_component::scroll::{Scrollbar, ScrollbarAxis, ScrollbarState};
-use gpui_component::{Root, StyledExt, VirtualListScrollHandle, v_flex, v_virtual_list};
+use gpui_component::{
+    ActiveTheme, ContextModal, Disableable, Icon, IconName, Root, Sizable, StyledExt, Theme,
+    VirtualListScrollHandle, h_flex, v_flex, v_virtual_list,
+};
 use hayro::{InterpreterSettings, Pdf, RenderSettings};
-use std::collections::HashSet;
+use hayro_syntax::object::dict::keys;
+use hayro_syntax::page::Page;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::hash::{Hash, Hasher};
-use std::ops::Range;
-use std::path::PathBuf;
+use std::num::NonZeroUsize;
+use std::ops::{Range, RangeInclusive};
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::rc::Rc;
-use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::sync::{Arc, Condvar, Mutex, Weak};
 use std::task::{Poll, Waker};
 use std::time::Duration;
 
@@ -51,6 +101,20 @@ impl Default for RenderSettings2 {
         RenderSettings::default().into()
     }
 }
+impl RenderSettings2 {
+    /// Settings that render a page exactly `width` pixels wide no matter its size in PDF units.
+    /// This decouples the resolution from the window size, for example for thumbnails or exports.
+    pub fn with_fixed_width(page: &Page, width: u16) -> Self {
+        let (page_width, page_height) = page.render_dimensions();
+        let scale = f32::from(width) / page_width.max(1.);
+        Self {
+            x_scale: scale,
+            y_scale: scale,
+            width: Some(width),
+            height: Some((page_height * scale).floor().clamp(1., f32::from(u16::MAX)) as u16),
+        }
+    }
+}
 impl From<RenderSettings> for RenderSettings2 {
     fn from(value: RenderSettings) -> RenderSettings2 {
         <RenderSettings2 as From<&'_ RenderSettings>>::from(&value)
@@ -77,11 +141,36 @@ impl From<RenderSettings2> for RenderSettings {
     }
 }
 
-/// `true` if both ranges overlap or share an edge.
+/// `true` if both ranges overlap or share an edge, so that their union doesn't cover any index that
+/// is outside of both. An empty range is contiguous with every range since it adds nothing to the
+/// union (see [`range_union`]), wherever it starts.
+///
+/// ```
+/// use pdf_reader_gpui::range_is_contiguous;
+///
+/// assert!(range_is_contiguous(0..3, 2..5)); // overlapping
+/// assert!(range_is_contiguous(0..3, 3..5)); // adjacent
+/// assert!(range_is_contiguous(2..4, 0..10)); // nested
+/// assert!(!range_is_contiguous(0..3, 4..5)); // gap at index 3
+/// assert!(range_is_contiguous(0..0, 5..5));
+/// assert!(range_is_contiguous(8..8, 0..3));
+/// ```
 pub fn range_is_contiguous(a: Range<usize>, b: Range<usize>) -> bool {
     range_union(a.clone(), b.clone()).len() <= a.len() + b.len()
 }
-/// Get the smallest range that contains both `a` and `b`.
+/// Get the smallest range that contains both `a` and `b`. Empty ranges are ignored, so the union
+/// of two empty ranges is `0..0`.
+///
+/// ```
+/// use pdf_reader_gpui::range_union;
+///
+/// assert_eq!(range_union(0..3, 2..5), 0..5);
+/// assert_eq!(range_union(0..3, 3..5), 0..5);
+/// assert_eq!(range_union(6..8, 0..2), 0..8); // also covers the gap
+/// assert_eq!(range_union(2..4, 7..7), 2..4);
+/// assert_eq!(range_union(7..7, 2..4), 2..4);
+/// assert_eq!(range_union(3..3, 5..5), 0..0);
+/// ```
 pub fn range_union(a: Range<usize>, b: Range<usize>) -> Range<usize> {
     match (a.len(), b.len()) {
         (0, 0) => 0..0,
@@ -90,16 +179,138 @@ pub fn range_union(a: Range<usize>, b: Range<usize>) -> Range<usize> {
         _ => a.start.min(b.start)..a.end.max(b.end),
     }
 }
-/// Get the largest range that is covered by both `a` and `b`.
+/// Get the largest range that is covered by both `a` and `b`. The result is empty if they don't
+/// overlap, it then starts where the later range starts.
+///
+/// ```
+/// use pdf_reader_gpui::range_intersection;
+///
+/// assert_eq!(range_intersection(0..5, 3..8), 3..5);
+/// assert_eq!(range_intersection(2..4, 0..10), 2..4);
+/// assert!(range_intersection(0..3, 3..5).is_empty()); // adjacent
+/// assert_eq!(range_intersection(0..2, 5..7), 5..5);
+/// assert!(range_intersection(4..4, 0..10).is_empty());
+/// ```
 pub fn range_intersection(a: Range<usize>, b: Range<usize>) -> Range<usize> {
     let start = a.start.max(b.start);
     start..a.end.min(b.end).max(start)
 }
+/// Add the pages that were requested by a list to those that it requested earlier in the same
+/// frame. A range that isn't [contiguous](range_is_contiguous) with the earlier ones replaces them,
+/// the earlier pages were likely requested before the list's layout determined that they aren't
+/// visible.
+///
+/// ```
+/// use pdf_reader_gpui::merge_frame_request;
+///
+/// assert_eq!(merge_frame_request(0..0, 4..6), 4..6); // first request this frame
+/// assert_eq!(merge_frame_request(4..6, 6..7), 4..7);
+/// assert_eq!(merge_frame_request(4..6, 5..6), 4..6);
+/// assert_eq!(merge_frame_request(0..1, 40..43), 40..43); // stale layout
+/// assert_eq!(merge_frame_request(40..43, 3..3), 40..43);
+/// ```
+pub fn merge_frame_request(this_frame: Range<usize>, requested: Range<usize>) -> Range<usize> {
+    if this_frame.is_empty() || !range_is_contiguous(this_frame.clone(), requested.clone()) {
+        requested
+    } else {
+        range_union(this_frame, requested)
+    }
+}
+/// Index of the item that covers the most of the span from `visible_top` to `visible_bottom`
+/// when items with the given `heights` are stacked from zero. The first item wins ties.
+pub fn most_visible_item(
+    heights: impl IntoIterator<Item = f32>,
+    visible_top: f32,
+    visible_bottom: f32,
+) -> Option<usize> {
+    let mut best: Option<(usize, f32)> = None;
+    let mut top = 0.;
+    for (index, height) in heights.into_iter().enumerate() {
+        if top >= visible_bottom {
+            break;
+        }
+        let bottom = top + height;
+        let covered = bottom.min(visible_bottom) - top.max(visible_top);
+        if covered > 0. && best.is_none_or(|(_, most)| covered > most) {
+            best = Some((index, covered));
+        }
+        top = bottom;
+    }
+    best.map(|(index, _)| index)
+}
 
 pub struct PdfTabData {
     path: Arc<PathBuf>,
     pdf_data: Arc<Vec<u8>>,
-    scroll: Arc<Mutex<VirtualListScrollHandle>>,
+    /// [`Self::pdf_data`] parsed once when it was loaded, `None` while it is parsed in the
+    /// background since that takes a while for large files.
+    pdf: Option<Result<Arc<Pdf>, LoadError>>,
+    scroll: Arc<Mutex<ScrollPosition>>,
+    rotation: Arc<Mutex<DocumentRotation>>,
+    zoom: Arc<Mutex<TabZoom>>,
+    /// Overrides [`Settings::invert_colors`] for this document.
+    invert_colors: Option<bool>,
+}
+
+impl PdfTabData {
+    /// Use [`PdfReader::new_tab_data`] which also parses the data.
+    fn new(path: PathBuf, pdf_data: Arc<Vec<u8>>, zoom: TabZoom) -> Self {
+        Self {
+            path: Arc::new(path),
+            pdf: None,
+            pdf_data,
+            scroll: Default::default(),
+            rotation: Default::default(),
+            zoom: Arc::new(Mutex::new(zoom)),
+            invert_colors: None,
+        }
+    }
+    /// Replace the document with a new version of the file, the view settings are kept.
+    fn reload(&mut self, pdf_data: Arc<Vec<u8>>, pdf: Arc<Pdf>) {
+        self.pdf_data = pdf_data;
+        self.pdf = Some(Ok(pdf));
+    }
+}
+
+/// How a tab's pages are scaled, restored when switching back to the tab.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TabZoom {
+    pub fit_mode: FitMode,
+    /// See [`PdfPages::zoom`].
+    pub zoom: f32,
+}
+impl Default for TabZoom {
+    fn default() -> Self {
+        Self {
+            fit_mode: FitMode::Width,
+            zoom: 1.,
+        }
+    }
+}
+
+/// Where a document is scrolled to, relative to its pages so that it stays correct when the pages
+/// are laid out at a different size.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ScrollPosition {
+    /// The page at the top of the viewport.
+    pub page_index: usize,
+    /// How far down the page the viewport's top is, `0` is the page's top edge and `1` its bottom.
+    pub offset: f32,
+}
+
+/// How the pages of a document are turned, kept with the tab so that it survives tab switches.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DocumentRotation {
+    /// Applied to every page.
+    pub all_pages: PageRotation,
+    /// Extra rotation of individual pages on top of [`Self::all_pages`].
+    pub pages: HashMap<usize, PageRotation>,
+}
+impl DocumentRotation {
+    pub fn page(&self, index: usize) -> PageRotation {
+        self.all_pages
+            .then(self.pages.get(&index).copied().unwrap_or_default())
+    }
 }
 impl tabs::TabData for PdfTabData {
     fn label(&self) -> SharedString {
@@ -115,18 +326,6 @@ impl tabs::TabData for PdfTabData {
     }
 }
 
-pub struct NoGpuiImageCache;
-impl gpui::ImageCache for NoGpuiImageCache {
-    fn load(
-        &mut self,
-        _resource: &Resource,
-        _window: &mut Window,
-        _cx: &mut App,
-    ) -> Option<Result<Arc<RenderImage>, ImageCacheError>> {
-        None
-    }
-}
-
 struct ArcIdentity<T>(Arc<T>);
 impl<T> Hash for ArcIdentity<T> {
     fn hash<H: Hasher>(&self, state: &mut H) {
@@ -140,13 +339,65 @@ impl<T> PartialEq for ArcIdentity<T> {
 }
 impl<T> Eq for ArcIdentity<T> {}
 
+/// A rendered page or the reason it couldn't be rendered.
+type PageImage = Result<Arc<RenderImage>, pdf::RasterizeError>;
+
+/// What can be shown for a page, see [`PdfPageCache::get_images`].
+#[derive(Clone)]
+pub enum PageSlot {
+    /// No worker has been asked to render the page yet.
+    NotRequested,
+    /// The page is queued or being rendered.
+    Rendering,
+    /// The page's image, or a placeholder/thumbnail with a different resolution until it has been
+    /// rendered with the current settings.
+    Image(Arc<RenderImage>),
+    Failed(pdf::RasterizeError),
+}
+impl PageSlot {
+    pub fn image(&self) -> Option<&Arc<RenderImage>> {
+        match self {
+            PageSlot::Image(image) => Some(image),
+            _ => None,
+        }
+    }
+}
+
 struct PdfPageCacheMutableState {
     /// Currently cached images of PDF pages. Index of an image is the PDF page's index.
-    images: Vec<Option<Arc<RenderImage>>>,
+    images: Vec<Option<PageImage>>,
+    /// Degrees that each page is rotated clockwise to straighten crooked scans.
+    deskew: Vec<f32>,
+    /// Rotation of each page in steps of 90 degrees.
+    rotation: Vec<PageRotation>,
+    /// Recently evicted images, used by [`ImageCacheStrategy::Bounded`].
+    retained: RetainedImages,
+    /// Quickly rendered low resolution images that are shown while the full image is rendered.
+    /// These are kept when zooming since they are only placeholders anyway, but are forgotten
+    /// together with the full image when the page is scrolled far out of view.
+    thumbnails: Vec<Option<PageImage>>,
+    /// Images rendered with previous render settings, shown stretched until the page has been
+    /// rendered again so that zooming doesn't flicker.
+    placeholders: HashMap<usize, Arc<RenderImage>>,
+    /// Rendered images keyed by a hash of their pixels, used to share a single image between
+    /// identical pages.
+    identical_images: HashMap<u64, Weak<RenderImage>>,
+    /// Total number of image bytes that were saved by sharing images between identical pages.
+    deduplicated_bytes: usize,
     /// Settings (zoom) that will be used when rendering images.
     render_settings: RenderSettings2,
+    /// If set then every page is rendered at this pixel width instead of using
+    /// [`Self::render_settings`]'s scale.
+    fixed_width: Option<u16>,
+    /// Multiplies [`Self::render_settings`]'s scale for individual pages, missing entries use `1`.
+    /// Kept when a new PDF is set since it comes from the layout of that PDF.
+    page_scales: Vec<f32>,
+    /// Applied in order to the pixels of every rendered page.
+    filters: FilterChain,
     /// The parsed PDF file that the background thread will rasterize.
     pdf: Option<Arc<Pdf>>,
+    /// Another version of [`Self::pdf`], if set then pages show how they differ from this version.
+    compare_with: Option<Arc<Pdf>>,
     /// Notify/wake the foreground future so that it can request a re-render of the UI with newly
     /// cached images.
     wake_future: Option<Waker>,
@@ -154,35 +405,276 @@ struct PdfPageCacheMutableState {
     requested_pages: Range<usize>,
     /// The background thread has acknowledged that pages in this range will be rendered.
     acknowledged_pages: Range<usize>,
+    /// Number of pages before and after the visible pages that are also rendered.
+    cache_ahead: usize,
+    /// Pages are rendered at this many times their display resolution (unless
+    /// [`Self::fixed_width`] is set).
+    supersampling: f32,
+    /// Pages that a worker thread is currently rendering, other workers skip these.
+    rendering: HashSet<usize>,
+    /// Maximum number of bytes used by cached page images, `None` for no limit.
+    memory_budget: Option<usize>,
+    /// Number of full resolution pages that were rendered since the PDF was set.
+    rendered_pages: usize,
+    /// How many of the worker threads may render at the same time, the rest are parked.
+    active_workers: usize,
     /// If `true` then background worker thread and foreground task will exit.
     should_quit: bool,
 }
 impl PdfPageCacheMutableState {
+    fn new(active_workers: usize) -> Self {
+        Self {
+            images: Vec::with_capacity(256),
+            deskew: Vec::with_capacity(256),
+            rotation: Vec::with_capacity(256),
+            retained: RetainedImages::new(ImageCacheStrategy::default().capacity()),
+            placeholders: HashMap::new(),
+            thumbnails: Vec::with_capacity(256),
+            identical_images: HashMap::new(),
+            deduplicated_bytes: 0,
+            render_settings: RenderSettings2 {
+                x_scale: 1.,
+                y_scale: 1.,
+                ..Default::default()
+            },
+            fixed_width: None,
+            page_scales: Vec::new(),
+            filters: FilterChain::default(),
+            pdf: None,
+            compare_with: None,
+            wake_future: None,
+            requested_pages: 0..0,
+            acknowledged_pages: 0..0,
+            cache_ahead: 1,
+            supersampling: 1.,
+            rendering: HashSet::new(),
+            active_workers,
+            memory_budget: None,
+            rendered_pages: 0,
+            should_quit: false,
+        }
+    }
+
     pub fn set_new_pdf(&mut self, pdf: Option<Arc<Pdf>>, render_settings: RenderSettings2) {
         self.images.clear(); // <- always clear to ensure all items are None.
+        self.deskew.clear();
+        self.rotation.clear();
+        self.retained.clear();
+        self.placeholders.clear();
+        self.thumbnails.clear();
+        self.identical_images.clear();
+        self.deduplicated_bytes = 0;
+        self.rendered_pages = 0;
         if let Some(pdf) = pdf.as_ref() {
             self.images.resize_with(pdf.pages().len(), || None);
+            self.thumbnails.resize_with(pdf.pages().len(), || None);
+            self.deskew.resize(pdf.pages().len(), 0.);
+            self.rotation
+                .resize(pdf.pages().len(), PageRotation::default());
         }
         self.requested_pages = 0..0;
         self.acknowledged_pages = 0..0;
         self.render_settings = render_settings;
         self.pdf = pdf;
+        self.compare_with = None;
+    }
+
+    /// Reuse an already cached image if it has exactly the same pixels as `image`. Documents like
+    /// slide decks often contain identical pages so this can save a lot of memory.
+    fn deduplicate(&mut self, content_hash: u64, image: Arc<RenderImage>) -> Arc<RenderImage> {
+        self.identical_images
+            .retain(|_, existing| existing.strong_count() > 0);
+        if let Some(existing) = self
+            .identical_images
+            .get(&content_hash)
+            .and_then(Weak::upgrade)
+            && existing.size(0) == image.size(0)
+            && existing.as_bytes(0) == image.as_bytes(0)
+        {
+            self.deduplicated_bytes += image.as_bytes(0).map_or(0, <[u8]>::len);
+            log::debug!(
+                "Reusing image of identical page, saved {} bytes in total",
+                self.deduplicated_bytes
+            );
+            return existing;
+        }
+        self.identical_images
+            .insert(content_hash, Arc::downgrade(&image));
+        image
+    }
+
+    /// Bytes used by the full resolution images, placeholders and retained images of every page.
+    /// Images that are shared between identical pages are only counted once.
+    fn cached_bytes(&self) -> usize {
+        let images = self
+            .images
+            .iter()
+            .filter_map(|image| image.as_ref()?.as_ref().ok());
+        let mut counted = HashSet::new();
+        images
+            .chain(self.placeholders.values())
+            .chain(self.retained.images())
+            .filter(|image| counted.insert(Arc::as_ptr(image)))
+            .map(image_bytes)
+            .sum()
+    }
+    /// Guess the size of a page's image before it is rendered.
+    fn estimated_bytes(&self, index: usize) -> usize {
+        let Some(page) = self.pdf.as_ref().and_then(|pdf| pdf.pages().get(index)) else {
+            return 0;
+        };
+        let (width, height) = match self.fixed_width {
+            Some(width) => {
+                let settings = RenderSettings2::with_fixed_width(page, width);
+                (f32::from(width), f32::from(settings.height.unwrap_or(1)))
+            }
+            None => {
+                let (width, height) = pdf::page_dimensions(page);
+                let scale = self.page_scales.get(index).copied().unwrap_or(1.) * self.supersampling;
+                (
+                    width * self.render_settings.x_scale * scale,
+                    height * self.render_settings.y_scale * scale,
+                )
+            }
+        };
+        width.ceil() as usize * height.ceil() as usize * 4
+    }
+    /// Forget cached pages, farthest from `center` first, until an image of `needed` bytes fits in
+    /// the memory budget. Retained images aren't shown so they are all forgotten before any page,
+    /// after that only pages farther from `center` than `index` (the page that is about to be
+    /// rendered) are evicted. Returns `false` if the image still doesn't fit.
+    fn make_room(&mut self, needed: usize, center: usize, index: usize) -> bool {
+        let Some(budget) = self.memory_budget else {
+            return true;
+        };
+        let mut used = self.cached_bytes();
+        while used + needed > budget {
+            if let Some((retained, _)) = self.retained.pop_least_recent() {
+                used = self.cached_bytes();
+                log::debug!(
+                    "Forgot retained image of page {} to stay within the memory budget, {used} bytes cached",
+                    retained + 1
+                );
+                continue;
+            }
+            let farthest = (0..self.images.len())
+                .filter(|&cached| {
+                    !self.rendering.contains(&cached)
+                        && cached.abs_diff(center) > index.abs_diff(center)
+                        && (matches!(self.images[cached], Some(Ok(_)))
+                            || self.placeholders.contains_key(&cached))
+                })
+                .max_by_key(|cached| cached.abs_diff(center));
+            let Some(farthest) = farthest else {
+                return false;
+            };
+            if matches!(self.images[farthest], Some(Ok(_))) {
+                self.images[farthest] = None;
+            }
+            self.placeholders.remove(&farthest);
+            // Recount since the evicted image might still be used by an identical page:
+            used = self.cached_bytes();
+            log::debug!(
+                "Evicted page {} to stay within the memory budget, {used} bytes cached",
+                farthest + 1
+            );
+        }
+        true
     }
 }
+/// How [`PdfPages`] looked before presentation mode was started.
+struct PresentationRestore {
+    view_mode: ViewMode,
+    fit_mode: FitMode,
+    zoom: f32,
+    show_thumbnails: bool,
+    viewport_size: Size<Pixels>,
+}
+
+/// Snapshot of what a [`PdfPageCache`] holds, for diagnostics.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CacheStats {
+    /// Pages with a full resolution image.
+    pub images: usize,
+    /// Bytes used by the full resolution, placeholder and retained images.
+    pub image_bytes: usize,
+    /// Pages shown with an image rendered at a previous zoom level.
+    pub placeholders: usize,
+    /// Recently evicted images that are kept in case they are needed again.
+    pub retained: usize,
+    /// Full resolution pages rendered since the PDF was opened.
+    pub rendered_pages: usize,
+    /// Bytes saved by sharing images between identical pages.
+    pub deduplicated_bytes: usize,
+    /// See [`PdfPageCache::set_memory_budget`].
+    pub memory_budget: Option<usize>,
+    /// Page images uploaded to the GPU since the PDF was opened.
+    pub uploaded_textures: u64,
+    /// Page textures freed since the PDF was opened.
+    pub dropped_textures: u64,
+}
+
+/// Memory used by an image's pixels.
+fn image_bytes(image: &Arc<RenderImage>) -> usize {
+    let size = image.size(0);
+    size.width.0 as usize * size.height.0 as usize * 4
+}
+/// Leave one core for the UI thread (unless there is only one).
+fn default_render_threads(worker_count: usize) -> usize {
+    worker_count.saturating_sub(1).max(1)
+}
+
 struct PdfPageCacheSharedState {
     state: Mutex<PdfPageCacheMutableState>,
     wake_worker: Condvar,
+    /// Maximum number of worker threads, one per CPU core.
+    worker_count: usize,
+    /// Incremented whenever settings that affect every page change, so that workers can abandon
+    /// pages that they are rendering with the old settings.
+    generation: AtomicU64,
 }
+impl PdfPageCacheSharedState {
+    /// Make in-flight renders stop as soon as they can.
+    fn cancel_renders(&self) {
+        self.generation.fetch_add(1, AtomicOrdering::Relaxed);
+    }
+}
+/// Pages that were requested from a [`PdfPageCache`] during the current and the previous frame.
+#[derive(Clone, Debug, Default)]
+struct FrameRequests {
+    /// PDF pages rendered this frame.
+    this_frame: Range<usize>,
+    /// PDF pages rendered previous frame (keep this in cache).
+    last_frame: Range<usize>,
+}
+impl FrameRequests {
+    /// Forget the oldest frame's requests, called when a new frame starts.
+    fn next_frame(&mut self) {
+        self.last_frame = std::mem::take(&mut self.this_frame);
+    }
+    /// Record the pages that a list requested and return all pages that the workers should keep
+    /// rendered.
+    fn request(&mut self, visible_range: Range<usize>) -> Range<usize> {
+        self.this_frame = merge_frame_request(self.this_frame.clone(), visible_range);
+        range_union(self.this_frame.clone(), self.last_frame.clone())
+    }
+}
+
 struct PdfPageCache {
     /// Data shared between background worker thread, frontend async task and [`PdfPages`] view.
     shared: Arc<PdfPageCacheSharedState>,
     /// Dropping this will stop the foreground task.
     _ui_updater: Task<()>,
-    /// PDF pages rendered this frame.
-    pages_this_frame: Range<usize>,
-    /// PDF pages rendered previous frame (keep this in cache).
-    pages_last_frame: Range<usize>,
+    frame_requests: FrameRequests,
     rendered_images: HashSet<ArcIdentity<RenderImage>>,
+    /// Number of images that have been handed to GPUI, each is uploaded to its texture atlas.
+    uploaded_textures: u64,
+    /// Number of images that have been removed from GPUI's texture atlas, useful to compare the
+    /// GPU churn of different [`ImageCacheStrategy`] values.
+    dropped_textures: u64,
+    /// Number of worker threads running [`Self::background_work`], at most
+    /// [`PdfPageCacheSharedState::worker_count`].
+    spawned_workers: usize,
 }
 impl Drop for PdfPageCache {
     fn drop(&mut self) {
@@ -197,41 +689,53 @@ impl Drop for PdfPageCache {
     }
 }
 impl PdfPageCache {
-    pub fn new(window: &mut Window, cx: &mut Context<PdfPages>) -> Self {
+    /// Create a cache that renders up to `render_threads` pages in parallel, see
+    /// [`Self::set_render_threads`].
+    pub fn new(
+        render_threads: Option<usize>,
+        window: &mut Window,
+        cx: &mut Context<PdfPages>,
+    ) -> Self {
+        let worker_count = std::thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        let active_workers = render_threads
+            .unwrap_or_else(|| default_render_threads(worker_count))
+            .clamp(1, worker_count);
         let shared = Arc::new(PdfPageCacheSharedState {
-            state: Mutex::new(PdfPageCacheMutableState {
-                images: Vec::with_capacity(256),
-                render_settings: RenderSettings2 {
-                    x_scale: 1.,
-                    y_scale: 1.,
-                    ..Default::default()
-                },
-                pdf: None,
-                wake_future: None,
-                requested_pages: 0..0,
-                acknowledged_pages: 0..0,
-                should_quit: false,
-            }),
+            state: Mutex::new(PdfPageCacheMutableState::new(active_workers)),
             wake_worker: Condvar::new(),
+            worker_count,
+            generation: AtomicU64::new(0),
         });
-        let this = Self {
+        let mut this = Self {
             shared: shared.clone(),
             _ui_updater: cx.spawn_in(window, {
                 let shared = shared.clone();
                 async move |parent, window| Self::foreground_work(shared, parent, window).await
             }),
-            pages_this_frame: 0..0,
-            pages_last_frame: 0..0,
+            frame_requests: FrameRequests::default(),
             rendered_images: Default::default(),
+            uploaded_textures: 0,
+            dropped_textures: 0,
+            spawned_workers: 0,
         };
-        std::thread::Builder::new()
-            .name("PDF Rasterizer".to_owned())
-            .spawn(move || Self::background_work(shared))
-            .expect("failed to spawn background worker thread");
+        this.spawn_workers(active_workers);
 
         this
     }
 
+    /// Make sure that at least `count` worker threads are running. Workers are only spawned once
+    /// they are needed and then kept until the cache is dropped.
+    fn spawn_workers(&mut self, count: usize) {
+        for worker in self.spawned_workers..count {
+            let shared = self.shared.clone();
+            std::thread::Builder::new()
+                .name(format!("PDF Rasterizer {worker}"))
+                .spawn(move || Self::background_work(shared, worker))
+                .expect("failed to spawn background worker thread");
+        }
+        self.spawned_workers = self.spawned_workers.max(count);
+    }
+
     /// Notify [`PdfPages`] view when new PDF pages have been rendered by the worker thread running
     /// [`Self::background_work`].
     async fn foreground_work(
@@ -241,7 +745,7 @@ impl PdfPageCache {
     ) {
         struct WaitForChange<'a> {
             shared: &'a PdfPageCacheSharedState,
-            rendered_images: &'a mut Vec<bool>,
+            rendered_images: &'a mut Vec<(bool, bool)>,
         }
         impl<'a> Future for WaitForChange<'a> {
             type Output = bool;
@@ -251,12 +755,18 @@ impl PdfPageCache {
                 let mut guard = this.shared.state.lock().unwrap();
 
                 this.rendered_images
-                    .resize_with(guard.images.len(), || false);
+                    .resize_with(guard.images.len(), || (false, false));
 
                 let mut changed_state = false;
-                for (cache, is_cached) in guard.images.iter().zip(this.rendered_images.iter_mut()) {
-                    if cache.is_some() != *is_cached {
-                        *is_cached = cache.is_some();
+                for ((cache, thumbnail), is_cached) in guard
+                    .images
+                    .iter()
+                    .zip(&guard.thumbnails)
+                    .zip(this.rendered_images.iter_mut())
+                {
+                    let now_cached = (cache.is_some(), thumbnail.is_some());
+                    if now_cached != *is_cached {
+                        *is_cached = now_cached;
                         changed_state = true;
                     }
                 }
@@ -270,11 +780,11 @@ impl PdfPageCache {
             }
         }
 
-        // array of bools (true if an image is known to be cached)
+        // array of bools (true if an image and its thumbnail are known to be cached)
         let mut rendered_images = Vec::with_capacity(256);
         loop {
             let should_quit = WaitForChange {
-                shared: &*shared,
+                shared: &shared,
                 rendered_images: &mut rendered_images,
             }
             .await;
@@ -295,33 +805,60 @@ impl PdfPageCache {
 
     /// Executed by dedicated worker thread that will rasterize PDF pages as requested by the
     /// [`Self::get_images`] method.
-    fn background_work(shared: Arc<PdfPageCacheSharedState>) {
+    fn background_work(shared: Arc<PdfPageCacheSharedState>, worker: usize) {
         let mut guard = shared.state.lock().unwrap();
         loop {
+            if worker >= guard.active_workers {
+                // Parked to limit CPU usage:
+                guard = shared
+                    .wake_worker
+                    .wait_while(guard, |state| {
+                        !state.should_quit && worker >= state.active_workers
+                    })
+                    .unwrap();
+                if guard.should_quit {
+                    return;
+                }
+                continue;
+            }
+
             // Check if we need to rasterize another page:
             let mut index_to_render = None;
+            let mut restored_image = false;
             {
-                let mut wanted_pages = guard.requested_pages.clone();
+                let state = &mut *guard;
+                let mut wanted_pages = state.requested_pages.clone();
 
-                // more aggressively cache earlier pages since the virtual list doesn't:
-                wanted_pages.start = wanted_pages.start.saturating_sub(1);
+                // Render pages around the visible ones so that they are ready when scrolling:
+                wanted_pages.start = wanted_pages.start.saturating_sub(state.cache_ahead);
+                wanted_pages.end = (wanted_pages.end + state.cache_ahead).min(state.images.len());
 
                 // Chose the page closest to the center of the requested range:
                 let mut chose_index_distance = usize::MAX;
                 let center = wanted_pages.end.saturating_sub(1 + wanted_pages.len() / 2);
 
-                // We special case caching of the first page since the virtual list always requests it
-                let cache_first_image = guard.requested_pages.start <= 1;
-
-                for (index, image) in guard.images.iter_mut().enumerate() {
-                    let should_cache = if index == 0 {
-                        cache_first_image
-                    } else {
-                        wanted_pages.contains(&index)
-                    };
-                    if !should_cache {
-                        *image = None;
+                for (index, image) in state.images.iter_mut().enumerate() {
+                    if state.rendering.contains(&index) {
+                        continue;
+                    }
+                    if !wanted_pages.contains(&index) {
+                        if let Some(Ok(image)) = image.take() {
+                            state.retained.insert(index, image);
+                        }
+                        state.placeholders.remove(&index);
+                        // The list lays out the first page in every frame to measure it, so keep
+                        // its cheap thumbnail to show then:
+                        if index != 0
+                            && let Some(thumbnail) = state.thumbnails.get_mut(index)
+                        {
+                            *thumbnail = None;
+                        }
                     } else if image.is_none() {
+                        if let Some(retained) = state.retained.take(index) {
+                            *image = Some(Ok(retained));
+                            restored_image = true;
+                            continue;
+                        }
                         let distance = index.abs_diff(center);
                         if distance < chose_index_distance {
                             index_to_render = Some(index);
@@ -329,6 +866,20 @@ impl PdfPageCache {
                         }
                     }
                 }
+
+                if let Some(index) = index_to_render {
+                    let needed = state.estimated_bytes(index);
+                    // Visible pages are always rendered, even if they don't fit:
+                    if !state.make_room(needed, center, index)
+                        && !state.requested_pages.contains(&index)
+                    {
+                        log::debug!(
+                            "Not rendering page {} ahead since the memory budget is used up",
+                            index + 1
+                        );
+                        index_to_render = None;
+                    }
+                }
             }
 
             log::debug!(
@@ -338,29 +889,113 @@ impl PdfPageCache {
             );
             guard.acknowledged_pages = guard.requested_pages.clone();
 
+            if restored_image {
+                log::debug!("Restored retained images, {} left", guard.retained.len());
+                if let Some(waker) = guard.wake_future.take() {
+                    waker.wake();
+                }
+            }
+
             if let Some(index) = index_to_render {
                 // Copy render inputs:
                 let Some(pdf) = guard.pdf.clone() else {
                     continue;
                 };
+                guard.rendering.insert(index);
+                if guard.active_workers > 1 {
+                    // Let idle workers pick other pages to render in parallel:
+                    guard.acknowledged_pages = 0..0;
+                    shared.wake_worker.notify_all();
+                }
                 let render_settings = guard.render_settings;
+                let fixed_width = guard.fixed_width;
+                let page_scale = guard.page_scales.get(index).copied().unwrap_or(1.);
+                let filters = guard.filters.clone();
+                let compare_with = guard.compare_with.clone();
+                let supersampling = guard.supersampling;
+                let deskew = guard.deskew.get(index).copied().unwrap_or(0.);
+                let rotation = guard.rotation.get(index).copied().unwrap_or_default();
+                // Render a quick preview first if there isn't one:
+                let thumbnail = guard.thumbnails.get(index).is_some_and(Option::is_none);
+                let generation = shared.generation.load(AtomicOrdering::Relaxed);
+                let is_cancelled = || shared.generation.load(AtomicOrdering::Relaxed) != generation;
 
                 // render while not holding the lock:
                 drop(guard);
+                let mut page_settings = RenderSettings::from(match fixed_width {
+                    Some(width) => RenderSettings2::with_fixed_width(&pdf.pages()[index], width),
+                    None => render_settings,
+                });
+                if fixed_width.is_none() {
+                    // Don't render gigantic images for malformed page sizes:
+                    let factor =
+                        pdf::page_dimension_scale(&pdf.pages()[index]) * supersampling * page_scale;
+                    page_settings.x_scale *= factor;
+                    page_settings.y_scale *= factor;
+                }
+                if thumbnail {
+                    page_settings.x_scale *= THUMBNAIL_SCALE;
+                    page_settings.y_scale *= THUMBNAIL_SCALE;
+                    let shrink = |size: u16| ((f32::from(size) * THUMBNAIL_SCALE) as u16).max(1);
+                    page_settings.width = page_settings.width.map(shrink);
+                    page_settings.height = page_settings.height.map(shrink);
+                }
                 let new_image = pdf::rasterize_pdf_page(
                     &pdf.pages()[index],
                     &InterpreterSettings::default(),
-                    &RenderSettings::from(render_settings),
+                    &page_settings,
+                    compare_with
+                        .as_ref()
+                        .and_then(|other| other.pages().get(index)),
+                    deskew,
+                    rotation,
+                    &filters,
+                    &is_cancelled,
                 );
+                let content_hash = match &new_image {
+                    Ok(image) if !thumbnail => pdf::image_content_hash(image),
+                    _ => 0,
+                };
 
                 // re-acquire lock and save new image to shared state:
                 guard = shared.state.lock().unwrap();
-                if guard.render_settings == render_settings
+                guard.rendering.remove(&index);
+                let cancelled = matches!(new_image, Err(pdf::RasterizeError::Cancelled));
+                if cancelled {
+                    log::debug!("Cancelled rendering of page {}", index + 1);
+                }
+                let inputs_unchanged = !cancelled
+                    && guard.render_settings == render_settings
+                    && guard.fixed_width == fixed_width
+                    && guard.page_scales.get(index).copied().unwrap_or(1.) == page_scale
+                    && guard.filters == filters
+                    && guard.supersampling == supersampling
+                    && match (&guard.compare_with, &compare_with) {
+                        (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+                        (None, None) => true,
+                        _ => false,
+                    }
                     && guard
                         .pdf
                         .as_ref()
-                        .is_some_and(|new_pdf| Arc::ptr_eq(&pdf, &new_pdf))
-                {
+                        .is_some_and(|new_pdf| Arc::ptr_eq(&pdf, new_pdf))
+                    && guard.deskew.get(index) == Some(&deskew)
+                    && guard.rotation.get(index) == Some(&rotation);
+                if inputs_unchanged && thumbnail {
+                    // Errors are reported when the full image fails to render:
+                    if let Some(slot) = guard.thumbnails.get_mut(index) {
+                        *slot = Some(new_image);
+                        if let Some(waker) = guard.wake_future.take() {
+                            waker.wake();
+                        }
+                    }
+                } else if inputs_unchanged {
+                    if let Err(e) = &new_image {
+                        log::error!("Failed to render page {}: {e}", index + 1);
+                    }
+                    let new_image = new_image.map(|image| guard.deduplicate(content_hash, image));
+                    guard.placeholders.remove(&index);
+                    guard.rendered_pages += 1;
                     if let Some(image) = guard.images.get_mut(index) {
                         *image = Some(new_image);
                         log::debug!(
@@ -395,63 +1030,332 @@ impl PdfPageCache {
     pub fn set_new_pdf(&self, pdf: Option<Arc<Pdf>>, render_settings: RenderSettings2) {
         let mut guard = self.shared.state.lock().unwrap();
         guard.set_new_pdf(pdf, render_settings);
+        self.shared.cancel_renders();
+    }
+    /// Change the scale that pages are rendered at. Pages keep showing their old images until they
+    /// have been rendered with the new settings.
+    pub fn set_render_settings(&self, render_settings: RenderSettings2) {
+        let mut guard = self.shared.state.lock().unwrap();
+        if guard.render_settings == render_settings {
+            return;
+        }
+        guard.render_settings = render_settings;
+        self.shared.cancel_renders();
+        let state = &mut *guard;
+        for (index, image) in state.images.iter_mut().enumerate() {
+            if let Some(Ok(image)) = image.take() {
+                state.placeholders.insert(index, image);
+            }
+        }
+        state.retained.clear();
+        // Force the worker to re-check which pages need to be rendered:
+        state.acknowledged_pages = 0..0;
+        drop(guard);
+        self.shared.wake_worker.notify_all();
+    }
+    /// Scale individual pages relative to the render settings, missing entries use `1`. Changed
+    /// pages keep showing their old images until they have been rendered again.
+    pub fn set_page_scales(&self, page_scales: Vec<f32>) {
+        let mut guard = self.shared.state.lock().unwrap();
+        if guard.page_scales == page_scales {
+            return;
+        }
+        self.shared.cancel_renders();
+        let state = &mut *guard;
+        for (index, image) in state.images.iter_mut().enumerate() {
+            let old_scale = state.page_scales.get(index).copied().unwrap_or(1.);
+            let new_scale = page_scales.get(index).copied().unwrap_or(1.);
+            if old_scale != new_scale
+                && let Some(Ok(image)) = image.take()
+            {
+                state.placeholders.insert(index, image);
+            }
+        }
+        state.page_scales = page_scales;
+        state.retained.clear();
+        // Force the worker to re-check which pages need to be rendered:
+        state.acknowledged_pages = 0..0;
+        drop(guard);
+        self.shared.wake_worker.notify_all();
+    }
+    /// Render every page at a fixed pixel width or `None` to follow the render settings.
+    pub fn set_fixed_width(&self, width: Option<u16>) {
+        let mut guard = self.shared.state.lock().unwrap();
+        if guard.fixed_width == width {
+            return;
+        }
+        guard.fixed_width = width;
+        self.shared.cancel_renders();
+        guard.images.iter_mut().for_each(|image| *image = None);
+        guard.retained.clear();
+        // Force the worker to re-check which pages need to be rendered:
+        guard.acknowledged_pages = 0..0;
+        drop(guard);
+        self.shared.wake_worker.notify_all();
+    }
+    /// Change the filters applied to rendered pages, already rendered pages are re-rendered.
+    pub fn set_filters(&self, filters: FilterChain) {
+        let mut guard = self.shared.state.lock().unwrap();
+        if guard.filters == filters {
+            return;
+        }
+        guard.filters = filters;
+        self.shared.cancel_renders();
+        guard.images.iter_mut().for_each(|image| *image = None);
+        guard.retained.clear();
+        // Force the worker to re-check which pages need to be rendered:
+        guard.acknowledged_pages = 0..0;
+        drop(guard);
+        self.shared.wake_worker.notify_all();
+    }
+    /// Limit how many pages are rendered in parallel, `None` uses all CPU cores except one.
+    pub fn set_render_threads(&mut self, threads: Option<usize>) {
+        let worker_count = self.shared.worker_count;
+        let threads = threads
+            .unwrap_or_else(|| default_render_threads(worker_count))
+            .clamp(1, worker_count);
+        let mut guard = self.shared.state.lock().unwrap();
+        if guard.active_workers == threads {
+            return;
+        }
+        guard.active_workers = threads;
+        drop(guard);
+        self.spawn_workers(threads);
+        self.shared.wake_worker.notify_all();
+    }
+    /// Limit the memory used by cached page images, pages far from the visible ones are forgotten
+    /// first. `None` removes the limit.
+    pub fn set_memory_budget(&self, bytes: Option<usize>) {
+        let mut guard = self.shared.state.lock().unwrap();
+        if guard.memory_budget == bytes {
+            return;
+        }
+        guard.memory_budget = bytes;
+        // Force the worker to re-check which pages need to be rendered:
+        guard.acknowledged_pages = 0..0;
+        drop(guard);
+        self.shared.wake_worker.notify_all();
+    }
+    /// Also render this many pages before and after the visible pages.
+    pub fn set_cache_ahead(&self, pages: usize) {
+        let mut guard = self.shared.state.lock().unwrap();
+        if guard.cache_ahead == pages {
+            return;
+        }
+        guard.cache_ahead = pages;
+        // Force the worker to re-check which pages need to be rendered:
+        guard.acknowledged_pages = 0..0;
+        drop(guard);
+        self.shared.wake_worker.notify_all();
+    }
+    /// Render pages at a multiple of their display resolution.
+    pub fn set_supersampling(&self, factor: f32) {
+        let mut guard = self.shared.state.lock().unwrap();
+        if guard.supersampling == factor {
+            return;
+        }
+        guard.supersampling = factor;
+        self.shared.cancel_renders();
+        guard.images.iter_mut().for_each(|image| *image = None);
+        guard.retained.clear();
+        // Force the worker to re-check which pages need to be rendered:
+        guard.acknowledged_pages = 0..0;
+        drop(guard);
+        self.shared.wake_worker.notify_all();
+    }
+    /// Show how each page differs from the same page in another version of the document, or `None`
+    /// to show the pages normally.
+    pub fn set_compare_with(&self, other: Option<Arc<Pdf>>) {
+        let mut guard = self.shared.state.lock().unwrap();
+        guard.compare_with = other;
+        self.shared.cancel_renders();
+        guard.images.iter_mut().for_each(|image| *image = None);
+        guard.retained.clear();
+        // Force the worker to re-check which pages need to be rendered:
+        guard.acknowledged_pages = 0..0;
+        drop(guard);
+        self.shared.wake_worker.notify_all();
+    }
+    pub fn stats(&self) -> CacheStats {
+        let guard = self.shared.state.lock().unwrap();
+        CacheStats {
+            images: guard
+                .images
+                .iter()
+                .filter(|image| matches!(image, Some(Ok(_))))
+                .count(),
+            image_bytes: guard.cached_bytes(),
+            placeholders: guard.placeholders.len(),
+            retained: guard.retained.len(),
+            rendered_pages: guard.rendered_pages,
+            deduplicated_bytes: guard.deduplicated_bytes,
+            memory_budget: guard.memory_budget,
+            uploaded_textures: self.uploaded_textures,
+            dropped_textures: self.dropped_textures,
+        }
+    }
+    /// The PDF whose pages are being cached.
+    pub fn pdf(&self) -> Option<Arc<Pdf>> {
+        self.shared.state.lock().unwrap().pdf.clone()
+    }
+    /// Pages that were requested during the latest frame.
+    pub fn visible_pages(&self) -> Range<usize> {
+        self.frame_requests.this_frame.clone()
+    }
+
+    pub fn page_deskew(&self, index: usize) -> f32 {
+        let guard = self.shared.state.lock().unwrap();
+        guard.deskew.get(index).copied().unwrap_or(0.)
+    }
+    /// Rotate a page clockwise by some degrees to straighten a crooked scan.
+    pub fn set_page_deskew(&self, index: usize, degrees: f32) {
+        let mut guard = self.shared.state.lock().unwrap();
+        let Some(deskew) = guard.deskew.get_mut(index) else {
+            return;
+        };
+        *deskew = degrees;
+        guard.images[index] = None;
+        guard.retained.take(index);
+        // Force the worker to re-check which pages need to be rendered:
+        guard.acknowledged_pages = 0..0;
+        drop(guard);
+        self.shared.wake_worker.notify_all();
+    }
+
+    /// Turn pages in steps of 90 degrees, only pages whose rotation changed are rendered again.
+    pub fn set_page_rotations(&self, rotation: impl Fn(usize) -> PageRotation) {
+        let mut guard = self.shared.state.lock().unwrap();
+        let state = &mut *guard;
+        let mut changed = false;
+        for (index, current) in state.rotation.iter_mut().enumerate() {
+            let new = rotation(index);
+            if *current == new {
+                continue;
+            }
+            *current = new;
+            changed = true;
+            // The old images have the wrong orientation so they can't be used as placeholders:
+            state.images[index] = None;
+            state.thumbnails[index] = None;
+            state.placeholders.remove(&index);
+            state.retained.take(index);
+        }
+        if !changed {
+            return;
+        }
+        // Force the worker to re-check which pages need to be rendered:
+        state.acknowledged_pages = 0..0;
+        drop(guard);
+        self.shared.wake_worker.notify_all();
     }
 
-    pub fn frame_start(&mut self, window: &mut Window, _cx: &mut Context<PdfPages>) {
+    /// Free the textures of images that are no longer used, `diagnostics` logs every texture that is
+    /// dropped.
+    pub fn frame_start(
+        &mut self,
+        diagnostics: bool,
+        window: &mut Window,
+        _cx: &mut Context<PdfPages>,
+    ) {
         log::trace!(r"PdfPage render started \\//");
-        self.pages_last_frame = self.pages_this_frame.clone();
-        self.pages_this_frame = 0..0;
+        self.frame_requests.next_frame();
 
+        let dropped_before = self.dropped_textures;
         self.rendered_images.retain(|image| {
             if Arc::strong_count(&image.0) == 1 {
+                if diagnostics {
+                    log::debug!(
+                        "Dropping texture of image {:?}, size={:?}",
+                        image.0.id,
+                        image.0.size(0)
+                    );
+                }
                 _ = window.drop_image(image.0.clone());
+                self.dropped_textures += 1;
                 false
             } else {
                 true
             }
         });
+        if self.dropped_textures != dropped_before {
+            log::debug!(
+                "Dropped {} page textures ({} in total)",
+                self.dropped_textures - dropped_before,
+                self.dropped_textures
+            );
+        }
+    }
+
+    /// Choose what happens to page images that are evicted from the cache.
+    pub fn set_image_cache_strategy(&self, strategy: ImageCacheStrategy) {
+        self.shared
+            .state
+            .lock()
+            .unwrap()
+            .retained
+            .set_capacity(strategy.capacity());
     }
 
+    /// Images for the pages in `visible_range`. If `request` is `false` then the pages are only
+    /// laid out by the list to measure them and aren't rendered unless they are visible anyway.
     pub fn get_images(
         &mut self,
         visible_range: Range<usize>,
+        request: bool,
         _window: &mut Window,
         _cx: &mut Context<PdfPages>,
-    ) -> Vec<Option<Arc<RenderImage>>> {
+    ) -> Vec<PageSlot> {
         let mut guard = self.shared.state.lock().unwrap();
-        let images = if let Some(images) = guard.images.get(visible_range.clone()) {
-            images.to_vec()
-        } else {
-            vec![None; visible_range.len()]
-        };
-
-        self.rendered_images.extend(
-            images
-                .iter()
-                .filter_map(|slot| slot.as_ref())
-                .map(|image| ArcIdentity(image.clone())),
-        );
+        let images: Vec<Option<PageImage>> =
+            if let Some(images) = guard.images.get(visible_range.clone()) {
+                images
+                    .iter()
+                    .zip(visible_range.clone())
+                    .map(|(image, index)| {
+                        image
+                            .clone()
+                            .or_else(|| guard.placeholders.get(&index).cloned().map(Ok))
+                            .or_else(|| guard.thumbnails.get(index)?.clone().filter(Result::is_ok))
+                    })
+                    .collect()
+            } else {
+                vec![None; visible_range.len()]
+            };
 
-        if visible_range.start == 0 && visible_range.end == 1 {
-            // Don't track request for only the first page since the virtual list always requests it.
-            return images;
+        for image in images
+            .iter()
+            .filter_map(|slot| slot.as_ref()?.as_ref().ok())
+        {
+            if self.rendered_images.insert(ArcIdentity(image.clone())) {
+                self.uploaded_textures += 1;
+            }
         }
 
-        if self.pages_this_frame.len() == 0
-            || !range_is_contiguous(self.pages_this_frame.clone(), visible_range.clone())
-        {
-            // If non-contiguous: ignore previous range this frame, it was likely rendered
-            // incorrectly before layout calculations determined that they weren't visible
-            self.pages_this_frame = visible_range.clone();
-        } else {
-            // Keep previously rendered pages cached.
-            self.pages_this_frame =
-                range_union(self.pages_this_frame.clone(), visible_range.clone());
+        // Pages without an image are waiting for a worker if they were requested:
+        let to_slots = |guard: &PdfPageCacheMutableState, images: Vec<Option<PageImage>>| {
+            images
+                .into_iter()
+                .zip(visible_range.clone())
+                .map(|(image, index)| match image {
+                    Some(Ok(image)) => PageSlot::Image(image),
+                    Some(Err(e)) => PageSlot::Failed(e),
+                    None if guard.rendering.contains(&index)
+                        || guard.requested_pages.contains(&index) =>
+                    {
+                        PageSlot::Rendering
+                    }
+                    None => PageSlot::NotRequested,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        if !request {
+            return to_slots(&guard, images);
         }
 
         // Tell the background thread about the new image range:
-        guard.requested_pages =
-            range_union(self.pages_this_frame.clone(), self.pages_last_frame.clone());
+        guard.requested_pages = self.frame_requests.request(visible_range.clone());
+        let slots = to_slots(&guard, images);
 
         if guard.requested_pages != guard.acknowledged_pages {
             if let Some(waker) = guard.wake_future.take() {
@@ -463,13 +1367,13 @@ impl PdfPageCache {
 
         log::trace!(
             "Rendering pdf pages at visible_range={visible_range:?}, current_images={:?}",
-            images
+            slots
                 .iter()
-                .map(|image| image.is_some())
+                .map(|slot| !matches!(slot, PageSlot::NotRequested | PageSlot::Rendering))
                 .collect::<Vec<_>>()
         );
 
-        images
+        slots
     }
 }
 
@@ -479,308 +1383,5867 @@ pub struct PdfPages {
     /// State of the scrollbar element.
     scroll_state: ScrollbarState,
     /// Pointer to scroll info inside tab data. Use to save current scroll position before loading a new PDF.
-    save_scroll: Arc<Mutex<VirtualListScrollHandle>>,
+    save_scroll: Arc<Mutex<ScrollPosition>>,
     /// Sizes of each page in the PDF file.
     item_sizes: Rc<Vec<Size<Pixels>>>,
+    /// Unscaled and unrotated size of each page. Pages that aren't in [`Self::measured_pages`]
+    /// are estimated from the first page so that opening a large document doesn't have to read
+    /// every page.
+    page_dimensions: Rc<Vec<(f32, f32)>>,
+    /// Pages whose [`Self::page_dimensions`] were read from the PDF instead of estimated.
+    measured_pages: Vec<bool>,
+    /// Shared with the active tab's [`PdfTabData`].
+    rotation: Arc<Mutex<DocumentRotation>>,
+    /// The active tab's zoom, updated when switching to another tab.
+    save_zoom: Arc<Mutex<TabZoom>>,
+    /// Pages shown in each row of the list, rows hold two pages in [`ViewMode::TwoPage`].
+    rows: Rc<Vec<Range<usize>>>,
+    /// Size of each row in [`Self::rows`], these are the items of the virtual list.
+    row_sizes: Rc<Vec<Size<Pixels>>>,
+    view_mode: ViewMode,
+    /// Show the first page alone on the right in [`ViewMode::TwoPage`] so that the following
+    /// pages pair up like in a book (2-3, 4-5 and so on).
+    cover_page: bool,
+    /// Show the pages of a row from right to left, for documents written in a right-to-left
+    /// script.
+    right_to_left: bool,
+    /// Empty space below every row except the last, included in [`Self::row_sizes`].
+    page_gap: Pixels,
+    /// Background around and between pages.
+    canvas_color: CanvasColor,
+    /// The page that is shown in [`ViewMode::SinglePage`].
+    single_page: usize,
+    /// Mouse wheel movement past the edge of the page that hasn't flipped the page yet.
+    wheel_flip: f32,
+    /// Logical pixels per PDF unit used for [`Self::item_sizes`], this is the scale chosen by
+    /// [`Self::fit_mode`] multiplied by [`Self::zoom`].
+    layout_scale: f32,
+    /// Multiplies [`Self::layout_scale`] for individual pages, empty if every page uses the same
+    /// scale.
+    page_scales: Vec<f32>,
+    fit_mode: FitMode,
+    /// Size of the area that pages are shown in, used by [`Self::fit_mode`].
+    viewport_size: Size<Pixels>,
+    /// Zoom factor chosen by the user, `1` is the scale chosen by [`Self::fit_mode`].
+    zoom: f32,
+    /// The zoom before and after double-clicking a page, a second double-click goes back unless
+    /// the zoom was changed in between.
+    zoom_toggle: Option<(f32, f32)>,
+    /// Device pixels per logical pixel, pages are rendered at the display's resolution.
+    scale_factor: f32,
+    /// Applies the render settings for a new zoom level once the user stops zooming.
+    delayed_render: Option<Task<()>>,
+    /// Animates programmatic scrolling of the pages.
+    smooth_scroll: SmoothScrollState,
+    /// Focused when the pages are clicked, bindings in [`PAGES_CONTEXT`] only apply then.
+    focus_handle: FocusHandle,
     /// Cached rasterized PDF pages.
     pdf_page_cache: PdfPageCache,
+    /// Show a side panel with small images of every page.
+    show_thumbnails: bool,
+    /// Show an overlay with statistics about the page cache.
+    show_cache_stats: bool,
+    /// The view to go back to after presentation mode, `Some` while presenting.
+    presentation: Option<PresentationRestore>,
+    /// Pages rendered at [`THUMBNAIL_WIDTH`] for the thumbnail panel.
+    thumbnail_cache: PdfPageCache,
+    thumbnail_scroll: VirtualListScrollHandle,
+    /// Sizes of the items in the thumbnail panel, including the page number below each image.
+    thumbnail_sizes: Rc<Vec<Size<Pixels>>>,
+    /// The current page the last time the thumbnail panel was scrolled to it.
+    followed_thumbnail: Option<usize>,
     /// Used to bypass GPUI's inbuilt image cache.
     disabled_cache: Entity<NoGpuiImageCache>,
+    /// Window bounds of each page the last time it was painted.
+    page_bounds: Rc<RefCell<HashMap<usize, Bounds<Pixels>>>>,
+    /// What happens when the left mouse button is pressed on a page.
+    pointer_tool: PointerTool,
+    /// A region that is being selected by dragging on a page.
+    region_drag: Option<RegionDrag>,
+    /// Mouse position in window coordinates the last time the pages were moved by the hand tool.
+    pan_drag: Option<Point<Pixels>>,
+    /// Points clicked with the measure tool.
+    measurement: Option<Measurement>,
+    measure_unit: MeasureUnit,
+    /// Drawing scale, measurements are multiplied by this (`50` for a 1:50 drawing).
+    measure_scale: f64,
+    link_opening: LinkOpening,
+    /// The page that covers most of the viewport, updated while the pages are rendered.
+    most_visible_page: Option<usize>,
+    /// Extracted text of the current document, built the first time it is searched.
+    text_index: Option<Arc<TextIndex>>,
+    /// Builds [`Self::text_index`] in the background, this is also shown as [`Self::job`].
+    text_index_job: Option<(CancellationToken, Arc<Mutex<Progress>>, Task<()>)>,
+    search: Option<SearchResults>,
+    /// An export or other operation that processes every page.
+    job: Option<PageJob>,
+    /// Other pages that are scrolled to the same page as these, see [`Self::set_scroll_sync`].
+    scroll_sync: Option<WeakEntity<PdfPages>>,
+    /// Where these pages were when they last scrolled [`Self::scroll_sync`] or were scrolled by
+    /// it, `None` until the next frame after being scrolled by it.
+    synced_position: Option<ScrollPosition>,
 }
-impl PdfPages {
-    #[cfg_attr(feature = "hotpath", hotpath::measure)]
-    pub fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
-        Self {
-            scroll_handle: VirtualListScrollHandle::from(ScrollHandle::default()),
-            scroll_state: Default::default(),
-            save_scroll: Arc::new(Mutex::new(VirtualListScrollHandle::from(
-                ScrollHandle::new(),
-            ))),
-            item_sizes: Rc::new(vec![]),
-            pdf_page_cache: PdfPageCache::new(window, cx),
-            disabled_cache: cx.new(|_cx| NoGpuiImageCache),
+
+/// Read a file that was reported as changed, retrying a few times since it might still be
+/// being written. Returns `None` if it never became a valid PDF file, otherwise the data together
+/// with the parsed document so that it doesn't need to be parsed again.
+async fn read_changed_pdf(
+    window: &mut AsyncWindowContext,
+    path: &Path,
+) -> Option<(Arc<Vec<u8>>, Arc<Pdf>)> {
+    let mut last_error = String::new();
+    for attempt in 0..RELOAD_ATTEMPTS {
+        if attempt > 0 {
+            window.background_executor().timer(RELOAD_DELAY).await;
+        }
+        let path = path.to_owned();
+        let result = window
+            .background_executor()
+            .spawn(async move {
+                let data = Arc::new(std::fs::read(&path).map_err(|e| e.to_string())?);
+                let pdf = pdf::load_pdf(data.clone()).map_err(|e| format!("{e:?}"))?;
+                Ok::<_, String>((data, pdf))
+            })
+            .await;
+        match result {
+            Ok(loaded) => return Some(loaded),
+            Err(e) => last_error = e,
         }
     }
+    log::warn!(
+        "Not reloading {} since it couldn't be read: {last_error}",
+        path.display()
+    );
+    None
 }
-impl Render for PdfPages {
-    #[cfg_attr(feature = "hotpath", hotpath::measure)]
-    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        fn weak_image(image: &Arc<RenderImage>) -> ImageSource {
-            let image = Arc::downgrade(image);
-            ImageSource::Custom(Arc::new(move |_window, _cx| Some(Ok(image.upgrade()?))))
-        }
 
-        self.pdf_page_cache.frame_start(window, cx);
-        let element = div()
-            .relative()
-            .size_full()
-            .child(
+/// Bytes of a file that [`read_files`] has read so far.
+#[derive(Default)]
+struct ReadProgress {
+    read: AtomicU64,
+    /// Size of the file, `0` until it has been opened.
+    total: AtomicU64,
+}
+
+/// Read a file in chunks so that `progress` can be shown while large files are read.
+fn read_with_progress(path: &Path, progress: &ReadProgress) -> std::io::Result<Vec<u8>> {
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path)?;
+    let total = file.metadata().map_or(0, |metadata| metadata.len());
+    progress.total.store(total, AtomicOrdering::Relaxed);
+    let mut data = Vec::with_capacity(usize::try_from(total).unwrap_or(0));
+    loop {
+        let read = (&mut file).take(READ_CHUNK_SIZE).read_to_end(&mut data)?;
+        if read == 0 {
+            return Ok(data);
+        }
+        progress
+            .read
+            .fetch_add(read as u64, AtomicOrdering::Relaxed);
+    }
+}
+
+/// Read files in parallel, reporting the progress of files that take a while with
+/// [`PdfCommand::LoadProgress`]. Files that can't be read are left out and the user is notified
+/// about them.
+async fn read_files(
+    window: &mut AsyncWindowContext,
+    sender: &mut MsgSender<PdfReader>,
+    paths: Vec<PathBuf>,
+) -> Vec<(PathBuf, Vec<u8>)> {
+    let executor = window.background_executor().clone();
+    // Start reading every file before waiting for any of them:
+    let mut reads = paths
+        .into_iter()
+        .map(|path| {
+            let progress = Arc::new(ReadProgress::default());
+            let read = executor.spawn({
+                let path = path.clone();
+                let progress = progress.clone();
+                async move { read_with_progress(&path, &progress) }
+            });
+            (path, progress, read)
+        })
+        .collect::<Vec<_>>();
+    let mut files = Vec::with_capacity(reads.len());
+    for index in 0..reads.len() {
+        let result = loop {
+            let outcome = (&mut reads[index].2)
+                .with_timeout(LOAD_PROGRESS_INTERVAL, &executor)
+                .await;
+            match outcome {
+                Ok(result) => break result,
+                Err(Timeout) => {
+                    // Report every file that is still being read, not just the awaited one:
+                    for (path, progress, _) in &reads[index..] {
+                        let read = progress.read.load(AtomicOrdering::Relaxed);
+                        let total = progress.total.load(AtomicOrdering::Relaxed);
+                        if read < total {
+                            sender.send_ignoring_errors(PdfCommand::LoadProgress(
+                                path.clone(),
+                                Some(LoadStage::Reading { read, total }),
+                            ));
+                        }
+                    }
+                }
+            }
+        };
+        let path = reads[index].0.clone();
+        match result {
+            Ok(data) => {
+                sender.send_ignoring_errors(PdfCommand::LoadProgress(
+                    path.clone(),
+                    Some(LoadStage::Parsing),
+                ));
+                files.push((path, data));
+            }
+            Err(e) => {
+                sender.send_ignoring_errors(PdfCommand::LoadProgress(path.clone(), None));
+                sender.send_ignoring_errors(PdfCommand::Notify(
+                    Severity::Error,
+                    format!("Failed to read {}: {e}", path.display()),
+                ));
+            }
+        }
+    }
+    files
+}
+
+/// Show a page image without keeping it alive so that [`PdfPageCache::frame_start`] can tell when
+/// GPUI no longer uses it. If `diagnostics` is set then every request for the image is logged.
+fn weak_image(image: &Arc<RenderImage>, page: usize, diagnostics: bool) -> ImageSource {
+    let image = Arc::downgrade(image);
+    ImageSource::Custom(Arc::new(move |_window, _cx| {
+        let image = image.upgrade();
+        if diagnostics {
+            match &image {
+                Some(image) => log::debug!(
+                    "Handing image {:?} of page {page} to GPUI, size={:?}",
+                    image.id,
+                    image.size(0)
+                ),
+                None => log::debug!("Image of page {page} was freed before GPUI requested it"),
+            }
+        }
+        Some(Ok(image?))
+    }))
+}
+
+/// A long running operation whose progress is shown on top of the pages.
+struct PageJob {
+    label: &'static str,
+    progress: Arc<Mutex<Progress>>,
+    cancel: CancellationToken,
+    /// Re-renders the pages so that the progress stays up to date.
+    _refresh: Task<()>,
+}
+
+/// Matches for the last search query.
+#[derive(Clone, Debug)]
+struct SearchResults {
+    query: String,
+    /// Sorted by page.
+    hits: Vec<SearchHit>,
+    /// Index into [`Self::hits`] of the match that was scrolled to.
+    current_match: usize,
+}
+impl SearchResults {
+    /// Index of the match that is shown in a different color than the others.
+    fn current_index(&self) -> Option<usize> {
+        (self.current_match < self.hits.len()).then_some(self.current_match)
+    }
+    /// Move to the next (or previous) match, wrapping around at both ends.
+    fn step(&mut self, forward: bool) -> Option<&SearchHit> {
+        let count = self.hits.len();
+        if count == 0 {
+            return None;
+        }
+        self.current_match = if forward {
+            (self.current_match + 1) % count
+        } else {
+            (self.current_match + count - 1) % count
+        };
+        self.hits.get(self.current_match)
+    }
+    /// Indexes into [`Self::hits`] of the matches on a page.
+    fn hits_on_page(&self, page: usize) -> Range<usize> {
+        let start = self.hits.partition_point(|hit| hit.page < page);
+        let end = self.hits.partition_point(|hit| hit.page <= page);
+        start..end
+    }
+}
+
+/// How pages are arranged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ViewMode {
+    /// Every page below the previous one.
+    #[default]
+    Continuous,
+    /// Only one page at a time, scrolling past its edge flips to the next page.
+    SinglePage,
+    /// Pages side by side in pairs.
+    TwoPage,
+}
+impl ViewMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            ViewMode::Continuous => "Continuous",
+            ViewMode::SinglePage => "Single page",
+            ViewMode::TwoPage => "Two pages",
+        }
+    }
+    fn next(self) -> Self {
+        match self {
+            ViewMode::Continuous => ViewMode::SinglePage,
+            ViewMode::SinglePage => ViewMode::TwoPage,
+            ViewMode::TwoPage => ViewMode::Continuous,
+        }
+    }
+}
+
+/// How the scale of the pages is chosen before the user's zoom is applied.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FitMode {
+    /// The widest page fills the viewport's width.
+    #[default]
+    Width,
+    /// Every page fills the viewport's width on its own, so that a single oversized page doesn't
+    /// make the other pages tiny.
+    EachWidth,
+    /// The tallest page fills the viewport's height.
+    Height,
+    /// The largest page fits entirely in the viewport.
+    Page,
+    /// A fixed number of logical pixels per PDF unit that doesn't change when the window is
+    /// resized, `1` shows pages at their actual size.
+    Custom(f32),
+}
+impl FitMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            FitMode::Width => "Fit width",
+            FitMode::EachWidth => "Fit each page's width",
+            FitMode::Height => "Fit height",
+            FitMode::Page => "Fit page",
+            FitMode::Custom(_) => "Custom scale",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PointerTool {
+    /// Clicks are ignored.
+    #[default]
+    None,
+    /// Drag to select a region to detect tables in, the tables are then exported as CSV.
+    SelectTableRegion,
+    /// Drag to select a region that is copied to the clipboard as an image.
+    CopyImageRegion,
+    /// Drag to select a region that is saved as a PNG file.
+    SaveImageRegion,
+    /// Click points to measure the distance between them and the area they enclose.
+    Measure,
+    /// Drag to move the pages in both directions.
+    Hand,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct RegionDrag {
+    page: usize,
+    /// Window coordinates where the drag started.
+    start: Point<Pixels>,
+    /// Window coordinates of the mouse.
+    end: Point<Pixels>,
+}
+impl PdfPages {
+    #[cfg_attr(feature = "hotpath", hotpath::measure)]
+    pub fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
+        Self {
+            scroll_handle: VirtualListScrollHandle::from(ScrollHandle::default()),
+            scroll_state: Default::default(),
+            save_scroll: Default::default(),
+            item_sizes: Rc::new(vec![]),
+            page_dimensions: Rc::new(vec![]),
+            measured_pages: Vec::new(),
+            rotation: Default::default(),
+            save_zoom: Default::default(),
+            rows: Rc::new(vec![]),
+            row_sizes: Rc::new(vec![]),
+            view_mode: ViewMode::default(),
+            cover_page: true,
+            right_to_left: false,
+            page_gap: px(0.),
+            canvas_color: CanvasColor::default(),
+            single_page: 0,
+            wheel_flip: 0.,
+            layout_scale: 1.,
+            page_scales: Vec::new(),
+            fit_mode: FitMode::default(),
+            viewport_size: Size::default(),
+            zoom: 1.,
+            zoom_toggle: None,
+            scale_factor: 1.,
+            delayed_render: None,
+            smooth_scroll: SmoothScrollState::new(),
+            focus_handle: cx.focus_handle(),
+            pdf_page_cache: PdfPageCache::new(None, window, cx),
+            show_thumbnails: false,
+            show_cache_stats: false,
+            presentation: None,
+            // Thumbnails are cheap to render, don't compete with the main view:
+            thumbnail_cache: PdfPageCache::new(Some(1), window, cx),
+            thumbnail_scroll: VirtualListScrollHandle::from(ScrollHandle::default()),
+            thumbnail_sizes: Rc::new(vec![]),
+            followed_thumbnail: None,
+            disabled_cache: cx.new(|_cx| NoGpuiImageCache::default()),
+            page_bounds: Default::default(),
+            pointer_tool: PointerTool::None,
+            region_drag: None,
+            pan_drag: None,
+            measurement: None,
+            measure_unit: MeasureUnit::default(),
+            measure_scale: 1.,
+            link_opening: LinkOpening::default(),
+            most_visible_page: None,
+            text_index: None,
+            text_index_job: None,
+            search: None,
+            job: None,
+            scroll_sync: None,
+            synced_position: None,
+        }
+    }
+
+    /// Apply the settings that affect how pages are shown.
+    fn apply_settings(
+        &mut self,
+        settings: &Settings,
+        filters: FilterChain,
+        cx: &mut Context<Self>,
+    ) {
+        self.set_filters(filters, cx);
+        self.set_link_opening(settings.link_opening);
+        self.set_performance(&settings.performance);
+        self.set_smooth_scroll(settings.smooth_scroll);
+        self.set_right_to_left(settings.right_to_left, cx);
+        self.set_page_gap(px(settings.layout.page_gap), cx);
+        self.set_canvas_color(settings.layout.canvas_color, cx);
+    }
+
+    /// Render pages at a fixed pixel width no matter the window size or `None` to render at the
+    /// window's resolution. Pages are still shown at their normal layout size.
+    pub fn set_fixed_render_width(&mut self, width: Option<u16>, cx: &mut Context<Self>) {
+        self.pdf_page_cache.set_fixed_width(width);
+        cx.notify();
+    }
+
+    /// Change the filters that post-process rendered pages.
+    pub fn set_filters(&mut self, filters: FilterChain, cx: &mut Context<Self>) {
+        self.thumbnail_cache.set_filters(filters.clone());
+        self.pdf_page_cache.set_filters(filters);
+        cx.notify();
+    }
+
+    /// Apply changed performance settings to the page cache.
+    pub fn set_performance(&mut self, performance: &PerformanceSettings) {
+        let cache = &mut self.pdf_page_cache;
+        cache.set_image_cache_strategy(performance.image_cache);
+        cache.set_cache_ahead(performance.cache_ahead);
+        cache.set_render_threads(performance.render_threads);
+        cache.set_supersampling(performance.supersampling);
+        cache.set_memory_budget(
+            performance
+                .memory_budget_mb
+                .map(|megabytes| megabytes.saturating_mul(1024 * 1024)),
+        );
+    }
+    pub fn set_smooth_scroll(&mut self, settings: SmoothScrollSettings) {
+        self.smooth_scroll.set_settings(settings);
+    }
+    pub fn set_page_gap(&mut self, page_gap: Pixels, cx: &mut Context<Self>) {
+        if self.page_gap == page_gap {
+            return;
+        }
+        self.page_gap = page_gap;
+        if self.rows.is_empty() {
+            return;
+        }
+        let page = self.current_page().unwrap_or(0);
+        self.relayout_at(page, cx);
+    }
+    pub fn set_canvas_color(&mut self, canvas_color: CanvasColor, cx: &mut Context<Self>) {
+        if self.canvas_color != canvas_color {
+            self.canvas_color = canvas_color;
+            cx.notify();
+        }
+    }
+    pub fn set_right_to_left(&mut self, right_to_left: bool, cx: &mut Context<Self>) {
+        if self.right_to_left != right_to_left {
+            self.right_to_left = right_to_left;
+            cx.notify();
+        }
+    }
+
+    /// Show how pages differ from another version of the document.
+    pub fn set_compare_with(&mut self, other: Option<Arc<Pdf>>, cx: &mut Context<Self>) {
+        self.pdf_page_cache.set_compare_with(other);
+        cx.notify();
+    }
+
+    pub fn set_link_opening(&mut self, link_opening: LinkOpening) {
+        self.link_opening = link_opening;
+    }
+
+    /// The page that covers most of the viewport.
+    pub fn current_page(&self) -> Option<usize> {
+        if self.view_mode == ViewMode::SinglePage {
+            return (self.single_page < self.item_sizes.len()).then_some(self.single_page);
+        }
+        if let Some(index) = self.most_visible_page
+            && index < self.item_sizes.len()
+        {
+            return Some(index);
+        }
+        // Not rendered yet, guess from the pages that were requested:
+        let visible = self.pdf_page_cache.visible_pages();
+        if visible.is_empty() {
+            // Nothing requested yet, the list starts at the first page:
+            (!self.item_sizes.is_empty()).then_some(0)
+        } else {
+            Some(visible.start + (visible.len() - 1) / 2)
+        }
+    }
+
+    /// Convert a position in the window to unscaled page coordinates, this is the coordinate space
+    /// used by [`pdf::extract_features`] with default render settings.
+    pub fn window_to_page(&self, index: usize, position: Point<Pixels>) -> Option<kurbo::Point> {
+        let bounds = *self.page_bounds.borrow().get(&index)?;
+        let pdf = self.pdf_page_cache.pdf()?;
+        let (width, height) = pdf.pages().get(index)?.render_dimensions();
+        let local = position - bounds.origin;
+        let (x, y) = self.page_rotation(index).unrotate_fraction((
+            f64::from(f32::from(local.x) / f32::from(bounds.size.width)),
+            f64::from(f32::from(local.y) / f32::from(bounds.size.height)),
+        ));
+        Some(kurbo::Point::new(
+            x * f64::from(width),
+            y * f64::from(height),
+        ))
+    }
+
+    /// Convert unscaled page coordinates to a position within the page's element, which has the
+    /// size `page_size`.
+    fn page_to_local(
+        &self,
+        index: usize,
+        position: kurbo::Point,
+        page_size: Size<Pixels>,
+    ) -> Option<Point<Pixels>> {
+        let pdf = self.pdf_page_cache.pdf()?;
+        let (width, height) = pdf.pages().get(index)?.render_dimensions();
+        let (x, y) = self.page_rotation(index).rotate_fraction((
+            position.x / f64::from(width),
+            position.y / f64::from(height),
+        ));
+        Some(point(
+            page_size.width * x as f32,
+            page_size.height * y as f32,
+        ))
+    }
+
+    pub fn page_rotation(&self, index: usize) -> PageRotation {
+        self.rotation.lock().unwrap().page(index)
+    }
+    /// Change the rotation of the document's pages while staying on the current page.
+    fn update_rotation(
+        &mut self,
+        change: impl FnOnce(&mut DocumentRotation),
+        cx: &mut Context<Self>,
+    ) {
+        let page = self.current_page().unwrap_or(0);
+        change(&mut self.rotation.lock().unwrap());
+        self.apply_rotation();
+        self.smooth_scroll.reset(); // stop animations
+        let x = self.scroll_handle.offset().x;
+        self.scroll_handle
+            .set_offset(point(x, -self.page_top(page)));
+        cx.notify();
+    }
+    /// Lay out and render pages with the rotation in [`Self::rotation`].
+    fn apply_rotation(&mut self) {
+        let rotation = self.rotation.lock().unwrap().clone();
+        self.pdf_page_cache
+            .set_page_rotations(|index| rotation.page(index));
+        self.thumbnail_cache
+            .set_page_rotations(|index| rotation.page(index));
+        if let Some(pdf) = self.pdf_page_cache.pdf() {
+            self.layout_thumbnails(&pdf);
+            let render_settings = self.layout_pages(&pdf);
+            self.pdf_page_cache.set_render_settings(render_settings);
+        }
+    }
+    /// Show thumbnails of a new document or `None` to forget the current one.
+    fn set_thumbnail_pdf(&mut self, pdf: Option<Arc<Pdf>>) {
+        self.thumbnail_scroll = VirtualListScrollHandle::from(ScrollHandle::default());
+        self.followed_thumbnail = None;
+        self.thumbnail_cache
+            .set_new_pdf(pdf.clone(), RenderSettings2::default());
+        self.thumbnail_cache
+            .set_fixed_width(Some((THUMBNAIL_WIDTH * self.scale_factor).ceil() as u16));
+        let rotation = self.rotation.lock().unwrap().clone();
+        self.thumbnail_cache
+            .set_page_rotations(|index| rotation.page(index));
+        match pdf {
+            Some(pdf) => self.layout_thumbnails(&pdf),
+            None => self.thumbnail_sizes = Rc::new(vec![]),
+        }
+    }
+    fn layout_thumbnails(&mut self, pdf: &Pdf) {
+        self.thumbnail_sizes = Rc::new(
+            self.rotated_page_dimensions(pdf)
+                .into_iter()
+                .map(|(width, height)| {
+                    let image_height = (THUMBNAIL_WIDTH * height / width.max(1.)).floor();
+                    size(
+                        px(THUMBNAIL_WIDTH + 2. * THUMBNAIL_PADDING),
+                        px(image_height + THUMBNAIL_LABEL_HEIGHT + 2. * THUMBNAIL_PADDING),
+                    )
+                })
+                .collect(),
+        );
+    }
+    pub fn on_action_toggle_thumbnails(
+        &mut self,
+        _: &ToggleThumbnails,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.show_thumbnails = !self.show_thumbnails;
+        self.followed_thumbnail = None;
+        cx.notify();
+    }
+    pub fn is_presenting(&self) -> bool {
+        self.presentation.is_some()
+    }
+    /// Show one page at a time that fills `screen_size` like a slideshow, the current page stays
+    /// the same.
+    pub fn start_presentation(&mut self, screen_size: Size<Pixels>, cx: &mut Context<Self>) {
+        if self.presentation.is_some() {
+            return;
+        }
+        let page = self.current_page().unwrap_or(0);
+        self.presentation = Some(PresentationRestore {
+            view_mode: self.view_mode,
+            fit_mode: self.fit_mode,
+            zoom: self.zoom,
+            show_thumbnails: self.show_thumbnails,
+            viewport_size: self.viewport_size,
+        });
+        self.view_mode = ViewMode::SinglePage;
+        self.single_page = page;
+        self.fit_mode = FitMode::Page;
+        self.zoom = 1.;
+        self.show_thumbnails = false;
+        // Nothing else is shown so the pages get the whole window:
+        self.viewport_size = screen_size;
+        self.relayout_at(page, cx);
+    }
+    /// Go back to the view that was used before [`Self::start_presentation`].
+    pub fn stop_presentation(&mut self, cx: &mut Context<Self>) {
+        let Some(restore) = self.presentation.take() else {
+            return;
+        };
+        let page = self.current_page().unwrap_or(0);
+        self.view_mode = restore.view_mode;
+        self.single_page = page;
+        self.fit_mode = restore.fit_mode;
+        self.zoom = restore.zoom;
+        self.show_thumbnails = restore.show_thumbnails;
+        self.viewport_size = restore.viewport_size;
+        self.relayout_at(page, cx);
+    }
+
+    /// Show `pdf` laid out for the size that the pages were last shown at. The scroll position and
+    /// zoom of the previous document are saved before the ones in `scroll` and `zoom` are restored.
+    fn load_pdf(
+        &mut self,
+        pdf: Option<Arc<Pdf>>,
+        scroll: Arc<Mutex<ScrollPosition>>,
+        rotation: Arc<Mutex<DocumentRotation>>,
+        zoom: Arc<Mutex<TabZoom>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        // The list was laid out for the current window size during the last frame:
+        let list_size = self.scroll_handle.bounds().size;
+        // Save scroll while the old layout is still known:
+        *self.save_scroll.lock().unwrap() = self.scroll_position();
+        self.item_sizes = Rc::new(vec![]); // forget page sizes
+        self.page_dimensions = Rc::new(vec![]);
+        self.measured_pages.clear();
+        self.layout_rows();
+        self.pdf_page_cache.clear(); // clear cache
+        self.set_thumbnail_pdf(None);
+
+        self.scroll_handle = VirtualListScrollHandle::from(ScrollHandle::default()); // reset scroll
+        self.smooth_scroll.reset(); // stop animations
+        self.most_visible_page = None;
+        self.clear_search(cx);
+        self.save_scroll = scroll;
+        self.rotation = rotation;
+        // Presentation mode chooses its own scale and restores the view when it ends:
+        if self.presentation.is_none() {
+            *self.save_zoom.lock().unwrap() = TabZoom {
+                fit_mode: self.fit_mode,
+                zoom: self.zoom,
+            };
+            let restored = *zoom.lock().unwrap();
+            self.fit_mode = restored.fit_mode;
+            self.zoom = restored.zoom;
+            self.delayed_render = None;
+        }
+        self.save_zoom = zoom;
+
+        let Some(pdf) = pdf else {
+            return;
+        };
+        if pdf.pages().is_empty() {
+            // no pages
+            return;
+        }
+        // Scale pages to fit the area they are shown in:
+        self.viewport_size = if list_size.width > px(0.) && list_size.height > px(0.) {
+            list_size
+        } else {
+            // Not shown yet:
+            window.viewport_size()
+        };
+        self.scale_factor = window.scale_factor();
+
+        // Update layout/sizes and image rendering:
+        let render_settings = self.layout_pages(&pdf);
+        self.pdf_page_cache
+            .set_new_pdf(Some(pdf.clone()), render_settings);
+        let rotation = self.rotation.lock().unwrap().clone();
+        self.pdf_page_cache
+            .set_page_rotations(|index| rotation.page(index));
+
+        self.set_thumbnail_pdf(Some(pdf.clone()));
+
+        // Restore scroll using the new layout:
+        let position = *self.save_scroll.lock().unwrap();
+        self.set_scroll_position(position);
+    }
+
+    /// Lay out the pages for an area that is `change` larger than before while staying at the same
+    /// scroll position. The pages are stretched to their new size until the next time the
+    /// document is loaded since rendering them at every step of a window resize would be slow.
+    pub fn preview_resize(&mut self, change: Size<Pixels>, cx: &mut Context<Self>) {
+        let Some(pdf) = self.pdf_page_cache.pdf() else {
+            return;
+        };
+        let position = self.scroll_position();
+        self.viewport_size = size(
+            (self.viewport_size.width + change.width).max(px(1.)),
+            (self.viewport_size.height + change.height).max(px(1.)),
+        );
+        self.layout_pages(&pdf);
+        self.smooth_scroll.reset(); // stop animations
+        self.set_scroll_position(position);
+        cx.notify();
+    }
+
+    /// Lay out the pages again and scroll to the top of `page`.
+    fn relayout_at(&mut self, page: usize, cx: &mut Context<Self>) {
+        if let Some(pdf) = self.pdf_page_cache.pdf() {
+            let render_settings = self.layout_pages(&pdf);
+            self.delayed_render = None;
+            self.pdf_page_cache.set_render_settings(render_settings);
+        }
+        self.smooth_scroll.reset(); // stop animations
+        self.scroll_handle
+            .set_offset(point(px(0.), -self.page_top(page)));
+        self.most_visible_page = Some(page);
+        cx.notify();
+    }
+    pub fn on_action_toggle_cache_stats(
+        &mut self,
+        _: &ToggleCacheStats,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.show_cache_stats = !self.show_cache_stats;
+        cx.notify();
+    }
+    /// Scroll the thumbnail panel so that the current page's thumbnail is visible.
+    fn follow_current_page_thumbnail(&mut self) {
+        let current = self.current_page();
+        if current == self.followed_thumbnail {
+            return;
+        }
+        let Some(index) = current.filter(|&index| index < self.thumbnail_sizes.len()) else {
+            return;
+        };
+        let viewport_height = self.thumbnail_scroll.bounds().size.height;
+        if viewport_height <= px(0.) {
+            return; // not laid out yet, try again next frame
+        }
+        self.followed_thumbnail = current;
+        let top = self.thumbnail_sizes[..index]
+            .iter()
+            .fold(px(0.), |top, size| top + size.height);
+        let bottom = top + self.thumbnail_sizes[index].height;
+        let offset = self.thumbnail_scroll.offset();
+        if top < -offset.y || bottom > -offset.y + viewport_height {
+            self.thumbnail_scroll.set_offset(point(
+                offset.x,
+                -(top - (viewport_height - (bottom - top)) / 2.).max(px(0.)),
+            ));
+        }
+    }
+    fn render_thumbnails(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
+        self.follow_current_page_thumbnail();
+        div()
+            .w(px(THUMBNAIL_WIDTH + 2. * THUMBNAIL_PADDING))
+            .h_full()
+            .flex_none()
+            .border_r_1()
+            .border_color(cx.theme().border)
+            .child(
                 v_virtual_list(
                     cx.entity().clone(),
-                    "pdf-viewer-pages-list",
-                    self.item_sizes.clone(),
+                    "pdf-thumbnails-list",
+                    self.thumbnail_sizes.clone(),
                     move |view, visible_range, window, cx| {
+                        let current_page = view.current_page();
+                        // The first thumbnail is also laid out to measure it:
+                        let measuring = visible_range == (0..1)
+                            && view.thumbnail_sizes.first().is_some_and(|size| {
+                                size.height <= -view.thumbnail_scroll.offset().y
+                            });
+                        let images = view.thumbnail_cache.get_images(
+                            visible_range.clone(),
+                            !measuring,
+                            window,
+                            cx,
+                        );
                         visible_range
-                            .clone()
-                            .zip(view.pdf_page_cache.get_images(visible_range, window, cx))
-                            .map(|(_row_ix, page_image)| {
-                                if let Some(page_image) = page_image {
-                                    img(weak_image(&page_image))
-                                        .object_fit(ObjectFit::Cover)
-                                        .max_w(window.viewport_size().width)
-                                        .image_cache(&view.disabled_cache)
-                                        //.w(px(page.media_box().width() as f32))
-                                        //.h(px(page.media_box().height() as f32))
-                                        .into_any_element()
-                                } else {
-                                    //  Loading or errored
-                                    div().into_any_element()
-                                }
+                            .zip(images)
+                            .map(|(index, image)| {
+                                let image_size = view.thumbnail_sizes[index];
+                                let image_height = image_size.height
+                                    - px(THUMBNAIL_LABEL_HEIGHT + 2. * THUMBNAIL_PADDING);
+                                v_flex()
+                                    .id(("pdf-thumbnail", index))
+                                    .items_center()
+                                    .p(px(THUMBNAIL_PADDING))
+                                    .cursor_pointer()
+                                    .on_click(cx.listener(move |view, _, _window, cx| {
+                                        view.scroll_to_page(index, cx);
+                                    }))
+                                    .child(
+                                        div()
+                                            .w(px(THUMBNAIL_WIDTH))
+                                            .h(image_height)
+                                            .border_2()
+                                            .border_color(if current_page == Some(index) {
+                                                cx.theme().primary
+                                            } else {
+                                                cx.theme().border
+                                            })
+                                            .bg(cx.theme().muted)
+                                            .when_some(image.image().cloned(), |this, image| {
+                                                this.child(
+                                                    img(weak_image(
+                                                        &image,
+                                                        index,
+                                                        view.disabled_cache.read(cx).diagnostics,
+                                                    ))
+                                                    .object_fit(ObjectFit::Cover)
+                                                    .image_cache(&view.disabled_cache)
+                                                    .size_full(),
+                                                )
+                                            }),
+                                    )
+                                    .child(
+                                        div()
+                                            .h(px(THUMBNAIL_LABEL_HEIGHT))
+                                            .text_xs()
+                                            .text_color(cx.theme().muted_foreground)
+                                            .child((index + 1).to_string()),
+                                    )
+                                    .into_any_element()
                             })
                             .collect()
                     },
                 )
-                .track_scroll(&self.scroll_handle),
+                .track_scroll(&self.thumbnail_scroll),
+            )
+    }
+    pub fn on_action_rotate_clockwise(
+        &mut self,
+        _: &RotateClockwise,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.update_rotation(
+            |rotation| rotation.all_pages = rotation.all_pages.clockwise(),
+            cx,
+        );
+    }
+    pub fn on_action_rotate_counterclockwise(
+        &mut self,
+        _: &RotateCounterclockwise,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.update_rotation(
+            |rotation| rotation.all_pages = rotation.all_pages.counterclockwise(),
+            cx,
+        );
+    }
+    pub fn on_action_rotate_page_clockwise(
+        &mut self,
+        _: &RotatePageClockwise,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.rotate_current_page(PageRotation::clockwise, cx);
+    }
+    pub fn on_action_rotate_page_counterclockwise(
+        &mut self,
+        _: &RotatePageCounterclockwise,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.rotate_current_page(PageRotation::counterclockwise, cx);
+    }
+    fn rotate_current_page(
+        &mut self,
+        turn: fn(PageRotation) -> PageRotation,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(index) = self.current_page() else {
+            return;
+        };
+        self.update_rotation(
+            |rotation| {
+                let page = rotation.pages.entry(index).or_default();
+                *page = turn(*page);
+                if *page == PageRotation::default() {
+                    rotation.pages.remove(&index);
+                }
+            },
+            cx,
+        );
+    }
+
+    pub fn set_pointer_tool(&mut self, tool: PointerTool, cx: &mut Context<Self>) {
+        self.pointer_tool = tool;
+        self.region_drag = None;
+        self.pan_drag = None;
+        self.measurement = None;
+        cx.notify();
+    }
+    pub fn pointer_tool(&self) -> PointerTool {
+        self.pointer_tool
+    }
+    pub fn measure_scale(&self) -> f64 {
+        self.measure_scale
+    }
+    pub fn set_measure_scale(&mut self, scale: f64, cx: &mut Context<Self>) {
+        self.measure_scale = scale;
+        cx.notify();
+    }
+    pub fn measure_unit(&self) -> MeasureUnit {
+        self.measure_unit
+    }
+    pub fn page_count(&self) -> usize {
+        self.item_sizes.len()
+    }
+    /// Logical pixels per PDF unit that pages are currently shown at.
+    pub fn effective_scale(&self) -> f32 {
+        self.layout_scale
+    }
+    /// Zoom level where `100` shows a PDF unit as one logical pixel.
+    pub fn zoom_percent(&self) -> f32 {
+        self.effective_scale() * 100.
+    }
+
+    /// Size of a page as read from the PDF, limited to plausible values.
+    fn measure_page(page: &Page, index: usize) -> (f32, f32) {
+        let reported = page.render_dimensions();
+        let clamped = pdf::clamp_page_dimensions(reported.0, reported.1);
+        if reported != clamped {
+            log::warn!(
+                "Page {} reports implausible dimensions {reported:?}, using {clamped:?}",
+                index + 1
+            );
+        }
+        clamped
+    }
+    /// Unscaled and unrotated size of each page. Only the first page is read when the pages
+    /// aren't known yet, the others are assumed to be the same size until
+    /// [`Self::measure_visible_pages`] reads them.
+    fn page_dimensions(&mut self, pdf: &Pdf) -> Rc<Vec<(f32, f32)>> {
+        let pages = pdf.pages();
+        if self.page_dimensions.len() != pages.len() {
+            let estimate = pages
+                .first()
+                .map_or((1., 1.), |page| Self::measure_page(page, 0));
+            self.page_dimensions = Rc::new(vec![estimate; pages.len()]);
+            self.measured_pages = vec![false; pages.len()];
+            if let Some(first) = self.measured_pages.first_mut() {
+                *first = true;
+            }
+        }
+        self.page_dimensions.clone()
+    }
+    /// Replace the estimated sizes of the pages in `range` with their real sizes, laying out the
+    /// pages again if any of them differed from the estimate.
+    fn measure_visible_pages(&mut self, range: Range<usize>, cx: &mut Context<Self>) {
+        let Some(pdf) = self.pdf_page_cache.pdf() else {
+            return;
+        };
+        let range =
+            range.start.min(self.measured_pages.len())..range.end.min(self.measured_pages.len());
+        if self.measured_pages[range.clone()]
+            .iter()
+            .all(|&measured| measured)
+        {
+            return;
+        }
+        let pages = pdf.pages();
+        let mut changed = false;
+        for index in range {
+            if std::mem::replace(&mut self.measured_pages[index], true) {
+                continue;
+            }
+            let Some(page) = pages.get(index) else {
+                continue;
+            };
+            let measured = Self::measure_page(page, index);
+            if self.page_dimensions[index] != measured {
+                Rc::make_mut(&mut self.page_dimensions)[index] = measured;
+                changed = true;
+            }
+        }
+        if !changed {
+            return;
+        }
+        let position = self.scroll_position();
+        let render_settings = self.layout_pages(&pdf);
+        self.pdf_page_cache.set_render_settings(render_settings);
+        self.set_scroll_position(position);
+        cx.notify();
+    }
+    fn rotated_page_dimensions(&mut self, pdf: &Pdf) -> Vec<(f32, f32)> {
+        let dimensions = self.page_dimensions(pdf);
+        let rotation = self.rotation.lock().unwrap();
+        dimensions
+            .iter()
+            .enumerate()
+            .map(|(index, &(width, height))| {
+                if rotation.page(index).is_sideways() {
+                    (height, width)
+                } else {
+                    (width, height)
+                }
+            })
+            .collect()
+    }
+
+    /// Logical pixels per PDF unit chosen by [`Self::fit_mode`] for pages with the given
+    /// unscaled dimensions.
+    fn fit_scale(&self, dimensions: &[(f32, f32)]) -> f32 {
+        let max_width = dimensions
+            .iter()
+            .map(|&(width, _)| width)
+            .fold(1., f32::max);
+        let max_height = dimensions
+            .iter()
+            .map(|&(_, height)| height)
+            .fold(1., f32::max);
+        // Two pages are shown next to each other:
+        let columns = if self.view_mode == ViewMode::TwoPage {
+            2.
+        } else {
+            1.
+        };
+        let width_scale = f32::from(self.viewport_size.width) / (max_width * columns);
+        let height_scale = f32::from(self.viewport_size.height) / max_height;
+        match self.fit_mode {
+            FitMode::Width | FitMode::EachWidth => width_scale,
+            FitMode::Height => height_scale,
+            FitMode::Page => width_scale.min(height_scale),
+            FitMode::Custom(scale) => scale,
+        }
+    }
+
+    /// Compute page sizes from [`Self::fit_mode`] and [`Self::zoom`] and return the render
+    /// settings that match them.
+    fn layout_pages(&mut self, pdf: &Pdf) -> RenderSettings2 {
+        let dimensions = self.rotated_page_dimensions(pdf);
+        let scale = self.fit_scale(&dimensions) * self.zoom;
+        self.layout_scale = scale;
+        self.page_scales = if self.fit_mode == FitMode::EachWidth {
+            // The scale fits the widest page so enlarge the others to the same width:
+            let max_width = dimensions
+                .iter()
+                .map(|&(width, _)| width)
+                .fold(1., f32::max);
+            dimensions
+                .iter()
+                .map(|&(width, _)| max_width / width.max(1.))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        self.pdf_page_cache
+            .set_page_scales(self.page_scales.clone());
+        self.item_sizes = Rc::new(
+            dimensions
+                .into_iter()
+                .enumerate()
+                .map(|(index, (width, height))| {
+                    let scale = self.page_scale(index);
+                    // Code adapted from `hayro::render`:
+                    let (width, height) = (width * scale, height * scale);
+
+                    size(px(width.floor()), px(height.floor()))
+                })
+                .collect::<Vec<_>>(),
+        );
+        self.layout_rows();
+        // Render at the display's resolution while the layout uses logical pixels:
+        RenderSettings {
+            x_scale: scale * self.scale_factor,
+            y_scale: scale * self.scale_factor,
+            ..Default::default()
+        }
+        .into()
+    }
+
+    /// Logical pixels per PDF unit that a page is shown at.
+    fn page_scale(&self, index: usize) -> f32 {
+        self.layout_scale * self.page_scales.get(index).copied().unwrap_or(1.)
+    }
+
+    /// Group the pages into the rows of the list according to [`Self::view_mode`].
+    fn layout_rows(&mut self) {
+        let page_count = self.item_sizes.len();
+        let rows = match self.view_mode {
+            ViewMode::Continuous => (0..page_count).map(|index| index..index + 1).collect(),
+            ViewMode::SinglePage if page_count == 0 => Vec::new(),
+            ViewMode::SinglePage => {
+                let index = self.single_page.min(page_count - 1);
+                std::iter::once(index..index + 1).collect()
+            }
+            ViewMode::TwoPage => {
+                let first_pair = if self.cover_page { 1 } else { 0 };
+                (0..first_pair.min(page_count))
+                    .map(|index| index..index + 1)
+                    .chain(
+                        (first_pair..page_count)
+                            .step_by(2)
+                            .map(|index| index..(index + 2).min(page_count)),
+                    )
+                    .collect::<Vec<_>>()
+            }
+        };
+        self.row_sizes = Rc::new(
+            rows.iter()
+                .enumerate()
+                .map(|(row, pages)| {
+                    let sizes = &self.item_sizes[pages.clone()];
+                    let gap = if row + 1 < rows.len() {
+                        self.page_gap
+                    } else {
+                        px(0.)
+                    };
+                    size(
+                        sizes.iter().fold(px(0.), |width, size| width + size.width)
+                            + self.cover_spacer(row).unwrap_or_default(),
+                        sizes
+                            .iter()
+                            .map(|size| size.height)
+                            .fold(px(0.), |a, b| if b > a { b } else { a })
+                            + gap,
+                    )
+                })
+                .collect(),
+        );
+        self.rows = Rc::new(rows);
+    }
+    /// Width of the empty space next to the cover page, the cover is the only page in the first
+    /// row but is shown on the right like in a book (or on the left when reading right to left).
+    fn cover_spacer(&self, row: usize) -> Option<Pixels> {
+        if row != 0 || self.view_mode != ViewMode::TwoPage || !self.cover_page {
+            return None;
+        }
+        self.item_sizes.first().map(|size| size.width)
+    }
+    /// Width of the widest row, larger than the viewport when zoomed in past the page width.
+    fn content_width(&self) -> Pixels {
+        self.row_sizes
+            .iter()
+            .map(|size| size.width)
+            .fold(px(0.), |a, b| if b > a { b } else { a })
+    }
+    /// Index of the row in [`Self::rows`] that shows a page.
+    fn row_of(&self, index: usize) -> Option<usize> {
+        let row = self.rows.partition_point(|pages| pages.end <= index);
+        self.rows
+            .get(row)
+            .is_some_and(|pages| pages.contains(&index))
+            .then_some(row)
+    }
+
+    pub fn view_mode(&self) -> ViewMode {
+        self.view_mode
+    }
+    /// Change how pages are arranged while staying on the current page.
+    pub fn set_view_mode(&mut self, view_mode: ViewMode, cx: &mut Context<Self>) {
+        if view_mode == self.view_mode {
+            return;
+        }
+        let page = self.current_page().unwrap_or(0);
+        self.view_mode = view_mode;
+        self.single_page = page;
+        if let Some(pdf) = self.pdf_page_cache.pdf() {
+            let render_settings = self.layout_pages(&pdf);
+            self.delayed_render = None;
+            self.pdf_page_cache.set_render_settings(render_settings);
+        }
+        self.smooth_scroll.reset(); // stop animations
+        self.scroll_handle
+            .set_offset(point(px(0.), -self.page_top(page)));
+        self.most_visible_page = Some(page);
+        cx.notify();
+    }
+    pub fn fit_mode(&self) -> FitMode {
+        self.fit_mode
+    }
+    /// The scale chosen for the document, not the one used while presenting.
+    pub fn tab_zoom(&self) -> TabZoom {
+        match &self.presentation {
+            Some(restore) => TabZoom {
+                fit_mode: restore.fit_mode,
+                zoom: restore.zoom,
+            },
+            None => TabZoom {
+                fit_mode: self.fit_mode,
+                zoom: self.zoom,
+            },
+        }
+    }
+    /// Change how pages are scaled, this also resets the zoom.
+    pub fn set_fit_mode(&mut self, fit_mode: FitMode, cx: &mut Context<Self>) {
+        if fit_mode == self.fit_mode && self.zoom == 1. {
+            return;
+        }
+        let page = self.current_page().unwrap_or(0);
+        self.fit_mode = fit_mode;
+        self.zoom = 1.;
+        if let Some(pdf) = self.pdf_page_cache.pdf() {
+            let render_settings = self.layout_pages(&pdf);
+            self.delayed_render = None;
+            self.pdf_page_cache.set_render_settings(render_settings);
+        }
+        self.smooth_scroll.reset(); // stop animations
+        self.scroll_handle
+            .set_offset(point(px(0.), -self.page_top(page)));
+        cx.notify();
+    }
+    pub fn on_action_fit_width(
+        &mut self,
+        _: &FitWidth,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.set_fit_mode(FitMode::Width, cx);
+    }
+    /// Switch between one scale for every page and fitting each page to the viewport's width.
+    pub fn on_action_toggle_per_page_fit(
+        &mut self,
+        _: &TogglePerPageFit,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let fit_mode = if self.fit_mode == FitMode::EachWidth {
+            FitMode::Width
+        } else {
+            FitMode::EachWidth
+        };
+        self.set_fit_mode(fit_mode, cx);
+    }
+    pub fn on_action_fit_height(
+        &mut self,
+        _: &FitHeight,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.set_fit_mode(FitMode::Height, cx);
+    }
+    pub fn on_action_fit_page(
+        &mut self,
+        _: &FitPage,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.set_fit_mode(FitMode::Page, cx);
+    }
+    pub fn on_action_actual_size(
+        &mut self,
+        _: &ActualSize,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.set_fit_mode(FitMode::Custom(1.), cx);
+    }
+    pub fn on_action_cycle_view_mode(
+        &mut self,
+        _: &CycleViewMode,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.set_view_mode(self.view_mode.next(), cx);
+    }
+
+    pub fn cover_page(&self) -> bool {
+        self.cover_page
+    }
+    /// Choose if the first page is shown alone in [`ViewMode::TwoPage`], comics usually look best
+    /// without this.
+    pub fn set_cover_page(&mut self, cover_page: bool, cx: &mut Context<Self>) {
+        if cover_page == self.cover_page {
+            return;
+        }
+        let page = self.current_page().unwrap_or(0);
+        self.cover_page = cover_page;
+        self.layout_rows();
+        if self.view_mode == ViewMode::TwoPage {
+            self.smooth_scroll.reset(); // stop animations
+            let x = self.scroll_handle.offset().x;
+            self.scroll_handle
+                .set_offset(point(x, -self.page_top(page)));
+        }
+        cx.notify();
+    }
+    pub fn on_action_toggle_cover_page(
+        &mut self,
+        _: &ToggleCoverPage,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.set_cover_page(!self.cover_page, cx);
+    }
+
+    /// Go to the next (or previous) page, or the next pair of pages in [`ViewMode::TwoPage`].
+    pub fn flip_page(&mut self, forward: bool, cx: &mut Context<Self>) {
+        let Some(current) = self.current_page() else {
+            return;
+        };
+        let step = |index: usize| {
+            if forward {
+                Some(index + 1)
+            } else {
+                index.checked_sub(1)
+            }
+        };
+        let target = if self.view_mode == ViewMode::SinglePage {
+            step(current)
+        } else {
+            step(self.row_of(current).unwrap_or(0))
+                .and_then(|row| self.rows.get(row))
+                .map(|pages| pages.start)
+        };
+        if let Some(target) = target.filter(|&target| target < self.page_count()) {
+            self.scroll_to_page(target, cx);
+        }
+    }
+    pub fn on_action_next_page(
+        &mut self,
+        _: &NextPage,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.flip_page(true, cx);
+    }
+    pub fn on_action_prev_page(
+        &mut self,
+        _: &PrevPage,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.flip_page(false, cx);
+    }
+
+    /// Flip pages in [`ViewMode::SinglePage`] when the mouse wheel keeps scrolling past the top
+    /// or bottom of the page.
+    fn flip_with_wheel(
+        &mut self,
+        event: &ScrollWheelEvent,
+        window: &Window,
+        cx: &mut Context<Self>,
+    ) {
+        let delta = f32::from(event.delta.pixel_delta(window.line_height()).y);
+        let offset = -self.scroll_handle.offset().y;
+        let max_offset = self.scroll_handle.max_offset().height;
+        let past_edge = (delta > 0. && offset <= px(0.)) || (delta < 0. && offset >= max_offset);
+        if !past_edge {
+            self.wheel_flip = 0.;
+            return;
+        }
+        self.wheel_flip += delta;
+        if self.wheel_flip.abs() >= WHEEL_FLIP_DISTANCE {
+            let forward = self.wheel_flip < 0.;
+            self.wheel_flip = 0.;
+            self.flip_page(forward, cx);
+        }
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+    /// Zoom relative to the scale chosen by [`Self::fit_mode`], this is clamped between [`MIN_ZOOM`] and
+    /// [`MAX_ZOOM`]. The part of the document at the top left of the viewport stays in place.
+    pub fn set_zoom(&mut self, zoom: f32, cx: &mut Context<Self>) {
+        self.set_zoom_at(zoom, Point::default(), false, cx);
+    }
+    /// Zoom so that the part of the document at `anchor` (relative to the viewport's top left
+    /// corner) stays in place. If `debounce` is `true` then pages are stretched for a while before
+    /// they are rendered at the new scale, this avoids rendering pages at every intermediate zoom
+    /// level of a scroll gesture.
+    pub fn set_zoom_at(
+        &mut self,
+        zoom: f32,
+        anchor: Point<Pixels>,
+        debounce: bool,
+        cx: &mut Context<Self>,
+    ) {
+        let zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        if zoom == self.zoom {
+            return;
+        }
+        let ratio = zoom / self.zoom;
+        self.zoom = zoom;
+        if let Some(pdf) = self.pdf_page_cache.pdf() {
+            let render_settings = self.layout_pages(&pdf);
+            if debounce {
+                self.delayed_render = Some(cx.spawn(async move |this, cx| {
+                    cx.background_executor().timer(ZOOM_RENDER_DELAY).await;
+                    _ = this.update(cx, |this, _cx| {
+                        this.pdf_page_cache.set_render_settings(render_settings);
+                    });
+                }));
+            } else {
+                self.delayed_render = None;
+                self.pdf_page_cache.set_render_settings(render_settings);
+            }
+        }
+        self.smooth_scroll.reset(); // stop animations
+
+        // The document position under the anchor is scaled along with the document:
+        let document_position = anchor - self.scroll_handle.offset();
+        let offset = anchor - document_position * ratio;
+        // The scroll handle's max offset is only updated once the list is painted again:
+        let max_x = (self.content_width() - self.viewport_size.width).max(px(0.));
+        self.scroll_handle
+            .set_offset(point(offset.x.clamp(-max_x, px(0.)), offset.y));
+        cx.notify();
+    }
+
+    /// Zoom with the mouse wheel while the control key is held.
+    fn on_zoom_scroll_wheel(&mut self, event: &ScrollWheelEvent, cx: &mut Context<Self>) {
+        let steps = match event.delta {
+            ScrollDelta::Lines(lines) => lines.y,
+            // Touchpads report many small pixel deltas:
+            ScrollDelta::Pixels(pixels) => f32::from(pixels.y) / 50.,
+        };
+        if steps == 0. {
+            return;
+        }
+        let anchor = event.position - self.scroll_handle.bounds().origin;
+        self.set_zoom_at(self.zoom * ZOOM_STEP.powf(steps), anchor, true, cx);
+    }
+
+    /// Size of a PDF unit in multiples of 1/72 inch, specified by the page's `/UserUnit` entry.
+    fn user_unit(&self, index: usize) -> f64 {
+        self.pdf_page_cache
+            .pdf()
+            .and_then(|pdf| pdf.pages().get(index)?.raw().get::<f32>(keys::USER_UNIT))
+            .map_or(1., f64::from)
+    }
+
+    fn on_page_mouse_down(
+        &mut self,
+        index: usize,
+        event: &MouseDownEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if matches!(
+            self.pointer_tool,
+            PointerTool::SelectTableRegion
+                | PointerTool::CopyImageRegion
+                | PointerTool::SaveImageRegion
+        ) {
+            self.region_drag = Some(RegionDrag {
+                page: index,
+                start: event.position,
+                end: event.position,
+            });
+            cx.stop_propagation();
+            cx.notify();
+        } else if self.pointer_tool == PointerTool::Measure {
+            let Some(point) = self.window_to_page(index, event.position) else {
+                return;
+            };
+            match &mut self.measurement {
+                Some(measurement) if measurement.page == index => measurement.points.push(point),
+                _ => {
+                    self.measurement = Some(Measurement {
+                        page: index,
+                        points: vec![point],
+                    })
+                }
+            }
+            cx.stop_propagation();
+            cx.notify();
+        } else if self.pointer_tool == PointerTool::Hand {
+            if self.smooth_scroll.is_animating() {
+                self.smooth_scroll.interrupt(&self.scroll_handle);
+            }
+            self.pan_drag = Some(event.position);
+            cx.stop_propagation();
+            cx.notify();
+        } else if self.follow_link(index, event.position, window, cx) {
+            cx.stop_propagation();
+        } else if event.click_count == 2 {
+            self.toggle_actual_size(index, event.position, cx);
+            cx.stop_propagation();
+        }
+    }
+    /// Switch between the scale chosen by [`Self::fit_mode`] and showing the page at `index` at its
+    /// actual size, keeping the part of the page at `position` (in window coordinates) in place.
+    fn toggle_actual_size(
+        &mut self,
+        index: usize,
+        position: Point<Pixels>,
+        cx: &mut Context<Self>,
+    ) {
+        let anchor = position - self.scroll_handle.bounds().origin;
+        if let Some((before, after)) = self.zoom_toggle.take()
+            && after == self.zoom
+        {
+            self.set_zoom_at(before, anchor, false, cx);
+            return;
+        }
+        let page_scale = self.page_scale(index);
+        let target = if (page_scale - 1.).abs() < 0.01 {
+            1. // already at actual size, so fit instead
+        } else {
+            self.zoom / page_scale
+        };
+        let before = self.zoom;
+        self.set_zoom_at(target, anchor, false, cx);
+        self.zoom_toggle = Some((before, self.zoom));
+    }
+    fn on_mouse_move(
+        &mut self,
+        event: &MouseMoveEvent,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(drag) = &mut self.region_drag {
+            drag.end = event.position;
+            cx.notify();
+        }
+        if let Some(last) = self.pan_drag.replace(event.position) {
+            let max = self.scroll_handle.max_offset();
+            let offset = self.scroll_handle.offset() + (event.position - last);
+            self.scroll_handle.set_offset(point(
+                offset.x.clamp(-max.width, px(0.)),
+                offset.y.clamp(-max.height, px(0.)),
+            ));
+            cx.notify();
+        }
+    }
+    fn on_mouse_up(&mut self, event: &MouseUpEvent, window: &mut Window, cx: &mut Context<Self>) {
+        if self.pan_drag.take().is_some() {
+            cx.notify();
+        }
+        let Some(drag) = self.region_drag.take() else {
+            return;
+        };
+        let region = self
+            .window_to_page(drag.page, drag.start)
+            .zip(self.window_to_page(drag.page, event.position))
+            .map(|(start, end)| kurbo::Rect::from_points(start, end));
+        let tool = self.pointer_tool;
+        self.set_pointer_tool(PointerTool::None, cx);
+        let Some(region) = region else {
+            return;
+        };
+        match tool {
+            PointerTool::SelectTableRegion => {
+                self.export_tables(drag.page, Some(region), window, cx)
+            }
+            PointerTool::CopyImageRegion => {
+                self.export_region_image(drag.page, region, false, window, cx)
+            }
+            PointerTool::SaveImageRegion => {
+                self.export_region_image(drag.page, region, true, window, cx)
+            }
+            PointerTool::None | PointerTool::Measure | PointerTool::Hand => {}
+        }
+    }
+
+    /// Render a region of a page as a PNG image and copy it to the clipboard or let the user save
+    /// it to a file.
+    fn export_region_image(
+        &self,
+        index: usize,
+        region: kurbo::Rect,
+        save_to_file: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(pdf) = self.pdf_page_cache.pdf() else {
+            return;
+        };
+        let Some(page) = pdf.pages().get(index) else {
+            return;
+        };
+        // At least the resolution shown on screen but sharper when zoomed out:
+        let (width, height) = pdf::page_dimensions(page);
+        let scale = (self.page_scale(index) * window.scale_factor())
+            .max(MIN_REGION_IMAGE_SCALE)
+            .min(MAX_EXPORT_DIMENSION / width.max(height))
+            // The layout uses clamped page dimensions but the region is in the page's own units:
+            * pdf::page_dimension_scale(page);
+        let png = cx.background_spawn(async move {
+            pdf::render_region_png(
+                &pdf.pages()[index],
+                &InterpreterSettings::default(),
+                scale,
+                region,
+            )
+        });
+        let file_name = format!("page-{}.png", index + 1);
+        let save_prompt = save_to_file
+            .then(|| prompt_save_file(Some(&NoDisplayHandle(&*window)), "Save image", &file_name));
+        cx.spawn(async move |_this, cx| {
+            let Some(png) = png.await else {
+                log::warn!("Selected region is empty");
+                return;
+            };
+            match save_prompt {
+                Some(prompt) => {
+                    if let Some(file) = prompt.await
+                        && let Err(e) = file.write(&png).await
+                    {
+                        log::error!("Failed to save image: {e}");
+                    }
+                }
+                None => {
+                    let image = gpui::Image::from_bytes(gpui::ImageFormat::Png, png);
+                    _ = cx.update(|cx| cx.write_to_clipboard(ClipboardItem::new_image(&image)));
+                    log::info!("Copied region of page {} to the clipboard", index + 1);
+                }
+            }
+        })
+        .detach();
+    }
+
+    /// Follow the link at `position` on page `index`, returns `false` if there is no link there.
+    fn follow_link(
+        &mut self,
+        index: usize,
+        position: Point<Pixels>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        let (Some(point), Some(pdf)) = (
+            self.window_to_page(index, position),
+            self.pdf_page_cache.pdf(),
+        ) else {
+            return false;
+        };
+        let Some(link) = links::page_links(&pdf, index)
+            .into_iter()
+            .find(|link| link.rect.contains(point))
+        else {
+            return false;
+        };
+        match link.action {
+            LinkAction::GoTo { page } => self.scroll_to_page(page, cx),
+            LinkAction::Uri(uri) => match self.link_opening {
+                LinkOpening::Open => cx.open_url(&uri),
+                LinkOpening::Confirm => {
+                    let answer = window.prompt(
+                        PromptLevel::Warning,
+                        "Open link in your browser?",
+                        Some(&uri),
+                        &["Open", "Cancel"],
+                        cx,
+                    );
+                    cx.spawn(async move |_this, cx| {
+                        if answer.await == Ok(0) {
+                            _ = cx.update(|cx| cx.open_url(&uri));
+                        }
+                    })
+                    .detach();
+                }
+                LinkOpening::Block => log::info!("Blocked link to {uri:?}"),
+            },
+            LinkAction::Suspicious(action) => {
+                log::warn!("Refused to follow a potentially dangerous link: {action:?}");
+                // Only informs the user, so the answer isn't needed:
+                _ = window.prompt(
+                    PromptLevel::Critical,
+                    "This link was blocked",
+                    Some(&format!(
+                        "The document tried to run a potentially dangerous action: {action}"
+                    )),
+                    &["OK"],
+                    cx,
+                );
+            }
+            LinkAction::Unsupported(kind) => {
+                log::warn!("Ignored link with unsupported action {kind:?}")
+            }
+        }
+        true
+    }
+
+    /// Scroll `other` to the same page whenever these pages are scrolled, `None` to stop.
+    pub fn set_scroll_sync(&mut self, other: Option<WeakEntity<PdfPages>>) {
+        self.scroll_sync = other;
+        self.synced_position = None;
+    }
+    /// Tell [`Self::scroll_sync`] if these pages were scrolled since the last frame.
+    fn sync_scroll(&mut self, cx: &mut Context<Self>) {
+        let Some(other) = self.scroll_sync.clone() else {
+            return;
+        };
+        if self.rows.is_empty() {
+            return;
+        }
+        let position = self.scroll_position();
+        let Some(synced) = self.synced_position.replace(position) else {
+            // Just followed the other pages, sending this back could move them a bit:
+            return;
+        };
+        if synced.page_index == position.page_index
+            && (synced.offset - position.offset).abs() < 1e-3
+        {
+            return;
+        }
+        cx.defer(move |cx| {
+            _ = other.update(cx, |other, cx| other.follow_scroll(position, cx));
+        });
+    }
+    /// Scroll to where the pages in [`Self::scroll_sync`] were scrolled to.
+    fn follow_scroll(&mut self, position: ScrollPosition, cx: &mut Context<Self>) {
+        self.synced_position = None;
+        // Stay at the end of a shorter document instead of jumping back to its last page:
+        if position.page_index < self.page_count() {
+            self.smooth_scroll.reset();
+            self.set_scroll_position(position);
+        }
+        cx.notify();
+    }
+
+    /// Scroll so that the top of the page at `index` is at the top of the viewport.
+    pub fn scroll_to_page(&mut self, index: usize, cx: &mut Context<Self>) {
+        self.show_page(index);
+        let top = self.page_top(index);
+        let x = self.scroll_handle.offset().x;
+        self.smooth_scroll
+            .animate_to(&self.scroll_handle, point(x, -top));
+        cx.notify();
+    }
+
+    /// Distance from the top of the document to the top of the page at `index`.
+    fn page_top(&self, index: usize) -> Pixels {
+        let Some(row) = self.row_of(index) else {
+            return px(0.);
+        };
+        self.row_sizes[..row]
+            .iter()
+            .fold(px(0.), |top, size| top + size.height)
+    }
+
+    /// The current scroll position relative to the page at the top of the viewport.
+    fn scroll_position(&self) -> ScrollPosition {
+        let offset = -self.scroll_handle.offset().y;
+        let mut row_top = px(0.);
+        for (pages, size) in self.rows.iter().zip(self.row_sizes.iter()) {
+            let row_bottom = row_top + size.height;
+            if offset < row_bottom {
+                return ScrollPosition {
+                    page_index: pages.start,
+                    offset: (f32::from(offset - row_top) / f32::from(size.height).max(1.))
+                        .clamp(0., 1.),
+                };
+            }
+            row_top = row_bottom;
+        }
+        // Scrolled past the end or there are no pages:
+        ScrollPosition {
+            page_index: self.rows.last().map_or(0, |pages| pages.start),
+            offset: if self.rows.is_empty() { 0. } else { 1. },
+        }
+    }
+    /// Scroll to a position that was returned by [`Self::scroll_position`], possibly with a
+    /// different layout.
+    fn set_scroll_position(&mut self, position: ScrollPosition) {
+        let page_index = position
+            .page_index
+            .min(self.item_sizes.len().saturating_sub(1));
+        self.show_page(page_index);
+        let height = self
+            .row_of(page_index)
+            .map_or(px(0.), |row| self.row_sizes[row].height);
+        let top = self.page_top(page_index) + height * position.offset;
+        self.scroll_handle.set_offset(point(px(0.), -top));
+    }
+
+    /// Make the page at `index` part of the list, this is only needed in [`ViewMode::SinglePage`]
+    /// where the list only contains the shown page.
+    fn show_page(&mut self, index: usize) {
+        if self.view_mode != ViewMode::SinglePage || self.single_page == index {
+            return;
+        }
+        self.single_page = index;
+        self.layout_rows();
+        self.smooth_scroll.reset(); // stop animations
+        self.scroll_handle.set_offset(Point::default());
+    }
+
+    /// Convert unscaled page coordinates to coordinates within the page's element.
+    fn page_rect_to_local(
+        &self,
+        index: usize,
+        rect: kurbo::Rect,
+        page_size: Size<Pixels>,
+    ) -> Option<Bounds<Pixels>> {
+        let a = self.page_to_local(index, kurbo::Point::new(rect.x0, rect.y0), page_size)?;
+        let b = self.page_to_local(index, kurbo::Point::new(rect.x1, rect.y1), page_size)?;
+        // Rotation can swap which corner is the top left one:
+        Some(Bounds::from_corners(a.min(&b), a.max(&b)))
+    }
+
+    /// Find `query` in the document and scroll to the first match, or to the next match if
+    /// `query` was already searched for. The document's text is extracted in the background the
+    /// first time this is called.
+    pub fn search(&mut self, query: String, cx: &mut Context<Self>) {
+        if self
+            .search
+            .as_ref()
+            .is_some_and(|search| search.query == query)
+        {
+            self.find_next(true, cx);
+            return;
+        }
+        let Some(text_index) = self.text_index.clone() else {
+            let Some(pdf) = self.pdf_page_cache.pdf() else {
+                return;
+            };
+            // Only the latest query is searched for once the text is extracted:
+            self.cancel_text_index_job(cx);
+            let (cancel, progress) = self.start_job("Extracting text", cx);
+            let build = cx.background_spawn({
+                let cancel = cancel.clone();
+                let progress = progress.clone();
+                async move {
+                    TextIndex::build(
+                        &pdf,
+                        &InterpreterSettings::default(),
+                        &cancel,
+                        &mut |update| *progress.lock().unwrap() = update,
+                    )
+                }
+            });
+            let task = cx.spawn({
+                let progress = progress.clone();
+                async move |this, cx| {
+                    let text_index = build.await;
+                    _ = this.update(cx, |this, cx| {
+                        this.finish_job(&progress, cx);
+                        this.text_index_job = None;
+                        match text_index {
+                            Ok(text_index) => {
+                                this.text_index = Some(Arc::new(text_index));
+                                this.search(query, cx);
+                            }
+                            Err(Cancelled) => cx.notify(),
+                        }
+                    });
+                }
+            });
+            self.text_index_job = Some((cancel, progress, task));
+            return;
+        };
+        let hits = text_index.search(&query);
+        if hits.is_empty() {
+            log::info!("No matches for {query:?}");
+        }
+        if let Some(hit) = hits.first() {
+            self.scroll_to_hit(hit, cx);
+        }
+        self.search = Some(SearchResults {
+            query,
+            hits,
+            current_match: 0,
+        });
+        cx.notify();
+    }
+
+    /// Scroll to the next (or previous) search match.
+    pub fn find_next(&mut self, forward: bool, cx: &mut Context<Self>) {
+        let Some(hit) = self
+            .search
+            .as_mut()
+            .and_then(|search| search.step(forward))
+            .cloned()
+        else {
+            return;
+        };
+        self.scroll_to_hit(&hit, cx);
+    }
+
+    /// Short description of the search results, like "3 of 27".
+    pub fn search_status(&self) -> Option<String> {
+        if let Some((_, progress, _)) = &self.text_index_job {
+            let Progress { done, total } = *progress.lock().unwrap();
+            if total == 0 {
+                return Some("Searching...".to_owned());
+            }
+            return Some(format!(
+                "Searching page {} of {total}",
+                (done + 1).min(total)
+            ));
+        }
+        let search = self.search.as_ref()?;
+        Some(match search.current_index() {
+            Some(index) => format!("{} of {}", index + 1, search.hits.len()),
+            None => "No matches".to_owned(),
+        })
+    }
+
+    pub fn on_action_find_next(
+        &mut self,
+        _: &FindNext,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.find_next(true, cx);
+    }
+    pub fn on_action_find_previous(
+        &mut self,
+        _: &FindPrevious,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.find_next(false, cx);
+    }
+
+    /// Stop extracting the document's text for a search that hasn't finished yet.
+    pub fn cancel_text_index_job(&mut self, cx: &mut Context<Self>) {
+        if let Some((cancel, progress, _task)) = self.text_index_job.take() {
+            cancel.cancel();
+            self.finish_job(&progress, cx);
+            cx.notify();
+        }
+    }
+
+    /// Forget the search results and the extracted text, used when the document changes.
+    pub fn clear_search(&mut self, cx: &mut Context<Self>) {
+        self.cancel_text_index_job(cx);
+        self.text_index = None;
+        self.search = None;
+        cx.notify();
+    }
+
+    /// Scroll so that a search match is in the upper part of the viewport.
+    fn scroll_to_hit(&mut self, hit: &SearchHit, cx: &mut Context<Self>) {
+        let Some(page_size) = self.item_sizes.get(hit.page).copied() else {
+            return;
+        };
+        let Some(local) = self.page_rect_to_local(hit.page, hit.rect, page_size) else {
+            return;
+        };
+        self.show_page(hit.page);
+        let viewport_height = self.scroll_handle.bounds().size.height;
+        let top = self.page_top(hit.page) + local.origin.y - viewport_height / 3.;
+        let x = self.scroll_handle.offset().x;
+        self.smooth_scroll
+            .animate_to(&self.scroll_handle, point(x, -top));
+        cx.notify();
+    }
+
+    /// The element for a single page. Until `page_image` has an image the page is shown as a blank
+    /// page if no worker was asked to render it yet, with a spinner while it is being rendered or
+    /// with the error if rendering failed.
+    fn render_page(&self, index: usize, page_image: PageSlot, cx: &Context<Self>) -> AnyElement {
+        let page_size = self.item_sizes.get(index).copied().unwrap_or_default();
+        let page_element = match page_image {
+            PageSlot::Image(page_image) => img(weak_image(
+                &page_image,
+                index,
+                self.disabled_cache.read(cx).diagnostics,
+            ))
+            // Letterbox instead of cropping when the image's aspect ratio doesn't match the
+            // layout size:
+            .object_fit(ObjectFit::Contain)
+            .image_cache(&self.disabled_cache)
+            // The image resolution can differ from the layout size (fixed width rendering or
+            // reduced to fit within pdf::MAX_PIXMAP_DIMENSION):
+            .w(page_size.width)
+            .h(page_size.height)
+            .into_any_element(),
+            PageSlot::Failed(e) => v_flex()
+                .size_full()
+                .items_center()
+                .justify_center()
+                .bg(cx.theme().muted)
+                .text_color(cx.theme().danger)
+                .child(format!("Failed to render page {}: {e}", index + 1))
+                .into_any_element(),
+            PageSlot::Rendering => v_flex()
+                .size_full()
+                .items_center()
+                .justify_center()
+                .bg(cx.theme().background)
+                .child(Indicator::new().large().color(cx.theme().muted_foreground))
+                .into_any_element(),
+            PageSlot::NotRequested => div()
+                .size_full()
+                .bg(cx.theme().background)
+                .into_any_element(),
+        };
+        div()
+            .relative()
+            .w(page_size.width)
+            .h(page_size.height)
+            .on_mouse_down(
+                MouseButton::Left,
+                cx.listener(move |view, event, window, cx| {
+                    view.on_page_mouse_down(index, event, window, cx)
+                }),
+            )
+            .child(page_element)
+            .child({
+                // Remember where the page is so that mouse positions can be converted to page
+                // coordinates:
+                let page_bounds = self.page_bounds.clone();
+                canvas(
+                    move |bounds, _window, _cx| {
+                        page_bounds.borrow_mut().insert(index, bounds);
+                    },
+                    |_, _, _, _| {},
+                )
+                .absolute()
+                .size_full()
+            })
+            .children(self.render_search_overlay(index, page_size))
+            .children(self.render_region_overlay(index, cx))
+            .children(self.render_measurement_overlay(index, page_size, cx))
+            .into_any_element()
+    }
+
+    /// Marks along the scrollbar's track where each row of pages starts, the current page's mark is
+    /// longer and highlighted.
+    fn render_scrollbar_ticks(&self, cx: &Context<Self>) -> Option<Div> {
+        let total = self
+            .row_sizes
+            .iter()
+            .map(|size| f32::from(size.height))
+            .sum::<f32>();
+        let track = f32::from(self.scroll_handle.bounds().size.height);
+        if self.row_sizes.len() < 2 || total <= track {
+            return None; // nothing to scroll
+        }
+        let current_row = self.current_page().and_then(|page| self.row_of(page));
+        let mut ticks = Vec::new();
+        let mut row_top = 0.;
+        let mut last_tick = 0.;
+        for (row, size) in self.row_sizes.iter().enumerate() {
+            let y = row_top / total * track;
+            row_top += f32::from(size.height);
+            let current = current_row == Some(row);
+            if row == 0 || !(current || y - last_tick >= MIN_SCROLLBAR_TICK_SPACING) {
+                continue;
+            }
+            last_tick = y;
+            ticks.push(
+                div()
+                    .absolute()
+                    .top(px(y))
+                    .right_0()
+                    .h(px(1.))
+                    .when(current, |this| this.w(px(10.)).bg(cx.theme().primary))
+                    .when(!current, |this| {
+                        this.w(px(5.)).bg(cx.theme().muted_foreground.opacity(0.5))
+                    }),
+            );
+        }
+        Some(
+            div()
+                .absolute()
+                .top_0()
+                .bottom_0()
+                .right_0()
+                .w(px(10.))
+                .children(ticks),
+        )
+    }
+
+    /// Highlight the search matches on a page, the current match uses a stronger color.
+    fn render_search_overlay(&self, index: usize, page_size: Size<Pixels>) -> Vec<Div> {
+        let Some(search) = &self.search else {
+            return Vec::new();
+        };
+        let current = search.current_index();
+        search
+            .hits_on_page(index)
+            .filter_map(|hit_index| {
+                let hit = &search.hits[hit_index];
+                // Text rectangles are in device space so the y-axis already points downwards:
+                let bounds = self.page_rect_to_local(index, hit.rect, page_size)?;
+                Some(
+                    div()
+                        .absolute()
+                        .left(bounds.origin.x)
+                        .top(bounds.origin.y)
+                        .w(bounds.size.width)
+                        .h(bounds.size.height)
+                        .bg(if Some(hit_index) == current {
+                            gpui::rgba(0xff8c0080)
+                        } else {
+                            gpui::rgba(0xffd40059)
+                        }),
+                )
+            })
+            .collect()
+    }
+
+    /// Highlight the region that is being selected.
+    fn render_region_overlay(&self, index: usize, cx: &Context<Self>) -> Option<Div> {
+        let drag = self.region_drag.filter(|drag| drag.page == index)?;
+        let origin = self.page_bounds.borrow().get(&index)?.origin;
+        let bounds = Bounds::from_corners(
+            drag.start.min(&drag.end) - origin,
+            drag.start.max(&drag.end) - origin,
+        );
+        Some(
+            div()
+                .absolute()
+                .left(bounds.origin.x)
+                .top(bounds.origin.y)
+                .w(bounds.size.width)
+                .h(bounds.size.height)
+                .border_1()
+                .border_color(cx.theme().primary)
+                .bg(cx.theme().primary.opacity(0.1)),
+        )
+    }
+
+    /// Draw lines between the measured points and a label with the result.
+    fn render_measurement_overlay(
+        &self,
+        index: usize,
+        page_size: Size<Pixels>,
+        cx: &Context<Self>,
+    ) -> Option<AnyElement> {
+        let measurement = self.measurement.as_ref().filter(|m| m.page == index)?;
+        let mut points = measurement
+            .points
+            .iter()
+            .map(|&p| self.page_to_local(index, p, page_size))
+            .collect::<Option<Vec<_>>>()?;
+        let label_at = *points.last()?;
+        if points.len() >= 3 {
+            // Close the polygon:
+            points.push(points[0]);
+        }
+        let color = cx.theme().primary;
+        let label = measurement.label(self.measure_unit, self.user_unit(index), self.measure_scale);
+
+        Some(
+            div()
+                .absolute()
+                .size_full()
+                .child(
+                    canvas(
+                        |_, _, _| {},
+                        move |bounds, (), window, _cx| {
+                            let mut builder = PathBuilder::stroke(px(2.));
+                            for (i, &p) in points.iter().enumerate() {
+                                if i == 0 {
+                                    builder.move_to(bounds.origin + p);
+                                } else {
+                                    builder.line_to(bounds.origin + p);
+                                }
+                            }
+                            if let Ok(path) = builder.build() {
+                                window.paint_path(path, color);
+                            }
+                        },
+                    )
+                    .size_full(),
+                )
+                .child(
+                    div()
+                        .absolute()
+                        .left(label_at.x + px(8.))
+                        .top(label_at.y + px(8.))
+                        .px_1()
+                        .rounded(cx.theme().radius)
+                        .bg(cx.theme().popover)
+                        .text_color(cx.theme().popover_foreground)
+                        .text_xs()
+                        .whitespace_nowrap()
+                        .child(label),
+                )
+                .into_any_element(),
+        )
+    }
+
+    /// Find tables on a page and let the user save them as a CSV file.
+    fn export_tables(
+        &self,
+        index: usize,
+        region: Option<kurbo::Rect>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(pdf) = self.pdf_page_cache.pdf() else {
+            return;
+        };
+        let Some(page) = pdf.pages().get(index) else {
+            return;
+        };
+        let tables = tables::detect_tables(page, &InterpreterSettings::default(), region);
+        if tables.is_empty() {
+            log::info!("Found no tables on page {}", index + 1);
+            return;
+        }
+        log::info!("Found {} tables on page {}", tables.len(), index + 1);
+        // Separate tables with an empty line:
+        let csv = tables
+            .iter()
+            .map(tables::Table::to_csv)
+            .collect::<Vec<_>>()
+            .join("\n");
+        let prompt = prompt_save_file(
+            Some(&NoDisplayHandle(&*window)),
+            "Export tables",
+            &format!("page-{}-tables.csv", index + 1),
+        );
+        cx.spawn(async move |_this, _cx| {
+            if let Some(file) = prompt.await
+                && let Err(e) = file.write(csv.as_bytes()).await
+            {
+                log::error!("Failed to save tables: {e}");
+            }
+        })
+        .detach();
+    }
+
+    /// Show progress for an operation on every page, this cancels any earlier operation.
+    fn start_job(
+        &mut self,
+        label: &'static str,
+        cx: &mut Context<Self>,
+    ) -> (CancellationToken, Arc<Mutex<Progress>>) {
+        let cancel = CancellationToken::new();
+        let progress = Arc::new(Mutex::new(Progress::default()));
+        let refresh = cx.spawn(async move |this, cx| {
+            loop {
+                cx.background_executor().timer(JOB_REFRESH_INTERVAL).await;
+                if this.update(cx, |_this, cx| cx.notify()).is_err() {
+                    break;
+                }
+            }
+        });
+        let previous = self.job.replace(PageJob {
+            label,
+            progress: progress.clone(),
+            cancel: cancel.clone(),
+            _refresh: refresh,
+        });
+        if let Some(previous) = previous {
+            previous.cancel.cancel();
+        }
+        cx.notify();
+        (cancel, progress)
+    }
+    /// Hide the progress of a job unless it was already replaced by another job.
+    fn finish_job(&mut self, progress: &Arc<Mutex<Progress>>, cx: &mut Context<Self>) {
+        if self
+            .job
+            .as_ref()
+            .is_some_and(|job| Arc::ptr_eq(&job.progress, progress))
+        {
+            self.job = None;
+            cx.notify();
+        }
+    }
+
+    /// Diagnostics for reports of high memory usage, refreshed every frame.
+    fn render_cache_stats(&self, cx: &Context<Self>) -> Option<impl IntoElement> {
+        if !self.show_cache_stats {
+            return None;
+        }
+        let megabytes = |bytes: usize| format!("{:.1} MB", bytes as f64 / (1024. * 1024.));
+        let stats = self.pdf_page_cache.stats();
+        let thumbnails = self.thumbnail_cache.stats();
+        let budget = stats
+            .memory_budget
+            .map_or_else(|| "unlimited".to_owned(), megabytes);
+        Some(
+            v_flex()
+                .absolute()
+                .top_3()
+                .left_3()
+                .px_2()
+                .py_1()
+                .rounded(cx.theme().radius)
+                .bg(cx.theme().popover)
+                .border_1()
+                .border_color(cx.theme().border)
+                .text_xs()
+                .font_family("monospace")
+                .child(format!(
+                    "Cached pages: {} ({}, budget {budget})",
+                    stats.images,
+                    megabytes(stats.image_bytes)
+                ))
+                .child(format!("Placeholders: {}", stats.placeholders))
+                .child(format!("Retained: {}", stats.retained))
+                .child(format!("Rendered: {}", stats.rendered_pages))
+                .child(format!(
+                    "Textures: {} uploaded, {} dropped",
+                    stats.uploaded_textures, stats.dropped_textures
+                ))
+                .child(format!(
+                    "Deduplicated: {}",
+                    megabytes(stats.deduplicated_bytes)
+                ))
+                .child(format!(
+                    "Thumbnails: {} ({})",
+                    thumbnails.images,
+                    megabytes(thumbnails.image_bytes)
+                )),
+        )
+    }
+    fn render_job_progress(&self, cx: &Context<Self>) -> Option<impl IntoElement> {
+        let job = self.job.as_ref()?;
+        let Progress { done, total } = *job.progress.lock().unwrap();
+        Some(
+            h_flex()
+                .absolute()
+                .top_3()
+                .right_5()
+                .gap_2()
+                .px_2()
+                .py_1()
+                .rounded(cx.theme().radius)
+                .bg(cx.theme().popover)
+                .border_1()
+                .border_color(cx.theme().border)
+                .child(format!("{} {done} / {total}", job.label))
+                .child(
+                    Button::new("cancel-job")
+                        .ghost()
+                        .label("Cancel")
+                        .on_click(cx.listener(|this, _, _window, cx| {
+                            if let Some(job) = this.job.take() {
+                                job.cancel.cancel();
+                            }
+                            cx.notify();
+                        })),
+                ),
+        )
+    }
+
+    /// Let the user save the text of every page as a UTF-8 text file.
+    pub fn export_text(&mut self, file_name: &str, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(pdf) = self.pdf_page_cache.pdf() else {
+            return;
+        };
+        let prompt = prompt_save_text_file(Some(&NoDisplayHandle(&*window)), file_name);
+        cx.spawn_in(window, async move |this, cx| {
+            let Some(file) = prompt.await else {
+                return;
+            };
+            let Ok((cancel, progress)) =
+                this.update(cx, |this, cx| this.start_job("Exporting text", cx))
+            else {
+                return;
+            };
+            let pages = cx
+                .background_executor()
+                .spawn({
+                    let progress = progress.clone();
+                    async move {
+                        pdf::extract_document_text(
+                            &pdf,
+                            &InterpreterSettings::default(),
+                            &cancel,
+                            &mut |update| *progress.lock().unwrap() = update,
+                        )
+                    }
+                })
+                .await;
+            _ = this.update(cx, |this, cx| this.finish_job(&progress, cx));
+            let Ok(pages) = pages else {
+                log::info!("Cancelled text export");
+                return;
+            };
+            let text = pages
+                .iter()
+                .enumerate()
+                .map(|(index, text)| format!("--- Page {} ---\n{}\n", index + 1, text.trim()))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let (severity, message) = match file.write(text.as_bytes()).await {
+                Ok(()) => (
+                    Severity::Info,
+                    format!("Exported the text of {} pages", pages.len()),
+                ),
+                Err(e) => (Severity::Error, format!("Failed to save text: {e}")),
+            };
+            _ = cx.update(|window, cx| notify_user(severity, message, window, cx));
+        })
+        .detach();
+    }
+
+    /// Let the user pick a file name and save every image in the document as numbered PNG files
+    /// next to it (`photo.png` becomes `photo-1.png`, `photo-2.png` and so on).
+    pub fn export_images(&mut self, file_name: &str, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(pdf) = self.pdf_page_cache.pdf() else {
+            return;
+        };
+        let prompt = prompt_save_file(Some(&NoDisplayHandle(&*window)), "Export images", file_name);
+        cx.spawn_in(window, async move |this, cx| {
+            let Some(file) = prompt.await else {
+                return;
+            };
+            let path = file.path().to_owned();
+            let Ok((cancel, progress)) =
+                this.update(cx, |this, cx| this.start_job("Exporting images", cx))
+            else {
+                return;
+            };
+            let saved = cx
+                .background_executor()
+                .spawn({
+                    let progress = progress.clone();
+                    async move {
+                        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                        let mut saved = 0;
+                        for_each_page(
+                            &pdf,
+                            &cancel,
+                            &mut |update| *progress.lock().unwrap() = update,
+                            |index, page| {
+                                for image in
+                                    pdf::extract_images(page, &InterpreterSettings::default())
+                                {
+                                    let target =
+                                        path.with_file_name(format!("{stem}-{}.png", saved + 1));
+                                    let result = image
+                                        .to_png()
+                                        .map_err(anyhow::Error::from)
+                                        .and_then(|png| Ok(std::fs::write(&target, png)?));
+                                    match result {
+                                        Ok(()) => saved += 1,
+                                        Err(e) => log::error!(
+                                            "Failed to save image from page {}: {e}",
+                                            index + 1
+                                        ),
+                                    }
+                                }
+                            },
+                        )
+                        .map(|_| saved)
+                    }
+                })
+                .await;
+            _ = this.update(cx, |this, cx| this.finish_job(&progress, cx));
+            let message = match saved {
+                Ok(0) => "Found no images to export".to_owned(),
+                Ok(saved) => format!("Exported {saved} images"),
+                Err(_) => {
+                    log::info!("Cancelled image export");
+                    return;
+                }
+            };
+            _ = cx.update(|window, cx| notify_user(Severity::Info, message, window, cx));
+        })
+        .detach();
+    }
+
+    /// Render `pages` as PNG images at `dpi`. A single page is saved to a file that the user picks,
+    /// a range is saved as `{stem}-001.png`, `{stem}-002.png` and so on into a chosen folder.
+    pub fn export_pages(
+        &mut self,
+        pages: Range<usize>,
+        dpi: f32,
+        stem: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(pdf) = self.pdf_page_cache.pdf() else {
+            return;
+        };
+        let single_page = pages.len() == 1;
+        let parent = NoDisplayHandle(&*window);
+        let prompt: Pin<Box<dyn Future<Output = Option<PathBuf>>>> = if single_page {
+            let prompt = prompt_save_file(
+                Some(&parent),
+                "Export page",
+                &format!("{stem}-{}.png", pages.start + 1),
+            );
+            Box::pin(async move { prompt.await.map(|file| file.path().to_owned()) })
+        } else {
+            let prompt = prompt_pick_folder(Some(&parent), "Export pages to folder");
+            Box::pin(async move { prompt.await.map(|folder| folder.path().to_owned()) })
+        };
+        let stem = stem.to_owned();
+        cx.spawn_in(window, async move |this, cx| {
+            let Some(target) = prompt.await else {
+                return;
+            };
+            let Ok((cancel, progress)) =
+                this.update(cx, |this, cx| this.start_job("Exporting pages", cx))
+            else {
+                return;
+            };
+            let saved = cx
+                .background_executor()
+                .spawn({
+                    let progress = progress.clone();
+                    let total = pages.len();
+                    async move {
+                        let mut saved = 0;
+                        for (done, index) in pages.enumerate() {
+                            if cancel.is_cancelled() {
+                                return Err(Cancelled);
+                            }
+                            let Some(page) = pdf.pages().get(index) else {
+                                break;
+                            };
+                            let png = pdf::render_page_png(
+                                page,
+                                &InterpreterSettings::default(),
+                                dpi / 72.,
+                            );
+                            let path = if single_page {
+                                target.clone()
+                            } else {
+                                target.join(format!("{stem}-{:03}.png", index + 1))
+                            };
+                            match std::fs::write(&path, png) {
+                                Ok(()) => saved += 1,
+                                Err(e) => log::error!(
+                                    "Failed to save page {} to {}: {e}",
+                                    index + 1,
+                                    path.display()
+                                ),
+                            }
+                            *progress.lock().unwrap() = Progress {
+                                done: done + 1,
+                                total,
+                            };
+                        }
+                        Ok(saved)
+                    }
+                })
+                .await;
+            _ = this.update(cx, |this, cx| this.finish_job(&progress, cx));
+            match saved {
+                Ok(saved) => {
+                    let message = format!("Exported {saved} pages at {dpi} DPI");
+                    _ = cx.update(|window, cx| notify_user(Severity::Info, message, window, cx));
+                }
+                Err(Cancelled) => log::info!("Cancelled page export"),
+            }
+        })
+        .detach();
+    }
+
+    pub fn on_action_export_tables(
+        &mut self,
+        _: &ExportTables,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(index) = self.current_page() {
+            self.export_tables(index, None, window, cx);
+        }
+    }
+    pub fn on_action_copy_image_region(
+        &mut self,
+        _: &CopyImageRegion,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.toggle_pointer_tool(PointerTool::CopyImageRegion, cx);
+    }
+    pub fn on_action_save_image_region(
+        &mut self,
+        _: &SaveImageRegion,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.toggle_pointer_tool(PointerTool::SaveImageRegion, cx);
+    }
+    /// Start using `tool` or stop using it if it is already active.
+    fn toggle_pointer_tool(&mut self, tool: PointerTool, cx: &mut Context<Self>) {
+        let tool = if self.pointer_tool == tool {
+            PointerTool::None
+        } else {
+            tool
+        };
+        self.set_pointer_tool(tool, cx);
+    }
+    pub fn on_action_select_table_region(
+        &mut self,
+        _: &SelectTableRegion,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.toggle_pointer_tool(PointerTool::SelectTableRegion, cx);
+    }
+
+    pub fn on_action_toggle_measure_tool(
+        &mut self,
+        _: &ToggleMeasureTool,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.toggle_pointer_tool(PointerTool::Measure, cx);
+    }
+    pub fn on_action_toggle_hand_tool(
+        &mut self,
+        _: &ToggleHandTool,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.toggle_pointer_tool(PointerTool::Hand, cx);
+    }
+    pub fn on_action_cycle_measure_unit(
+        &mut self,
+        _: &CycleMeasureUnit,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.measure_unit = self.measure_unit.next();
+        cx.notify();
+    }
+
+    pub fn on_action_zoom_in(&mut self, _: &ZoomIn, _window: &mut Window, cx: &mut Context<Self>) {
+        self.set_zoom(self.zoom * ZOOM_STEP, cx);
+    }
+    pub fn on_action_zoom_out(
+        &mut self,
+        _: &ZoomOut,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.set_zoom(self.zoom / ZOOM_STEP, cx);
+    }
+    pub fn on_action_reset_zoom(
+        &mut self,
+        _: &ResetZoom,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.set_zoom(1., cx);
+    }
+
+    pub fn on_action_first_page(
+        &mut self,
+        _: &FirstPage,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.scroll_to_page(0, cx);
+    }
+    pub fn on_action_last_page(
+        &mut self,
+        _: &LastPage,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.scroll_to_page(self.page_count().saturating_sub(1), cx);
+    }
+    pub fn on_action_scroll_to_top(
+        &mut self,
+        _: &ScrollToTop,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.scroll_to_page(0, cx);
+    }
+    pub fn on_action_scroll_to_bottom(
+        &mut self,
+        _: &ScrollToBottom,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.scroll_to_bottom(cx);
+    }
+    /// Animate scrolling to the bottom edge of the last page.
+    fn scroll_to_bottom(&mut self, cx: &mut Context<Self>) {
+        let Some(last) = self.page_count().checked_sub(1) else {
+            return;
+        };
+        self.show_page(last);
+        let height = self
+            .row_sizes
+            .iter()
+            .fold(px(0.), |height, size| height + size.height);
+        let top = (height - self.scroll_handle.bounds().size.height).max(px(0.));
+        let x = self.scroll_handle.offset().x;
+        self.smooth_scroll
+            .animate_to(&self.scroll_handle, point(x, -top));
+        cx.notify();
+    }
+    /// Buttons that scroll to the start or end of the document, shown once the view is scrolled
+    /// away from the top.
+    fn render_scroll_to_edge_buttons(&self, cx: &Context<Self>) -> Option<impl IntoElement> {
+        let offset = -self.scroll_handle.offset().y;
+        if self.presentation.is_some() || offset <= px(0.) {
+            return None;
+        }
+        let height = self
+            .row_sizes
+            .iter()
+            .fold(px(0.), |height, size| height + size.height);
+        let at_bottom = offset >= height - self.scroll_handle.bounds().size.height - px(1.);
+        Some(
+            v_flex()
+                .absolute()
+                .bottom(px(40.))
+                .right_5()
+                .gap_1()
+                .child(
+                    Button::new("scroll-to-top")
+                        .icon(Icon::new(IconName::ChevronUp))
+                        .small()
+                        .tooltip("Scroll to the first page (ctrl-home)")
+                        .on_click(cx.listener(|this, _, _window, cx| this.scroll_to_page(0, cx))),
+                )
+                .when(!at_bottom, |this| {
+                    this.child(
+                        Button::new("scroll-to-bottom")
+                            .icon(Icon::new(IconName::ChevronDown))
+                            .small()
+                            .tooltip("Scroll to the end of the document (ctrl-end)")
+                            .on_click(
+                                cx.listener(|this, _, _window, cx| this.scroll_to_bottom(cx)),
+                            ),
+                    )
+                }),
+        )
+    }
+    pub fn on_action_next_screen(
+        &mut self,
+        _: &NextScreen,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.scroll_by_screen(1., cx);
+    }
+    pub fn on_action_prev_screen(
+        &mut self,
+        _: &PrevScreen,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.scroll_by_screen(-1., cx);
+    }
+    pub fn on_action_scroll_down(
+        &mut self,
+        _: &ScrollDown,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.scroll_by(px(self.smooth_scroll.settings().arrow_step), cx);
+    }
+    pub fn on_action_scroll_up(
+        &mut self,
+        _: &ScrollUp,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.scroll_by(px(-self.smooth_scroll.settings().arrow_step), cx);
+    }
+    /// Scroll down by `screens` viewport heights (up if negative). A little of the previous
+    /// screen stays visible so that the reader doesn't lose their place.
+    fn scroll_by_screen(&mut self, screens: f32, cx: &mut Context<Self>) {
+        let viewport = self.scroll_handle.bounds().size.height;
+        self.scroll_by(viewport * 0.9 * screens, cx);
+    }
+    /// Animate scrolling down by `distance` (up if negative), stopping at the ends of the
+    /// document.
+    fn scroll_by(&mut self, distance: Pixels, cx: &mut Context<Self>) {
+        if self.view_mode == ViewMode::SinglePage {
+            // Scroll within the page until its edge is reached:
+            let offset = -self.scroll_handle.offset().y;
+            let max_offset = self.scroll_handle.max_offset().height;
+            if (distance > px(0.) && offset >= max_offset)
+                || (distance < px(0.) && offset <= px(0.))
+            {
+                self.flip_page(distance > px(0.), cx);
+                return;
+            }
+        }
+        // Continue from the target of a running animation so that repeated key presses add up:
+        let start = if self.smooth_scroll.is_animating() {
+            self.smooth_scroll.wanted_offset()
+        } else {
+            self.scroll_handle.offset()
+        };
+        self.smooth_scroll
+            .animate_to(&self.scroll_handle, point(start.x, start.y - distance));
+        cx.notify();
+    }
+
+    pub fn on_action_auto_deskew(
+        &mut self,
+        _: &AutoDeskew,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some((index, pdf)) = self.current_page().zip(self.pdf_page_cache.pdf()) else {
+            return;
+        };
+        let Some(page) = pdf.pages().get(index) else {
+            return;
+        };
+        match pdf::estimate_skew_degrees(page, &InterpreterSettings::default()) {
+            Some(skew) => {
+                log::info!("Page {} is skewed by {skew:.2} degrees", index + 1);
+                self.pdf_page_cache.set_page_deskew(index, -skew);
+            }
+            None => log::info!("Found no text to deskew on page {}", index + 1),
+        }
+        cx.notify();
+    }
+    pub fn on_action_deskew_clockwise(
+        &mut self,
+        _: &DeskewClockwise,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.adjust_deskew(DESKEW_STEP_DEGREES, cx);
+    }
+    pub fn on_action_deskew_counterclockwise(
+        &mut self,
+        _: &DeskewCounterclockwise,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.adjust_deskew(-DESKEW_STEP_DEGREES, cx);
+    }
+    pub fn on_action_reset_deskew(
+        &mut self,
+        _: &ResetDeskew,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(index) = self.current_page() {
+            self.pdf_page_cache.set_page_deskew(index, 0.);
+            cx.notify();
+        }
+    }
+    fn adjust_deskew(&mut self, delta_degrees: f32, cx: &mut Context<Self>) {
+        let Some(index) = self.current_page() else {
+            return;
+        };
+        let degrees = self.pdf_page_cache.page_deskew(index) + delta_degrees;
+        self.pdf_page_cache.set_page_deskew(index, degrees);
+        cx.notify();
+    }
+}
+
+/// Images of page regions are rendered with at least this many pixels per PDF unit.
+const MIN_REGION_IMAGE_SCALE: f32 = 2.;
+/// Exported images are scaled down so that no side is larger than this many pixels.
+const MAX_EXPORT_DIMENSION: f32 = 8192.;
+/// Fixed scales offered by the status bar's zoom menu, `1` is the actual size.
+const ZOOM_PRESETS: [f32; 7] = [0.5, 0.75, 1., 1.25, 1.5, 2., 4.];
+/// Resolution of page images exported with [`ExportPages`] when no valid DPI was entered.
+const DEFAULT_EXPORT_DPI: f32 = 150.;
+const EXPORT_DPI_RANGE: RangeInclusive<f32> = 36.0..=1200.;
+
+/// Manual deskew adjustments rotate the current page by this many degrees.
+const DESKEW_STEP_DEGREES: f32 = 0.25;
+
+/// Quick previews of pages are rendered at this fraction of the full resolution.
+const THUMBNAIL_SCALE: f32 = 0.25;
+/// Logical width of the images in the thumbnail panel.
+const THUMBNAIL_WIDTH: f32 = 120.;
+const THUMBNAIL_PADDING: f32 = 8.;
+/// Space for the page number below each thumbnail.
+const THUMBNAIL_LABEL_HEIGHT: f32 = 16.;
+
+/// Zooming out further than this makes pages unreadable.
+pub const MIN_ZOOM: f32 = 0.1;
+/// Zooming in further than this would allocate gigantic images.
+pub const MAX_ZOOM: f32 = 8.;
+/// Zooming in or out multiplies or divides the zoom by this.
+const ZOOM_STEP: f32 = 1.25;
+/// Pages are rendered at a new zoom level once the mouse wheel has been still for this long.
+const ZOOM_RENDER_DELAY: Duration = Duration::from_millis(200);
+/// Distance that the mouse wheel must move past the edge of a page to flip to the next page in
+/// [`ViewMode::SinglePage`].
+const WHEEL_FLIP_DISTANCE: f32 = 120.;
+/// Page start marks on the scrollbar's track that would be closer than this many logical pixels
+/// to the previous mark are left out.
+const MIN_SCROLLBAR_TICK_SPACING: f32 = 3.;
+/// How often the progress of a [`PageJob`] is redrawn.
+const JOB_REFRESH_INTERVAL: Duration = Duration::from_millis(100);
+/// Wait this long after a file changed before reloading it, and between attempts to read it.
+const RELOAD_DELAY: Duration = Duration::from_millis(200);
+const RELOAD_ATTEMPTS: usize = 3;
+/// How often the progress of files that are being read is reported.
+const LOAD_PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+/// Files are read this many bytes at a time so that their progress can be reported.
+const READ_CHUNK_SIZE: u64 = 4 * 1024 * 1024;
+/// Pages are laid out for a new window size once the window hasn't been resized for this long.
+const RESIZE_DEBOUNCE: Duration = Duration::from_millis(150);
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct AutoDeskew;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct DeskewClockwise;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct DeskewCounterclockwise;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ResetDeskew;
+
+/// Export tables on the current page as CSV.
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ExportTables;
+
+/// Export the text of every page as a text file.
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ExportText;
+
+/// Send the active document to the printer.
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct Print;
+
+/// Export every image in the document as PNG files.
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ExportImages;
+
+/// Scroll the documents shown side by side together or independently.
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ToggleSyncScroll;
+
+/// Render the pages chosen in the export menu as PNG images.
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ExportPages;
+
+/// Drag on a page to export the tables inside the selected region.
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct SelectTableRegion;
+
+/// Drag on a page to copy the selected region as an image.
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct CopyImageRegion;
+
+/// Drag on a page to save the selected region as an image.
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct SaveImageRegion;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ToggleMeasureTool;
+
+/// Switch between selecting links and dragging to pan the pages.
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ToggleHandTool;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ZoomIn;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ZoomOut;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ResetZoom;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct CycleMeasureUnit;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct FindNext;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct FindPrevious;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct FirstPage;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct NextPage;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct PrevPage;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct CycleViewMode;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct FitWidth;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct FitHeight;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct TogglePerPageFit;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct FitPage;
+
+/// Show pages at a fixed scale where one PDF unit is one logical pixel.
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ActualSize;
+
+/// Rotate every page in the document.
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct RotateClockwise;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct RotateCounterclockwise;
+
+/// Rotate only the current page.
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct RotatePageClockwise;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct RotatePageCounterclockwise;
+
+/// Show the first page alone in the two page view mode or pair it with the second page.
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ToggleCoverPage;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct LastPage;
+
+/// Scroll to the top of the first page.
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ScrollToTop;
+
+/// Scroll to the bottom edge of the last page.
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ScrollToBottom;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct NextScreen;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct PrevScreen;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ScrollDown;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ScrollUp;
+impl Focusable for PdfPages {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+impl Render for PdfPages {
+    #[cfg_attr(feature = "hotpath", hotpath::measure)]
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let diagnostics = self.disabled_cache.read(cx).diagnostics;
+        self.pdf_page_cache.frame_start(diagnostics, window, cx);
+        self.thumbnail_cache.frame_start(diagnostics, window, cx);
+        self.smooth_scroll
+            .preform_scroll(window, cx, &self.scroll_handle);
+        self.sync_scroll(cx);
+        let element = div()
+            .relative()
+            .size_full()
+            .key_context({
+                let mut context = KeyContext::default();
+                context.add(PAGES_CONTEXT);
+                if self.presentation.is_some() {
+                    context.add(PRESENTATION_CONTEXT);
+                }
+                context
+            })
+            .track_focus(&self.focus_handle)
+            // The gutter between pages:
+            .when(self.presentation.is_none(), |this| {
+                this.bg(match self.canvas_color.rgb() {
+                    Some(color) => gpui::rgb(color).into(),
+                    None => cx.theme().muted,
+                })
+            })
+            .on_scroll_wheel(cx.listener(|view, event: &ScrollWheelEvent, window, cx| {
+                // Let the user take over instead of fighting an in-progress animation:
+                if view.smooth_scroll.is_animating() {
+                    view.smooth_scroll.interrupt(&view.scroll_handle);
+                }
+                if view.view_mode == ViewMode::SinglePage {
+                    view.flip_with_wheel(event, window, cx);
+                } else {
+                    // Keep scrolling through long documents after a flick:
+                    view.smooth_scroll.noticed_scroll_gesture(
+                        window,
+                        event.delta.pixel_delta(window.line_height()),
+                        event.touch_phase,
+                    );
+                }
+            }))
+            .on_mouse_move(cx.listener(Self::on_mouse_move))
+            .on_mouse_up(MouseButton::Left, cx.listener(Self::on_mouse_up))
+            .child({
+                // Zoom instead of scrolling while control is held, this needs to run in the
+                // capture phase since the list would otherwise scroll first:
+                let this = cx.entity().downgrade();
+                canvas(
+                    |_, _, _| {},
+                    move |bounds, _, window, _cx| {
+                        window.on_mouse_event(
+                            move |event: &ScrollWheelEvent, phase, _window, cx| {
+                                if phase == DispatchPhase::Capture
+                                    && event.modifiers.control
+                                    && bounds.contains(&event.position)
+                                {
+                                    cx.stop_propagation();
+                                    _ = this.update(cx, |this, cx| {
+                                        this.on_zoom_scroll_wheel(event, cx)
+                                    });
+                                }
+                            },
+                        );
+                    },
+                )
+                .absolute()
+                .size_full()
+            })
+            .map(|this| match self.pointer_tool {
+                PointerTool::None => this,
+                PointerTool::Hand if self.pan_drag.is_some() => this.cursor_grabbing(),
+                PointerTool::Hand => this.cursor_grab(),
+                _ => this.cursor_crosshair(),
+            })
+            .child(
+                v_virtual_list(
+                    cx.entity().clone(),
+                    "pdf-viewer-pages-list",
+                    self.row_sizes.clone(),
+                    move |view, visible_range, window, cx| {
+                        let visible_top = -f32::from(view.scroll_handle.offset().y);
+                        let viewport_height = f32::from(view.scroll_handle.bounds().size.height);
+                        let most_visible = most_visible_item(
+                            view.row_sizes.iter().map(|size| f32::from(size.height)),
+                            visible_top,
+                            visible_top + viewport_height,
+                        )
+                        .and_then(|row| view.rows.get(row))
+                        .map(|pages| pages.start);
+                        if most_visible != view.most_visible_page {
+                            // The page indicator was already rendered for this frame:
+                            view.most_visible_page = most_visible;
+                            cx.notify();
+                        }
+
+                        let rows = view.rows.clone();
+                        let content_width = view.content_width();
+                        let visible_pages = match (
+                            rows.get(visible_range.start),
+                            visible_range
+                                .end
+                                .checked_sub(1)
+                                .and_then(|last| rows.get(last)),
+                        ) {
+                            (Some(first), Some(last)) => first.start..last.end,
+                            _ => 0..0,
+                        };
+                        view.measure_visible_pages(visible_pages.clone(), cx);
+                        // The list also lays out the first row to measure it, even when it is
+                        // scrolled out of view:
+                        let measuring = visible_range == (0..1)
+                            && view
+                                .row_sizes
+                                .first()
+                                .is_some_and(|size| f32::from(size.height) <= visible_top);
+                        let mut images = view
+                            .pdf_page_cache
+                            .get_images(visible_pages, !measuring, window, cx)
+                            .into_iter();
+                        visible_range
+                            .map(|row_ix| {
+                                let spacer = view
+                                    .cover_spacer(row_ix)
+                                    .map(|width| div().flex_none().w(width).into_any_element());
+                                let mut children = spacer
+                                    .into_iter()
+                                    .chain(rows[row_ix].clone().map(|index| {
+                                        view.render_page(
+                                            index,
+                                            images.next().unwrap_or(PageSlot::NotRequested),
+                                            cx,
+                                        )
+                                    }))
+                                    .collect::<Vec<_>>();
+                                if view.right_to_left {
+                                    // The next page is to the left:
+                                    children.reverse();
+                                }
+                                // Every row is as wide as the widest one so that the list can be
+                                // scrolled horizontally, narrower rows are centered in it:
+                                h_flex()
+                                    .w_full()
+                                    .min_w(content_width)
+                                    .items_start()
+                                    .justify_center()
+                                    .when(row_ix + 1 < rows.len(), |this| this.pb(view.page_gap))
+                                    .children(children)
+                                    .into_any_element()
+                            })
+                            .collect()
+                    },
+                )
+                .track_scroll(&self.scroll_handle),
+            )
+            .when(self.presentation.is_none(), |this| {
+                this.children(self.render_scrollbar_ticks(cx)).child(
+                    // Add scrollbars
+                    div()
+                        .absolute()
+                        .top_0()
+                        .left_0()
+                        .right_0()
+                        .bottom_0()
+                        .child(
+                            Scrollbar::both(&self.scroll_state, &self.scroll_handle).axis(
+                                if self.content_width() > self.viewport_size.width {
+                                    ScrollbarAxis::Both
+                                } else {
+                                    ScrollbarAxis::Vertical
+                                },
+                            ),
+                        ),
+                )
+            })
+            .when_some(
+                self.current_page().filter(|_| self.presentation.is_none()),
+                |this, index| {
+                    this.child(
+                        div()
+                            .absolute()
+                            .bottom_3()
+                            .right_5()
+                            .px_2()
+                            .py_0p5()
+                            .rounded(cx.theme().radius)
+                            .bg(cx.theme().popover)
+                            .border_1()
+                            .border_color(cx.theme().border)
+                            .text_sm()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(format!("{} / {}", index + 1, self.page_count())),
+                    )
+                },
+            )
+            .children(self.render_scroll_to_edge_buttons(cx))
+            .children(self.render_job_progress(cx))
+            .children(self.render_cache_stats(cx))
+            .into_any_element();
+
+        if !self.show_thumbnails {
+            return element;
+        }
+        h_flex()
+            .size_full()
+            .child(self.render_thumbnails(cx))
+            .child(div().flex_1().h_full().child(element))
+            .into_any_element()
+    }
+}
+
+/// A second document that is shown next to the active tab, for example an older revision.
+struct CompareView {
+    path: Arc<PathBuf>,
+    pdf: Arc<Pdf>,
+    pages: Entity<PdfPages>,
+    scroll: Arc<Mutex<ScrollPosition>>,
+    rotation: Arc<Mutex<DocumentRotation>>,
+    zoom: Arc<Mutex<TabZoom>>,
+    /// Keep both documents scrolled to the same page.
+    sync_scroll: bool,
+}
+
+pub struct PdfReader {
+    focus_handle: FocusHandle,
+    tabs: Entity<TabsView<PdfTabData>>,
+    pages: Entity<PdfPages>,
+    assumed_viewport_size: Size<Pixels>,
+    /// The window size when the last resize was noticed and the timer that lays out the pages once
+    /// the resize ends, replaced (which cancels the timer) when the window is resized again.
+    resize_debounce: Option<(Size<Pixels>, Task<()>)>,
+    /// Device pixels per logical pixel when the pages were last laid out.
+    assumed_scale_factor: f32,
+    /// Files embedded in the active PDF.
+    attachments: Rc<Vec<Attachment>>,
+    /// Show a side panel that lists the active PDF's attachments.
+    show_attachments: bool,
+    /// Table of contents of the active PDF.
+    outline: Rc<Vec<OutlineItem>>,
+    /// Outline items whose children are shown, identified by their position in a depth first
+    /// traversal of the outline.
+    expanded_outline: HashSet<usize>,
+    /// Show a side panel with the active PDF's outline.
+    show_outline: bool,
+    /// Show a panel with recently logged messages.
+    show_log_viewer: bool,
+    /// The open preferences window.
+    preferences_window: Option<WindowHandle<Root>>,
+    /// Re-renders the log viewer when new messages are logged, dropped when it is hidden.
+    log_viewer_refresh: Option<Task<()>>,
+    recent_files: RecentFiles,
+    /// `true` until a file is opened, the empty tab shown at startup always lists recent files.
+    at_startup: bool,
+    /// Zoom of the last document that was shown, used for [`settings::InitialZoom::LastUsed`].
+    last_zoom: TabZoom,
+    /// Files that are being read or parsed, see [`PdfCommand::LoadProgress`].
+    loading: Vec<(PathBuf, LoadStage)>,
+    /// Show the "Open Recent" drop down menu.
+    show_recent_files: bool,
+    /// Show the "Compare" drop down menu that lists other tabs.
+    show_compare_menu: bool,
+    /// Show the "Export" drop down menu.
+    show_export_menu: bool,
+    /// Show the drop down menu that selects [`Settings::color_filter`].
+    show_color_filter_menu: bool,
+    /// Show the zoom drop down menu of the status bar.
+    show_zoom_menu: bool,
+    /// Path of the tab that the active PDF is being compared with.
+    comparing_with: Option<Arc<PathBuf>>,
+    /// Another document shown next to the active one.
+    compare_view: Option<CompareView>,
+    /// `true` if presentation mode made the window fullscreen, it is restored when leaving.
+    presentation_fullscreen: bool,
+    settings: Settings,
+    /// Reports changes to the files of open tabs when [`Settings::auto_reload`] is enabled.
+    file_watcher: FileWatcher,
+    /// Reloads files reported by [`Self::file_watcher`].
+    _reload_task: Task<()>,
+    /// Drawing scale used by the measure tool.
+    measure_scale_input: Entity<InputState>,
+    /// Pages to export as images, like `3` or `2-5`. Empty for the current page.
+    export_pages_input: Entity<InputState>,
+    /// Resolution of exported page images.
+    export_dpi_input: Entity<InputState>,
+    /// 1-based page number to jump to, shows the current page while it isn't focused.
+    page_input: Entity<InputState>,
+    search_input: Entity<InputState>,
+    _subscriptions: Vec<Subscription>,
+}
+impl PdfReader {
+    fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
+        cx.bind_keys([
+            KeyBinding::new("ctrl-w", tabs::CloseTab, Some(CONTEXT)),
+            KeyBinding::new("ctrl-t", tabs::CreateTab, Some(CONTEXT)),
+            KeyBinding::new("ctrl-shift-t", tabs::ReopenTab, Some(CONTEXT)),
+            KeyBinding::new("ctrl-shift-c", tabs::CopyFilePath, Some(CONTEXT)),
+            KeyBinding::new("ctrl-o", OpenFiles, Some(CONTEXT)),
+            KeyBinding::new("ctrl-tab", tabs::NextTab, Some(CONTEXT)),
+            KeyBinding::new("ctrl-shift-tab", tabs::PrevTab, Some(CONTEXT)),
+            KeyBinding::new("ctrl-1", tabs::ActivateTab(0), Some(CONTEXT)),
+            KeyBinding::new("ctrl-2", tabs::ActivateTab(1), Some(CONTEXT)),
+            KeyBinding::new("ctrl-3", tabs::ActivateTab(2), Some(CONTEXT)),
+            KeyBinding::new("ctrl-4", tabs::ActivateTab(3), Some(CONTEXT)),
+            KeyBinding::new("ctrl-5", tabs::ActivateTab(4), Some(CONTEXT)),
+            KeyBinding::new("ctrl-6", tabs::ActivateTab(5), Some(CONTEXT)),
+            KeyBinding::new("ctrl-7", tabs::ActivateTab(6), Some(CONTEXT)),
+            KeyBinding::new("ctrl-8", tabs::ActivateTab(7), Some(CONTEXT)),
+            KeyBinding::new("ctrl-9", tabs::ActivateLastTab, Some(CONTEXT)),
+            KeyBinding::new("ctrl-shift-a", ToggleAttachments, Some(CONTEXT)),
+            KeyBinding::new("ctrl-shift-o", ToggleOutline, Some(CONTEXT)),
+            KeyBinding::new("ctrl-shift-g", ToggleThumbnails, Some(CONTEXT)),
+            KeyBinding::new("ctrl-shift-l", ToggleLogViewer, Some(CONTEXT)),
+            KeyBinding::new("ctrl-shift-m", ToggleCacheStats, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-l", CycleLogLevel, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-d", AutoDeskew, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-]", DeskewClockwise, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-[", DeskewCounterclockwise, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-r", ResetDeskew, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-t", ExportTables, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-e", ExportText, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-i", ExportImages, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-p", ExportPages, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-k", ToggleSyncScroll, Some(CONTEXT)),
+            KeyBinding::new("ctrl-p", Print, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-shift-t", SelectTableRegion, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-c", CopyImageRegion, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-shift-c", SaveImageRegion, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-m", ToggleMeasureTool, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-g", ToggleHandTool, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-u", CycleMeasureUnit, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-h", ToggleHighContrast, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-n", ToggleInvertColors, Some(CONTEXT)),
+            KeyBinding::new(
+                "ctrl-alt-shift-n",
+                ToggleDocumentInvertColors,
+                Some(CONTEXT),
+            ),
+            KeyBinding::new("ctrl-alt-s", ToggleStatusBar, Some(CONTEXT)),
+            KeyBinding::new("ctrl-,", OpenPreferences, Some(CONTEXT)),
+            KeyBinding::new("ctrl-f", FocusSearch, Some(CONTEXT)),
+            KeyBinding::new("f5", TogglePresentation, Some(CONTEXT)),
+            KeyBinding::new("f3", FindNext, Some(CONTEXT)),
+            KeyBinding::new("shift-f3", FindPrevious, Some(CONTEXT)),
+            // Enter is handled by the search input but shift-enter isn't:
+            KeyBinding::new("shift-enter", FindPrevious, Some(CONTEXT)),
+            KeyBinding::new("ctrl-+", ZoomIn, Some(CONTEXT)),
+            KeyBinding::new("ctrl-=", ZoomIn, Some(CONTEXT)),
+            KeyBinding::new("ctrl--", ZoomOut, Some(CONTEXT)),
+            KeyBinding::new("ctrl-0", ResetZoom, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-1", FitWidth, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-2", FitHeight, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-3", FitPage, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-4", TogglePerPageFit, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-0", ActualSize, Some(CONTEXT)),
+            KeyBinding::new("home", FirstPage, Some(CONTEXT)),
+            KeyBinding::new("end", LastPage, Some(CONTEXT)),
+            KeyBinding::new("ctrl-home", ScrollToTop, Some(CONTEXT)),
+            KeyBinding::new("ctrl-end", ScrollToBottom, Some(CONTEXT)),
+            KeyBinding::new("pagedown", NextScreen, Some(CONTEXT)),
+            KeyBinding::new("pageup", PrevScreen, Some(CONTEXT)),
+            // Text inputs don't bind space, so only take it while the pages are focused:
+            KeyBinding::new("space", NextScreen, Some(PAGES_CONTEXT)),
+            KeyBinding::new("shift-space", PrevScreen, Some(PAGES_CONTEXT)),
+            KeyBinding::new("down", ScrollDown, Some(CONTEXT)),
+            KeyBinding::new("up", ScrollUp, Some(CONTEXT)),
+            KeyBinding::new("right", NextPage, Some(CONTEXT)),
+            KeyBinding::new("left", PrevPage, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-v", CycleViewMode, Some(CONTEXT)),
+            KeyBinding::new("ctrl-alt-shift-v", ToggleCoverPage, Some(CONTEXT)),
+            KeyBinding::new("ctrl-r", RotateClockwise, Some(CONTEXT)),
+            KeyBinding::new("ctrl-shift-r", RotateCounterclockwise, Some(CONTEXT)),
+            KeyBinding::new("ctrl-]", RotatePageClockwise, Some(CONTEXT)),
+            KeyBinding::new("ctrl-[", RotatePageCounterclockwise, Some(CONTEXT)),
+            // Later bindings take precedence, so these replace scrolling while presenting:
+            KeyBinding::new("escape", ExitPresentation, Some(PRESENTATION_CONTEXT)),
+            KeyBinding::new("space", NextPage, Some(PRESENTATION_CONTEXT)),
+            KeyBinding::new("shift-space", PrevPage, Some(PRESENTATION_CONTEXT)),
+            KeyBinding::new("down", NextPage, Some(PRESENTATION_CONTEXT)),
+            KeyBinding::new("up", PrevPage, Some(PRESENTATION_CONTEXT)),
+        ]);
+        let settings = Settings::load();
+        let pages = Self::new_pages(&settings, settings.filters(None), window, cx);
+        pages.focus_handle(cx).focus(window);
+        let measure_scale_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("1")
+                .default_value("1")
+        });
+        let export_pages_input =
+            cx.new(|cx| InputState::new(window, cx).placeholder("Current page, or 2-5"));
+        let export_dpi_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder(DEFAULT_EXPORT_DPI.to_string())
+                .default_value(DEFAULT_EXPORT_DPI.to_string())
+        });
+        let page_input = cx.new(|cx| InputState::new(window, cx).placeholder("Page"));
+        let search_input = cx.new(|cx| InputState::new(window, cx).placeholder("Search (ctrl-f)"));
+        let subscriptions = vec![
+            cx.observe_window_appearance(window, |this, window, cx| {
+                // Follow the system's light or dark theme while running:
+                Theme::sync_system_appearance(Some(window), cx);
+                cx.refresh_windows();
+                this.apply_settings(cx);
+            }),
+            cx.on_app_quit(|this, cx| {
+                // The active tab's scroll position is otherwise only saved when leaving the tab:
+                this.pages.update(cx, |pages, _cx| {
+                    if !pages.rows.is_empty() {
+                        *pages.save_scroll.lock().unwrap() = pages.scroll_position();
+                    }
+                });
+                this.save_session(cx);
+                async {}
+            }),
+            cx.subscribe_in(
+                &measure_scale_input,
+                window,
+                |this, input, event: &InputEvent, _window, cx| {
+                    if let InputEvent::PressEnter { .. } = event {
+                        match input.read(cx).value().trim().parse::<f64>() {
+                            Ok(scale) if scale > 0. => this
+                                .pages
+                                .update(cx, |pages, cx| pages.set_measure_scale(scale, cx)),
+                            _ => log::warn!("Drawing scale must be a positive number"),
+                        }
+                    }
+                },
+            ),
+            cx.subscribe_in(
+                &page_input,
+                window,
+                |this, input, event: &InputEvent, window, cx| {
+                    if let InputEvent::PressEnter { .. } = event {
+                        match input.read(cx).value().trim().parse::<usize>() {
+                            Ok(number) => this.pages.update(cx, |pages, cx| {
+                                let number = number.clamp(1, pages.page_count().max(1));
+                                pages.scroll_to_page(number - 1, cx);
+                            }),
+                            Err(_) => log::warn!("Page number must be a positive integer"),
+                        }
+                        // Return to the pages so that the input shows the current page again:
+                        this.pages.focus_handle(cx).focus(window);
+                    }
+                },
+            ),
+            cx.subscribe_in(
+                &search_input,
+                window,
+                |this, input, event: &InputEvent, _window, cx| {
+                    if let InputEvent::PressEnter { .. } = event {
+                        let query = input.read(cx).value().to_string();
+                        this.pages.update(cx, |pages, cx| pages.search(query, cx));
+                    }
+                },
+            ),
+        ];
+        // dbg!(&cx.key_bindings().borrow().bindings().collect::<Vec<_>>());
+
+        Self {
+            focus_handle: cx.focus_handle(),
+            tabs: {
+                let sender = MsgSender::from_cx(window, cx);
+                cx.new(|cx| {
+                    let mut tabs = TabsView::new(settings.smooth_scroll, window, cx);
+                    tabs.set_right_to_left(settings.right_to_left, cx);
+                    tabs.on_tab_changed({
+                        let sender = sender.clone();
+                        move |_window, _cx| {
+                            sender
+                                .spawn(async move |_window, mut sender| {
+                                    sender.send_ignoring_errors(PdfCommand::ChangedTab);
+                                })
+                                .detach();
+                        }
+                    });
+                    tabs.on_last_tab_closed(move |_window, _cx| {
+                        sender
+                            .spawn(async move |_window, mut sender| {
+                                sender.send_ignoring_errors(PdfCommand::LastTabClosed);
+                            })
+                            .detach();
+                    });
+                    tabs
+                })
+            },
+            pages,
+            assumed_viewport_size: Default::default(),
+            resize_debounce: None,
+            assumed_scale_factor: window.scale_factor(),
+            attachments: Rc::new(vec![]),
+            outline: Rc::new(vec![]),
+            expanded_outline: HashSet::new(),
+            show_outline: false,
+            show_attachments: false,
+            show_log_viewer: false,
+            preferences_window: None,
+            log_viewer_refresh: None,
+            recent_files: RecentFiles::load(),
+            at_startup: true,
+            last_zoom: TabZoom::default(),
+            loading: Vec::new(),
+            show_recent_files: false,
+            show_compare_menu: false,
+            show_export_menu: false,
+            show_color_filter_menu: false,
+            show_zoom_menu: false,
+            comparing_with: None,
+            compare_view: None,
+            presentation_fullscreen: false,
+            settings,
+            file_watcher: FileWatcher::new(),
+            _reload_task: cx.spawn_in(window, async move |this, window| {
+                Self::reload_changed_files(this, window).await
+            }),
+            measure_scale_input,
+            export_pages_input,
+            export_dpi_input,
+            page_input,
+            search_input,
+            _subscriptions: subscriptions,
+        }
+    }
+
+    /// Wait for [`Self::file_watcher`] to report changed files and reload them.
+    async fn reload_changed_files(this: WeakEntity<Self>, window: &mut AsyncWindowContext) {
+        loop {
+            let Ok(changes) = this.update(window, |this, _cx| this.file_watcher.changes()) else {
+                return;
+            };
+            let Some(mut paths) = changes.await else {
+                return;
+            };
+            // Give the program that writes the file some time to finish:
+            window.background_executor().timer(RELOAD_DELAY).await;
+            let Ok(more) = this.update(window, |this, _cx| this.file_watcher.take_changes()) else {
+                return;
+            };
+            paths.extend(more);
+            paths.sort();
+            paths.dedup();
+
+            for path in paths {
+                let Some((pdf_data, pdf)) = read_changed_pdf(window, &path).await else {
+                    continue;
+                };
+                log::info!("Reloading {}", path.display());
+                let result = this.update_in(window, |this, window, cx| {
+                    Update::update(this, window, cx, PdfCommand::Reloaded(path, pdf_data, pdf));
+                });
+                if result.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+    /// Zoom of a document that is being opened, see [`Settings::initial_zoom`].
+    fn initial_zoom(&self, cx: &App) -> TabZoom {
+        self.settings.initial_zoom.zoom().unwrap_or_else(|| {
+            let pages = self.pages.read(cx);
+            if pages.page_count() > 0 {
+                pages.tab_zoom()
+            } else {
+                self.last_zoom
+            }
+        })
+    }
+    /// Watch the files of all open tabs if [`Settings::auto_reload`] is enabled.
+    fn update_watched_files(&mut self, cx: &mut Context<Self>) {
+        let paths = if self.settings.auto_reload {
+            self.tabs
+                .read(cx)
+                .tabs_data()
+                .iter()
+                .flatten()
+                .map(|tab_data| tab_data.path.clone())
+                .collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+        self.file_watcher
+            .set_files(paths.iter().map(|path| path.as_path()));
+    }
+
+    #[cfg_attr(feature = "hotpath", hotpath::measure)]
+    fn active_pdf_changed(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.update_watched_files(cx);
+        self.attachments = Rc::new(vec![]);
+        self.outline = Rc::new(vec![]);
+        self.expanded_outline.clear();
+        self.comparing_with = None; // the page cache stops comparing when given a new PDF
+        self.assumed_scale_factor = window.scale_factor();
+        if self.pages.read(cx).page_count() > 0 {
+            self.last_zoom = self.pages.read(cx).tab_zoom();
+        }
+        let filters = self.page_filters(cx);
+        let tab = self.tabs.read(cx).active_tab_data().map(|tab_data| {
+            (
+                tab_data.scroll.clone(),
+                tab_data.rotation.clone(),
+                tab_data.zoom.clone(),
+                tab_data.pdf.clone().and_then(Result::ok),
+            )
+        });
+        if tab.is_some() {
+            self.at_startup = false;
+        }
+        let (scroll, rotation, zoom, pdf) = tab.unwrap_or_default();
+        if let Some(pdf) = &pdf {
+            self.attachments = Rc::new(attachments::find_attachments(pdf));
+            self.outline = Rc::new(outline::read_outline(pdf));
+            visit_outline(&self.outline, &mut |id, _depth, item| {
+                if item.open {
+                    self.expanded_outline.insert(id);
+                }
+                true
+            });
+            if !pdf.pages().is_empty() {
+                self.assumed_viewport_size = window.viewport_size();
+            }
+        }
+        self.pages.update(cx, |pages, cx| {
+            pages.set_filters(filters, cx); // the document might override the settings
+            pages.load_pdf(pdf, scroll, rotation, zoom, window, cx)
+        });
+        self.load_compare_view(window, cx);
+        self.save_session(cx);
+    }
+    /// Remember the open tabs so that [`Self::restore_session`] can open them after a restart.
+    fn save_session(&self, cx: &App) {
+        let tabs = self.tabs.read(cx);
+        let mut session = Session::default();
+        for (index, tab_data) in tabs.tabs_data().iter().enumerate() {
+            let Some(tab_data) = tab_data else {
+                continue;
+            };
+            if index == tabs.active_tab() {
+                session.active_tab = session.tabs.len();
+            }
+            let scroll = *tab_data.scroll.lock().unwrap();
+            session.tabs.push(SessionTab {
+                path: (*tab_data.path).clone(),
+                page_index: scroll.page_index,
+                offset: scroll.offset,
+            });
+        }
+        session.save();
+    }
+    /// Open the tabs from the previous run in the background, followed by `paths` (for example
+    /// files passed on the command line).
+    pub fn restore_session(
+        &self,
+        paths: Vec<PathBuf>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let session = Session::load();
+        if session.tabs.is_empty() {
+            self.open_paths(paths, window, cx);
+            return;
+        }
+        MsgSender::from_cx(window, cx)
+            .spawn(async move |window, mut sender| {
+                let session_paths = session.tabs.iter().map(|tab| tab.path.clone()).collect();
+                let files = read_files(window, &mut sender, session_paths).await;
+                sender.send_ignoring_errors(PdfCommand::RestoredSession(session, files));
+                if !paths.is_empty() {
+                    let files = read_files(window, &mut sender, paths).await;
+                    sender.send_ignoring_errors(PdfCommand::LoadedMany(files));
+                }
+            })
+            .detach();
+    }
+    /// Lay out the pages again once they are shown at their new size, for when the area they are
+    /// shown in changes without the window being resized.
+    fn relayout_soon(&mut self, cx: &mut Context<Self>) {
+        if self.assumed_viewport_size != Size::default() {
+            // Makes the next frame's `check_window_size` treat this like a resize:
+            self.assumed_viewport_size = size(px(-1.), px(-1.));
+        }
+        cx.notify();
+    }
+
+    fn check_window_size(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let latest_window_size = window.viewport_size();
+        if self.assumed_viewport_size == Size::default() {
+            return; // no pages to lay out
+        }
+        if window.scale_factor() != self.assumed_scale_factor {
+            // Moved to a monitor with a different DPI, re-render right away since the size might
+            // not change (this keeps the scroll position just like a resize does):
+            log::debug!(
+                "Scale factor changed from {} to {}",
+                self.assumed_scale_factor,
+                window.scale_factor()
+            );
+            self.active_pdf_changed(window, cx);
+            return;
+        }
+        if latest_window_size == self.assumed_viewport_size {
+            self.resize_debounce = None; // resized back before the timer fired
+            return;
+        }
+        let previous_size = self
+            .resize_debounce
+            .as_ref()
+            .map_or(self.assumed_viewport_size, |(size, _)| *size);
+        if previous_size == latest_window_size {
+            return; // still waiting for the resize to end
+        }
+        // Not a resize if `relayout_soon` was used:
+        if self.settings.performance.live_resize && previous_size.width >= px(0.) {
+            let change = size(
+                latest_window_size.width - previous_size.width,
+                latest_window_size.height - previous_size.height,
+            );
+            self.pages
+                .update(cx, |pages, cx| pages.preview_resize(change, cx));
+        }
+        let this = cx.weak_entity();
+        let task = window.spawn(cx, async move |window: &mut AsyncWindowContext| {
+            window.background_executor().timer(RESIZE_DEBOUNCE).await;
+            _ = window.update(|window, cx| {
+                _ = this.update(cx, |this, cx| {
+                    this.resize_debounce = None;
+                    // Set again by `active_pdf_changed` if there are pages:
+                    this.assumed_viewport_size = Size::default();
+                    this.active_pdf_changed(window, cx);
+                });
+            });
+        });
+        self.resize_debounce = Some((latest_window_size, task));
+    }
+}
+impl PdfReader {
+    pub fn on_action_toggle_outline(
+        &mut self,
+        _: &ToggleOutline,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.show_outline = !self.show_outline;
+        cx.notify();
+    }
+    pub fn on_action_toggle_attachments(
+        &mut self,
+        _: &ToggleAttachments,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.show_attachments = !self.show_attachments;
+        cx.notify();
+    }
+    pub fn on_action_open_preferences(
+        &mut self,
+        _: &OpenPreferences,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        // Focus the preferences window if it is already open:
+        if let Some(handle) = self.preferences_window
+            && handle
+                .update(cx, |_, window, _cx| window.activate_window())
+                .is_ok()
+        {
+            return;
+        }
+        let reader = cx.entity().downgrade();
+        let bounds = Bounds::centered(None, size(px(440.), px(600.)), cx);
+        self.preferences_window = cx
+            .open_window(
+                WindowOptions {
+                    titlebar: Some(gpui::TitlebarOptions {
+                        title: Some("Preferences".into()),
+                        ..Default::default()
+                    }),
+                    window_bounds: Some(WindowBounds::Windowed(bounds)),
+                    window_min_size: Some(Size::new(px(300.), px(300.))),
+                    ..Default::default()
+                },
+                |window, cx| {
+                    let view = cx.new(|cx| PreferencesView::new(reader, cx));
+                    cx.new(|cx| Root::new(view.into(), window, cx))
+                },
+            )
+            .inspect_err(|e| log::error!("Failed to open the preferences window: {e:?}"))
+            .ok();
+    }
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+    fn new_pages(
+        settings: &Settings,
+        filters: FilterChain,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Entity<PdfPages> {
+        cx.new(|cx| {
+            let mut pages = PdfPages::new(window, cx);
+            pages.apply_settings(settings, filters, cx);
+            pages
+        })
+    }
+    /// Change the settings, save them and apply them to the open document.
+    pub fn update_settings(&mut self, cx: &mut Context<Self>, change: impl FnOnce(&mut Settings)) {
+        change(&mut self.settings);
+        self.settings.save();
+        self.apply_settings(cx);
+    }
+    fn apply_settings(&mut self, cx: &mut Context<Self>) {
+        let settings = self.settings.clone();
+        let filters = self.page_filters(cx);
+        self.pages
+            .update(cx, |pages, cx| pages.apply_settings(&settings, filters, cx));
+        if let Some(compare_view) = &self.compare_view {
+            let filters = self.default_filters(cx);
+            compare_view
+                .pages
+                .update(cx, |pages, cx| pages.apply_settings(&settings, filters, cx));
+        }
+        self.tabs.update(cx, |tabs, cx| {
+            tabs.set_smooth_scroll(settings.smooth_scroll);
+            tabs.set_right_to_left(settings.right_to_left, cx);
+        });
+        self.update_watched_files(cx);
+        cx.notify();
+    }
+    pub fn on_action_toggle_log_viewer(
+        &mut self,
+        _: &ToggleLogViewer,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.show_log_viewer = !self.show_log_viewer;
+        self.log_viewer_refresh = self.show_log_viewer.then(|| {
+            cx.spawn_in(window, async move |this, window| {
+                let mut seen = logging::generation();
+                loop {
+                    window
+                        .background_executor()
+                        .timer(Duration::from_millis(500))
+                        .await;
+                    let latest = logging::generation();
+                    if latest == seen {
+                        continue;
+                    }
+                    seen = latest;
+                    if this.update(window, |_this, cx| cx.notify()).is_err() {
+                        return;
+                    }
+                }
+            })
+        });
+        cx.notify();
+    }
+    pub fn on_action_cycle_log_level(
+        &mut self,
+        _: &CycleLogLevel,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let level = logging::cycle_level();
+        log::info!("Log level changed to {}", log_level_label(level));
+        cx.notify();
+    }
+    /// Open dropped files in new tabs.
+    ///
+    /// GPUI only reports drops from other applications as [`ExternalPaths`], so dropped text
+    /// (like a URL from a browser) can't be told apart from other payloads and isn't handled.
+    /// Let the user pick one or more files and open each of them in a new tab.
+    fn prompt_open_files(&self, window: &mut Window, cx: &mut Context<Self>) {
+        let prompt = prompt_load_pdf_files(Some(&NoDisplayHandle(&*window)));
+        MsgSender::from_cx(window, cx)
+            .spawn(async move |window, mut sender| {
+                let Some(files) = prompt.await else {
+                    return;
+                };
+                let paths = files.iter().map(|file| file.path().to_owned()).collect();
+                let files = read_files(window, &mut sender, paths).await;
+                sender.send_ignoring_errors(PdfCommand::LoadedMany(files));
+            })
+            .detach();
+    }
+    /// Open files in new tabs, in order. Used for files passed on the command line.
+    pub fn open_paths(&self, paths: Vec<PathBuf>, window: &mut Window, cx: &mut Context<Self>) {
+        if paths.is_empty() {
+            return;
+        }
+        MsgSender::from_cx(window, cx)
+            .spawn(async move |window, mut sender| {
+                let files = read_files(window, &mut sender, paths).await;
+                sender.send_ignoring_errors(PdfCommand::LoadedMany(files));
+            })
+            .detach();
+    }
+    pub fn on_action_open_files(
+        &mut self,
+        _: &OpenFiles,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.prompt_open_files(window, cx);
+    }
+    fn on_drop_paths(
+        &mut self,
+        paths: &ExternalPaths,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        for path in paths.paths() {
+            if path.is_dir() {
+                log::debug!("Ignoring dropped folder {}", path.display());
+                continue;
+            }
+            Update::update(self, window, cx, PdfCommand::OpenFileInNewTab(path.clone()));
+        }
+    }
+    /// `true` if documents without an override are shown with inverted colors.
+    fn invert_by_default(&self, cx: &App) -> bool {
+        let invert_colors = self.settings.invert_colors;
+        invert_colors.enabled || (invert_colors.follow_system && cx.theme().mode.is_dark())
+    }
+    /// `true` if the active document is shown with inverted colors.
+    fn pages_inverted(&self, cx: &App) -> bool {
+        self.tabs
+            .read(cx)
+            .active_tab_data()
+            .and_then(|tab_data| tab_data.invert_colors)
+            .unwrap_or_else(|| self.invert_by_default(cx))
+    }
+    /// Filters for the active document.
+    fn page_filters(&self, cx: &App) -> FilterChain {
+        self.settings.filters(Some(self.pages_inverted(cx)))
+    }
+    /// Filters for documents that don't override them.
+    fn default_filters(&self, cx: &App) -> FilterChain {
+        self.settings.filters(Some(self.invert_by_default(cx)))
+    }
+    pub fn on_action_toggle_invert_colors(
+        &mut self,
+        _: &ToggleInvertColors,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        // Documents that were overridden keep their override:
+        self.update_settings(cx, |settings| {
+            settings.invert_colors.enabled = !settings.invert_colors.enabled
+        });
+    }
+    /// Invert the colors of only the active document, or stop inverting them.
+    pub fn on_action_toggle_document_invert_colors(
+        &mut self,
+        _: &ToggleDocumentInvertColors,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let inverted = !self.pages_inverted(cx);
+        let global = self.invert_by_default(cx);
+        let changed = self.tabs.update(cx, |tabs, _cx| {
+            let Some(Some(tab_data)) = tabs.active_tab_data_mut() else {
+                return false;
+            };
+            // Forget the override when it matches the setting again:
+            tab_data.invert_colors = (inverted != global).then_some(inverted);
+            true
+        });
+        if changed {
+            let filters = self.page_filters(cx);
+            self.pages
+                .update(cx, |pages, cx| pages.set_filters(filters, cx));
+            cx.notify();
+        }
+    }
+    pub fn on_action_toggle_presentation(
+        &mut self,
+        _: &TogglePresentation,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.pages.read(cx).is_presenting() {
+            self.stop_presentation(window, cx);
+            return;
+        }
+        if self.tabs.read(cx).active_tab_data().is_none() {
+            return;
+        }
+        let screen_size = window.viewport_size();
+        self.pages
+            .update(cx, |pages, cx| pages.start_presentation(screen_size, cx));
+        if !window.is_fullscreen() {
+            window.toggle_fullscreen();
+            self.presentation_fullscreen = true;
+        }
+        cx.notify();
+    }
+    pub fn on_action_exit_presentation(
+        &mut self,
+        _: &ExitPresentation,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.stop_presentation(window, cx);
+    }
+    fn stop_presentation(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.pages
+            .update(cx, |pages, cx| pages.stop_presentation(cx));
+        if std::mem::take(&mut self.presentation_fullscreen) && window.is_fullscreen() {
+            window.toggle_fullscreen();
+        }
+        cx.notify();
+    }
+    pub fn on_action_toggle_high_contrast(
+        &mut self,
+        _: &ToggleHighContrast,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.update_settings(cx, |settings| {
+            settings.high_contrast.enabled = !settings.high_contrast.enabled
+        });
+    }
+    pub fn on_action_toggle_status_bar(
+        &mut self,
+        _: &ToggleStatusBar,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.update_settings(cx, |settings| {
+            settings.show_status_bar = !settings.show_status_bar
+        });
+    }
+
+    /// File name without extension for files exported from the active tab.
+    fn export_file_stem(&self, cx: &App) -> Option<String> {
+        let tab_data = self.tabs.read(cx).active_tab_data()?;
+        Some(
+            tab_data
+                .path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+
+    pub fn on_action_export_text(
+        &mut self,
+        _: &ExportText,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(stem) = self.export_file_stem(cx) else {
+            return;
+        };
+        self.pages.update(cx, |pages, cx| {
+            pages.export_text(&format!("{stem}.txt"), window, cx)
+        });
+    }
+    pub fn on_action_export_images(
+        &mut self,
+        _: &ExportImages,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(stem) = self.export_file_stem(cx) else {
+            return;
+        };
+        self.pages.update(cx, |pages, cx| {
+            pages.export_images(&format!("{stem}-image.png"), window, cx)
+        });
+    }
+
+    pub fn on_action_export_pages(
+        &mut self,
+        _: &ExportPages,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(stem) = self.export_file_stem(cx) else {
+            return;
+        };
+        let pages = self.pages.read(cx);
+        let text = self.export_pages_input.read(cx).value();
+        let range = if text.trim().is_empty() {
+            pages.current_page().map(|page| page..page + 1)
+        } else {
+            pdf::parse_page_range(&text, pages.page_count())
+        };
+        let Some(range) = range else {
+            log::warn!("Enter a page number like 3 or a range like 2-5 to export");
+            return;
+        };
+        let dpi = match self.export_dpi_input.read(cx).value().trim().parse::<f32>() {
+            Ok(dpi) if dpi.is_finite() => {
+                dpi.clamp(*EXPORT_DPI_RANGE.start(), *EXPORT_DPI_RANGE.end())
+            }
+            _ => DEFAULT_EXPORT_DPI,
+        };
+        self.show_export_menu = false;
+        self.pages.update(cx, |pages, cx| {
+            pages.export_pages(range, dpi, &stem, window, cx)
+        });
+    }
+
+    pub fn on_action_print(&mut self, _: &Print, _window: &mut Window, cx: &mut Context<Self>) {
+        let Some(tab_data) = self.tabs.read(cx).active_tab_data() else {
+            return;
+        };
+        let pdf_data = tab_data.pdf_data.clone();
+        let file_name = PathBuf::from(tab_data.path.file_name().unwrap_or("document.pdf".as_ref()));
+        cx.background_spawn(async move {
+            // Print the loaded bytes since the file on disk might have changed or been removed:
+            let folder = std::env::temp_dir().join("pdf-reader-print");
+            let path = folder.join(&file_name);
+            let result = std::fs::create_dir_all(&folder)
+                .and_then(|()| std::fs::write(&path, &*pdf_data))
+                .and_then(|()| print_file(&path));
+            match result {
+                Ok(()) => log::info!("Sent {} to the printer", file_name.display()),
+                Err(e) => log::error!("Failed to print {}: {e}", file_name.display()),
+            }
+        })
+        .detach();
+    }
+
+    pub fn on_action_focus_search(
+        &mut self,
+        _: &FocusSearch,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        window.focus(&self.search_input.read(cx).focus_handle(cx));
+    }
+    /// Escape in the search input stops a search that is still extracting text and returns to the
+    /// pages.
+    fn on_search_escape(&mut self, _: &input::Escape, window: &mut Window, cx: &mut Context<Self>) {
+        self.pages
+            .update(cx, |pages, cx| pages.cancel_text_index_job(cx));
+        self.pages.focus_handle(cx).focus(window);
+    }
+
+    /// How far opening `path` has gotten, `None` if it isn't being opened.
+    fn load_stage(&self, path: &Path) -> Option<LoadStage> {
+        self.loading
+            .iter()
+            .find(|(loading, _)| loading == path)
+            .map(|&(_, stage)| stage)
+    }
+    /// Progress of files that are still being read, shown before their tabs are opened.
+    fn render_load_progress(&self, cx: &Context<Self>) -> Option<impl IntoElement> {
+        let megabytes = |bytes: u64| format!("{:.1} MB", bytes as f64 / (1024. * 1024.));
+        let reading = self
+            .loading
+            .iter()
+            .filter_map(|(path, stage)| match *stage {
+                LoadStage::Reading { read, total } => Some(format!(
+                    "Reading {}: {} of {}",
+                    path.file_name().unwrap_or_default().to_string_lossy(),
+                    megabytes(read),
+                    megabytes(total)
+                )),
+                LoadStage::Parsing => None,
+            })
+            .collect::<Vec<_>>();
+        if reading.is_empty() {
+            return None;
+        }
+        Some(
+            v_flex()
+                .absolute()
+                .bottom_3()
+                .right_5()
+                .gap_1()
+                .px_2()
+                .py_1()
+                .rounded(cx.theme().radius)
+                .bg(cx.theme().popover)
+                .border_1()
+                .border_color(cx.theme().border)
+                .children(reading),
+        )
+    }
+    fn render_status_bar(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let pages = self.pages.read(cx);
+        let page_count = pages.page_count();
+        let current_page = pages.current_page();
+        let zoom = pages.zoom_percent();
+        let tool = pages.pointer_tool();
+        let view_mode = pages.view_mode();
+        let cover_page = pages.cover_page();
+        let fit_mode = pages.fit_mode();
+        let page_label = match current_page {
+            Some(index) if page_count > 0 => Some(format!("Page {} of {page_count}", index + 1)),
+            _ => None,
+        };
+        let tool_label = match tool {
+            PointerTool::None => None,
+            PointerTool::SelectTableRegion => Some("Select table region"),
+            PointerTool::CopyImageRegion => Some("Copy region as image"),
+            PointerTool::SaveImageRegion => Some("Save region as image"),
+            PointerTool::Measure => Some("Measure"),
+            PointerTool::Hand => Some("Hand tool"),
+        };
+        let border = cx.theme().border;
+        let segment = || div().px_2().border_l_1().border_color(border);
+        // Both the zoom and the fit mode open the zoom menu:
+        let toggle_zoom_menu = {
+            let view = cx.weak_entity();
+            move || {
+                let view = view.clone();
+                move |_: &gpui::ClickEvent, _: &mut Window, cx: &mut App| {
+                    _ = view.update(cx, |this, cx| {
+                        this.show_zoom_menu = !this.show_zoom_menu;
+                        cx.notify();
+                    });
+                }
+            }
+        };
+
+        h_flex()
+            .id("pdf-status-bar")
+            .w_full()
+            .flex_none()
+            .px_1()
+            .border_t_1()
+            .border_color(cx.theme().border)
+            .text_xs()
+            .text_color(cx.theme().muted_foreground)
+            .child(match page_label {
+                Some(label) => Button::new("status-page")
+                    .ghost()
+                    .xsmall()
+                    .label(label)
+                    .tooltip("Go to page")
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        window.focus(&this.page_input.read(cx).focus_handle(cx));
+                    }))
+                    .into_any_element(),
+                None => div().px_1().child("No pages").into_any_element(),
+            })
+            .child(
+                segment()
+                    .child(
+                        Button::new("status-zoom")
+                            .ghost()
+                            .xsmall()
+                            .label(format!("{zoom:.0}%"))
+                            .tooltip("Choose zoom")
+                            .on_click(toggle_zoom_menu()),
+                    )
+                    .when(self.show_zoom_menu, |this| {
+                        this.child(
+                            deferred(
+                                anchored()
+                                    .anchor(Corner::BottomLeft)
+                                    .snap_to_window_with_margin(px(8.))
+                                    .child(self.render_zoom_menu(fit_mode, cx)),
+                            )
+                            .with_priority(1),
+                        )
+                    }),
+            )
+            .child(
+                segment().child(
+                    Button::new("status-fit-mode")
+                        .ghost()
+                        .xsmall()
+                        .label(fit_mode.label())
+                        .tooltip("Choose how pages are scaled")
+                        .on_click(toggle_zoom_menu()),
+                ),
+            )
+            .child(
+                segment().child(
+                    Button::new("status-view-mode")
+                        .ghost()
+                        .xsmall()
+                        .label(match view_mode {
+                            ViewMode::TwoPage if cover_page => "Two pages with cover",
+                            _ => view_mode.label(),
+                        })
+                        .tooltip("Change view mode (ctrl-alt-v)")
+                        .on_click(cx.listener(|this, _, window, cx| {
+                            this.pages.update(cx, |pages, cx| {
+                                pages.on_action_cycle_view_mode(&CycleViewMode, window, cx)
+                            });
+                        })),
+                ),
+            )
+            .child(div().flex_1())
+            .when_some(tool_label, |this, label| {
+                this.child(
+                    Button::new("status-tool")
+                        .ghost()
+                        .label(label)
+                        .tooltip("Click to stop using this tool")
+                        .on_click(cx.listener(|this, _, _window, cx| {
+                            this.pages.update(cx, |pages, cx| {
+                                pages.set_pointer_tool(PointerTool::None, cx)
+                            });
+                        })),
+                )
+            })
+            .when(self.pages_inverted(cx), |this| {
+                this.child(
+                    Button::new("status-invert-colors")
+                        .ghost()
+                        .label("Inverted colors")
+                        .tooltip("Click to stop inverting this document (ctrl-alt-shift-n)")
+                        .on_click(cx.listener(|this, _, window, cx| {
+                            this.on_action_toggle_document_invert_colors(
+                                &ToggleDocumentInvertColors,
+                                window,
+                                cx,
+                            );
+                        })),
+                )
+            })
+            .when(self.settings.high_contrast.enabled, |this| {
+                this.child(
+                    Button::new("status-high-contrast")
+                        .ghost()
+                        .label("High contrast")
+                        .tooltip("Click to turn off high contrast (ctrl-alt-h)")
+                        .on_click(cx.listener(|this, _, window, cx| {
+                            this.on_action_toggle_high_contrast(&ToggleHighContrast, window, cx);
+                        })),
+                )
+            })
+    }
+
+    pub fn on_action_clear_recent_files(
+        &mut self,
+        _: &ClearRecentFiles,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.recent_files.clear();
+        self.recent_files.save();
+        self.show_recent_files = false;
+        cx.notify();
+    }
+
+    /// Show the current page in the page number input unless the user is typing in it.
+    fn sync_page_input(&self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.page_input.read(cx).focus_handle(cx).is_focused(window) {
+            return;
+        }
+        let current = self
+            .pages
+            .read(cx)
+            .current_page()
+            .map_or_else(String::new, |index| (index + 1).to_string());
+        if self.page_input.read(cx).value() != current.as_str() {
+            self.page_input
+                .update(cx, |input, cx| input.set_value(current, window, cx));
+        }
+    }
+
+    fn render_toolbar(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let pages = self.pages.read(cx);
+        let measuring = pages.pointer_tool() == PointerTool::Measure;
+        let panning = pages.pointer_tool() == PointerTool::Hand;
+        let measure_unit = pages.measure_unit();
+        let page_count = pages.page_count();
+        let search_status = pages.search_status();
+        h_flex()
+            .w_full()
+            .flex_none()
+            .gap_1()
+            .px_2()
+            .py_1()
+            .border_b_1()
+            .border_color(cx.theme().border)
+            .child(
+                Button::new("open-files").ghost().label("Open").on_click(
+                    cx.listener(|this, _, window, cx| this.prompt_open_files(window, cx)),
+                ),
+            )
+            .child(
+                v_flex()
+                    .child(
+                        Button::new("open-recent")
+                            .ghost()
+                            .label("Open Recent")
+                            .on_click(cx.listener(|this, _, _window, cx| {
+                                this.show_recent_files = !this.show_recent_files;
+                                cx.notify();
+                            })),
+                    )
+                    .when(self.show_recent_files, |this| {
+                        this.child(
+                            deferred(
+                                anchored()
+                                    .snap_to_window_with_margin(px(8.))
+                                    .child(self.render_recent_files_menu(cx)),
+                            )
+                            .with_priority(1),
+                        )
+                    }),
+            )
+            .child(
+                v_flex()
+                    .child(
+                        Button::new("compare")
+                            .ghost()
+                            .label(match &self.comparing_with {
+                                Some(path) => format!(
+                                    "Comparing with {}",
+                                    path.file_name().unwrap_or_default().to_string_lossy()
+                                ),
+                                None => "Compare".to_owned(),
+                            })
+                            .tooltip("Highlight differences from another open tab")
+                            .on_click(cx.listener(|this, _, _window, cx| {
+                                this.show_compare_menu = !this.show_compare_menu;
+                                cx.notify();
+                            })),
+                    )
+                    .when(self.show_compare_menu, |this| {
+                        this.child(
+                            deferred(
+                                anchored()
+                                    .snap_to_window_with_margin(px(8.))
+                                    .child(self.render_compare_menu(cx)),
+                            )
+                            .with_priority(1),
+                        )
+                    }),
+            )
+            .when(page_count > 0, |this| {
+                this.child(
+                    v_flex()
+                        .child(
+                            Button::new("export")
+                                .ghost()
+                                .label("Export")
+                                .on_click(cx.listener(|this, _, _window, cx| {
+                                    this.show_export_menu = !this.show_export_menu;
+                                    cx.notify();
+                                })),
+                        )
+                        .when(self.show_export_menu, |this| {
+                            this.child(
+                                deferred(
+                                    anchored()
+                                        .snap_to_window_with_margin(px(8.))
+                                        .child(self.render_export_menu(cx)),
+                                )
+                                .with_priority(1),
+                            )
+                        }),
+                )
+            })
+            .when(page_count > 0, |this| {
+                this.child(div().w(px(60.)).child(TextInput::new(&self.page_input)))
+                    .child(format!("of {page_count}"))
+                    .child(
+                        div()
+                            .w(px(200.))
+                            .on_action(cx.listener(Self::on_search_escape))
+                            .child(TextInput::new(&self.search_input)),
+                    )
+                    .children(search_status)
+                    .child(
+                        Button::new("hand-tool")
+                            .label("Hand")
+                            .tooltip("Drag to pan the pages (ctrl-alt-g)")
+                            .when(panning, |this| this.primary())
+                            .when(!panning, |this| this.ghost())
+                            .on_click(cx.listener(|this, _, window, cx| {
+                                this.pages.update(cx, |pages, cx| {
+                                    pages.on_action_toggle_hand_tool(&ToggleHandTool, window, cx)
+                                });
+                            })),
+                    )
+            })
+            .child(div().flex_1())
+            .when(measuring, |this| {
+                this.child("Drawing scale 1:")
+                    .child(
+                        div()
+                            .w(px(80.))
+                            .child(TextInput::new(&self.measure_scale_input)),
+                    )
+                    .child(
+                        Button::new("measure-unit")
+                            .ghost()
+                            .label(measure_unit.suffix())
+                            .tooltip("Change unit (ctrl-alt-u)")
+                            .on_click(cx.listener(|this, _, window, cx| {
+                                this.pages.update(cx, |pages, cx| {
+                                    pages.on_action_cycle_measure_unit(
+                                        &CycleMeasureUnit,
+                                        window,
+                                        cx,
+                                    )
+                                });
+                            })),
+                    )
+            })
+            .child(
+                v_flex()
+                    .child(
+                        Button::new("color-filter")
+                            .ghost()
+                            .label(self.settings.color_filter.label())
+                            .tooltip("Tint pages for reading comfort")
+                            .on_click(cx.listener(|this, _, _window, cx| {
+                                this.show_color_filter_menu = !this.show_color_filter_menu;
+                                cx.notify();
+                            })),
+                    )
+                    .when(self.show_color_filter_menu, |this| {
+                        this.child(
+                            deferred(
+                                anchored()
+                                    .snap_to_window_with_margin(px(8.))
+                                    .child(self.render_color_filter_menu(cx)),
+                            )
+                            .with_priority(1),
+                        )
+                    }),
+            )
+            .child(
+                Button::new("preferences")
+                    .ghost()
+                    .label("Preferences")
+                    .tooltip("Open preferences (ctrl-,)")
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.on_action_open_preferences(&OpenPreferences, window, cx)
+                    })),
+            )
+    }
+
+    /// A button for each recent file that opens it in a new tab.
+    fn recent_file_buttons(&self, id: &'static str, cx: &mut Context<Self>) -> Vec<Button> {
+        self.recent_files
+            .paths()
+            .iter()
+            .enumerate()
+            .map(|(index, path)| {
+                let label = path.file_name().map_or_else(
+                    || path.display().to_string(),
+                    |name| name.to_string_lossy().into_owned(),
+                );
+                let path = path.clone();
+                Button::new((id, index))
+                    .ghost()
+                    .label(label)
+                    .tooltip(path.display().to_string())
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        this.show_recent_files = false;
+                        Update::update(
+                            this,
+                            window,
+                            cx,
+                            PdfCommand::OpenFileInNewTab(path.clone()),
+                        );
+                    }))
+            })
+            .collect()
+    }
+
+    fn render_recent_files_menu(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .id("pdf-recent-files")
+            .min_w(px(200.))
+            .gap_1()
+            .p_1()
+            .bg(cx.theme().popover)
+            .text_color(cx.theme().popover_foreground)
+            .border_1()
+            .border_color(cx.theme().border)
+            .rounded(cx.theme().radius)
+            .shadow_md()
+            .on_mouse_down_out(cx.listener(|this, _, _window, cx| {
+                this.show_recent_files = false;
+                cx.notify();
+            }))
+            .when(self.recent_files.is_empty(), |this| {
+                this.child(
+                    div()
+                        .p_1()
+                        .text_color(cx.theme().muted_foreground)
+                        .child("No recent files"),
+                )
+            })
+            .children(self.recent_file_buttons("recent-file", cx))
+            .child(div().h(px(1.)).bg(cx.theme().border))
+            .child(
+                Button::new("clear-recent-files")
+                    .ghost()
+                    .label("Clear recent files")
+                    .disabled(self.recent_files.is_empty())
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.on_action_clear_recent_files(&ClearRecentFiles, window, cx);
+                    })),
+            )
+    }
+
+    /// Lists the other open tabs so that one can be chosen as the version to compare with.
+    fn render_compare_menu(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let tabs = self.tabs.read(cx);
+        let active = tabs.active_tab();
+        let candidates = tabs
+            .tabs_data()
+            .iter()
+            .enumerate()
+            .filter(|&(index, _)| index != active)
+            .filter_map(|(index, data)| {
+                let data = data.as_ref()?;
+                let label = tabs::TabData::label(data);
+                let pdf = data.pdf.clone()?.ok()?;
+                Some((index, label, data.path.clone(), pdf))
+            })
+            .collect::<Vec<_>>();
+        v_flex()
+            .id("pdf-compare-menu")
+            .min_w(px(200.))
+            .gap_1()
+            .p_1()
+            .bg(cx.theme().popover)
+            .text_color(cx.theme().popover_foreground)
+            .border_1()
+            .border_color(cx.theme().border)
+            .rounded(cx.theme().radius)
+            .shadow_md()
+            .on_mouse_down_out(cx.listener(|this, _, _window, cx| {
+                this.show_compare_menu = false;
+                cx.notify();
+            }))
+            .when(candidates.is_empty(), |this| {
+                this.child(
+                    div()
+                        .p_1()
+                        .text_color(cx.theme().muted_foreground)
+                        .child("Open another version in a new tab to compare with it"),
+                )
+            })
+            .children(candidates.into_iter().map(|(index, label, path, pdf)| {
+                h_flex()
+                    .child(
+                        Button::new(("compare-with", index))
+                            .flex_1()
+                            .ghost()
+                            .label(label)
+                            .tooltip(path.display().to_string())
+                            .on_click(cx.listener({
+                                let (path, pdf) = (path.clone(), pdf.clone());
+                                move |this, _, _window, cx| {
+                                    this.show_compare_menu = false;
+                                    this.compare_with(path.clone(), pdf.clone(), cx);
+                                }
+                            })),
+                    )
+                    .child(
+                        Button::new(("compare-side-by-side", index))
+                            .ghost()
+                            .label("Side by side")
+                            .on_click(cx.listener(move |this, _, window, cx| {
+                                this.show_compare_menu = false;
+                                this.open_compare_view(path.clone(), pdf.clone(), window, cx);
+                            })),
+                    )
+            }))
+            .child(div().h(px(1.)).bg(cx.theme().border))
+            .child(
+                Button::new("stop-comparing")
+                    .ghost()
+                    .label("Stop comparing")
+                    .disabled(self.comparing_with.is_none())
+                    .on_click(cx.listener(|this, _, _window, cx| {
+                        this.show_compare_menu = false;
+                        this.comparing_with = None;
+                        this.pages
+                            .update(cx, |pages, cx| pages.set_compare_with(None, cx));
+                    })),
+            )
+            .child(
+                Button::new("close-side-by-side")
+                    .ghost()
+                    .label("Close side by side view")
+                    .disabled(self.compare_view.is_none())
+                    .on_click(cx.listener(|this, _, _window, cx| {
+                        this.show_compare_menu = false;
+                        this.close_compare_view(cx);
+                    })),
+            )
+    }
+
+    /// Options for exporting the active document.
+    fn render_export_menu(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .id("pdf-export-menu")
+            .min_w(px(200.))
+            .gap_1()
+            .p_1()
+            .bg(cx.theme().popover)
+            .text_color(cx.theme().popover_foreground)
+            .border_1()
+            .border_color(cx.theme().border)
+            .rounded(cx.theme().radius)
+            .shadow_md()
+            .on_mouse_down_out(cx.listener(|this, _, _window, cx| {
+                this.show_export_menu = false;
+                cx.notify();
+            }))
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(div().w(px(50.)).child("Pages"))
+                    .child(
+                        div()
+                            .w(px(160.))
+                            .child(TextInput::new(&self.export_pages_input)),
+                    ),
+            )
+            .child(
+                h_flex().gap_1().child(div().w(px(50.)).child("DPI")).child(
+                    div()
+                        .w(px(160.))
+                        .child(TextInput::new(&self.export_dpi_input)),
+                ),
+            )
+            .child(
+                Button::new("export-pages")
+                    .ghost()
+                    .label("Export pages as PNG")
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.on_action_export_pages(&ExportPages, window, cx)
+                    })),
+            )
+            .child(div().h(px(1.)).bg(cx.theme().border))
+            .child(
+                Button::new("export-text")
+                    .ghost()
+                    .label("Export text")
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.show_export_menu = false;
+                        this.on_action_export_text(&ExportText, window, cx)
+                    })),
             )
             .child(
-                // Add scrollbars
-                div()
-                    .absolute()
-                    .top_0()
-                    .left_0()
-                    .right_0()
-                    .bottom_0()
-                    .child(
-                        Scrollbar::both(&self.scroll_state, &self.scroll_handle)
-                            .axis(ScrollbarAxis::Vertical),
-                    ),
+                Button::new("export-images")
+                    .ghost()
+                    .label("Export embedded images")
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.show_export_menu = false;
+                        this.on_action_export_images(&ExportImages, window, cx)
+                    })),
             )
-            .into_any_element();
-
-        element
     }
-}
-
-pub struct PdfReader {
-    focus_handle: FocusHandle,
-    tabs: Entity<TabsView<PdfTabData>>,
-    pages: Entity<PdfPages>,
-    assumed_viewport_size: Size<Pixels>,
-}
-impl PdfReader {
-    fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
-        cx.bind_keys([
-            KeyBinding::new("ctrl-w", tabs::CloseTab, Some(CONTEXT)),
-            KeyBinding::new("ctrl-t", tabs::CreateTab, Some(CONTEXT)),
-            KeyBinding::new("ctrl-tab", tabs::NextTab, Some(CONTEXT)),
-            KeyBinding::new("ctrl-shift-tab", tabs::PrevTab, Some(CONTEXT)),
-        ]);
-        // dbg!(&cx.key_bindings().borrow().bindings().collect::<Vec<_>>());
 
-        Self {
-            focus_handle: cx.focus_handle(),
-            tabs: {
-                let sender = MsgSender::from_cx(window, cx);
-                cx.new(|cx| {
-                    let mut tabs = TabsView::new(window, cx);
-                    tabs.on_tab_changed(move |_window, _cx| {
-                        sender
-                            .spawn(async move |_window, mut sender| {
-                                sender.send(PdfCommand::ChangedTab);
-                            })
-                            .detach();
-                    });
-                    tabs
-                })
-            },
-            pages: cx.new(|cx| PdfPages::new(window, cx)),
-            assumed_viewport_size: Default::default(),
-        }
+    fn render_color_filter_menu(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let current = self.settings.color_filter;
+        v_flex()
+            .id("pdf-color-filter-menu")
+            .min_w(px(160.))
+            .gap_1()
+            .p_1()
+            .bg(cx.theme().popover)
+            .text_color(cx.theme().popover_foreground)
+            .border_1()
+            .border_color(cx.theme().border)
+            .rounded(cx.theme().radius)
+            .shadow_md()
+            .on_mouse_down_out(cx.listener(|this, _, _window, cx| {
+                this.show_color_filter_menu = false;
+                cx.notify();
+            }))
+            .children(
+                ColorFilter::ALL
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, filter)| {
+                        Button::new(("color-filter-option", index))
+                            .label(filter.label())
+                            .when(filter == current, |this| this.primary())
+                            .when(filter != current, |this| this.ghost())
+                            .on_click(cx.listener(move |this, _, _window, cx| {
+                                this.show_color_filter_menu = false;
+                                this.update_settings(cx, |settings| settings.color_filter = filter);
+                            }))
+                    }),
+            )
     }
 
-    #[cfg_attr(feature = "hotpath", hotpath::measure)]
-    fn active_pdf_changed(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        self.pages.update(cx, |pages, cx| {
-            pages.item_sizes = Rc::new(vec![]); // forget page sizes
-            pages.pdf_page_cache.clear(); // clear cache
-
-            *pages.save_scroll.lock().unwrap() = pages.scroll_handle.clone(); // save scroll
-            pages.scroll_handle = VirtualListScrollHandle::from(ScrollHandle::default()); // reset scroll
-
-            let Some(tab_data) = self.tabs.read(cx).active_tab_data() else {
-                return;
-            };
-            pages.scroll_handle = tab_data.scroll.lock().unwrap().clone(); // restore scroll
-            let Ok(pdf) = Pdf::new(tab_data.pdf_data.clone()) else {
-                return;
-            };
-            let pdf = Arc::new(pdf);
-            if pdf.pages().is_empty() {
-                // no pages
-                return;
-            }
-            let viewport_size = window.viewport_size();
+    /// Fit modes and fixed zoom levels, opened from the status bar.
+    fn render_zoom_menu(&self, current: FitMode, cx: &mut Context<Self>) -> impl IntoElement {
+        let fit_modes = [
+            FitMode::Width,
+            FitMode::EachWidth,
+            FitMode::Height,
+            FitMode::Page,
+        ];
+        let option = |id: &'static str, index: usize, label: String, fit_mode: FitMode| {
+            Button::new((id, index))
+                .label(label)
+                .when(fit_mode == current, |this| this.primary())
+                .when(fit_mode != current, |this| this.ghost())
+                .on_click(cx.listener(move |this, _, _window, cx| {
+                    this.show_zoom_menu = false;
+                    this.pages
+                        .update(cx, |pages, cx| pages.set_fit_mode(fit_mode, cx));
+                }))
+        };
+        v_flex()
+            .id("pdf-zoom-menu")
+            .min_w(px(160.))
+            .gap_1()
+            .p_1()
+            .bg(cx.theme().popover)
+            .text_color(cx.theme().popover_foreground)
+            .border_1()
+            .border_color(cx.theme().border)
+            .rounded(cx.theme().radius)
+            .shadow_md()
+            .on_mouse_down_out(cx.listener(|this, _, _window, cx| {
+                this.show_zoom_menu = false;
+                cx.notify();
+            }))
+            .children(fit_modes.into_iter().enumerate().map(|(index, fit_mode)| {
+                option(
+                    "zoom-fit-option",
+                    index,
+                    fit_mode.label().to_owned(),
+                    fit_mode,
+                )
+            }))
+            .child(div().h(px(1.)).bg(cx.theme().border))
+            .children(ZOOM_PRESETS.into_iter().enumerate().map(|(index, scale)| {
+                option(
+                    "zoom-preset-option",
+                    index,
+                    format!("{:.0}%", scale * 100.),
+                    FitMode::Custom(scale),
+                )
+            }))
+    }
 
-            // Scale to fit window width:
-            let max_width = pdf
-                .pages()
-                .iter()
-                .map(|page| page.render_dimensions().0)
-                .max_by(f32::total_cmp)
-                .expect("there should be at least one page");
-            let viewport_width = f32::from(viewport_size.width);
-            let scale = viewport_width / max_width;
-
-            let render_settings = RenderSettings {
-                x_scale: scale,
-                y_scale: scale,
-                ..Default::default()
-            };
+    /// Highlight how the active PDF differs from the PDF in another tab.
+    fn compare_with(&mut self, path: Arc<PathBuf>, pdf: Arc<Pdf>, cx: &mut Context<Self>) {
+        self.comparing_with = Some(path);
+        self.pages
+            .update(cx, |pages, cx| pages.set_compare_with(Some(pdf), cx));
+    }
 
-            // Update image rendering:
-            pages
-                .pdf_page_cache
-                .set_new_pdf(Some(pdf.clone()), render_settings.into());
+    /// Show the PDF from another tab next to the active one.
+    fn open_compare_view(
+        &mut self,
+        path: Arc<PathBuf>,
+        pdf: Arc<Pdf>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.compare_view = Some(CompareView {
+            path,
+            pdf,
+            pages: Self::new_pages(&self.settings, self.default_filters(cx), window, cx),
+            scroll: Default::default(),
+            rotation: Default::default(),
+            zoom: Default::default(),
+            sync_scroll: false,
+        });
+        self.load_compare_view(window, cx);
+        self.set_sync_scroll(true, cx);
+        // Both documents now share the space that the active one had:
+        self.relayout_soon(cx);
+    }
+    fn close_compare_view(&mut self, cx: &mut Context<Self>) {
+        self.set_sync_scroll(false, cx);
+        self.compare_view = None;
+        self.relayout_soon(cx);
+    }
+    fn load_compare_view(&self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(compare_view) = &self.compare_view else {
+            return;
+        };
+        compare_view.pages.update(cx, |pages, cx| {
+            pages.load_pdf(
+                Some(compare_view.pdf.clone()),
+                compare_view.scroll.clone(),
+                compare_view.rotation.clone(),
+                compare_view.zoom.clone(),
+                window,
+                cx,
+            )
+        });
+    }
+    /// Scroll the documents in the compare view together.
+    fn set_sync_scroll(&mut self, sync_scroll: bool, cx: &mut Context<Self>) {
+        let Some(compare_view) = &mut self.compare_view else {
+            return;
+        };
+        compare_view.sync_scroll = sync_scroll;
+        let (main, other) = (self.pages.clone(), compare_view.pages.clone());
+        main.update(cx, |pages, _cx| {
+            pages.set_scroll_sync(sync_scroll.then(|| other.downgrade()))
+        });
+        other.update(cx, |pages, _cx| {
+            pages.set_scroll_sync(sync_scroll.then(|| main.downgrade()))
+        });
+        if sync_scroll {
+            // Start out at the same page:
+            let position = self.pages.read(cx).scroll_position();
+            compare_view
+                .pages
+                .update(cx, |pages, cx| pages.follow_scroll(position, cx));
+        }
+        cx.notify();
+    }
+    pub fn on_action_toggle_sync_scroll(
+        &mut self,
+        _: &ToggleSyncScroll,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(compare_view) = &self.compare_view {
+            let sync_scroll = !compare_view.sync_scroll;
+            self.set_sync_scroll(sync_scroll, cx);
+        }
+    }
 
-            // Update layout/sizes:
-            self.assumed_viewport_size = viewport_size;
-            pages.item_sizes = Rc::new(
-                pdf.pages()
-                    .iter()
-                    .map(|page| {
-                        // Code adapted from `hayro::render`:
-                        let (width, height) = page.render_dimensions();
-                        let (width, height) = (width * scale, height * scale);
+    /// Header and pages of [`Self::compare_view`].
+    fn render_compare_view(
+        &self,
+        compare_view: &CompareView,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        v_flex()
+            .flex_1()
+            .h_full()
+            .overflow_hidden()
+            .child(
+                h_flex()
+                    .gap_1()
+                    .px_2()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .child(
+                        div().flex_1().overflow_hidden().text_ellipsis().child(
+                            compare_view
+                                .path
+                                .file_name()
+                                .unwrap_or_default()
+                                .to_string_lossy()
+                                .into_owned(),
+                        ),
+                    )
+                    .child(
+                        Button::new("sync-scroll")
+                            .label("Lock scrolling")
+                            .tooltip("Scroll both documents to the same page (ctrl-alt-k)")
+                            .when(compare_view.sync_scroll, |this| this.primary())
+                            .when(!compare_view.sync_scroll, |this| this.ghost())
+                            .on_click(cx.listener(|this, _, window, cx| {
+                                this.on_action_toggle_sync_scroll(&ToggleSyncScroll, window, cx)
+                            })),
+                    )
+                    .child(
+                        Button::new("close-compare-view")
+                            .ghost()
+                            .label("Close")
+                            .on_click(
+                                cx.listener(|this, _, _window, cx| this.close_compare_view(cx)),
+                            ),
+                    ),
+            )
+            .child(div().flex_1().w_full().child(compare_view.pages.clone()))
+    }
 
-                        size(px(width.floor()), px(height.floor()))
+    fn render_log_viewer(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .id("pdf-log-viewer")
+            .h(px(200.))
+            .w_full()
+            .flex_none()
+            .p_2()
+            .overflow_y_scroll()
+            .border_t_1()
+            .border_color(cx.theme().border)
+            .text_xs()
+            .font_family("monospace")
+            .child(div().font_semibold().child(format!(
+                "Log level: {} (ctrl-alt-l to change)",
+                log_level_label(logging::level_override())
+            )))
+            .children(logging::recent_messages(200).into_iter().map(|message| {
+                div()
+                    .whitespace_nowrap()
+                    .when(message.level <= log::Level::Warn, |this| {
+                        this.text_color(cx.theme().danger)
                     })
-                    .collect::<Vec<_>>(),
+                    .child(format!(
+                        "[{} {}] {}",
+                        message.level, message.target, message.message
+                    ))
+            }))
+    }
+
+    fn render_outline(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let mut rows = Vec::new();
+        visit_outline(&self.outline, &mut |id, depth, item| {
+            let expanded = self.expanded_outline.contains(&id);
+            let toggle = div()
+                .id(("outline-toggle", id))
+                .w(px(16.))
+                .flex_none()
+                .when(!item.children.is_empty(), |this| {
+                    this.cursor_pointer()
+                        .child(if expanded { "▾" } else { "▸" })
+                        .on_click(cx.listener(move |this, _, _window, cx| {
+                            if !this.expanded_outline.remove(&id) {
+                                this.expanded_outline.insert(id);
+                            }
+                            cx.notify();
+                        }))
+                });
+            let page = item.page;
+            rows.push(
+                h_flex().pl(px(12. * depth as f32)).child(toggle).child(
+                    div()
+                        .id(("outline-item", id))
+                        .flex_1()
+                        .px_1()
+                        .rounded_sm()
+                        .overflow_hidden()
+                        .whitespace_nowrap()
+                        .when(page.is_none(), |this| {
+                            this.text_color(cx.theme().muted_foreground)
+                        })
+                        .when_some(page, |this, page| {
+                            this.cursor_pointer()
+                                .hover(|style| style.bg(cx.theme().accent))
+                                .on_click(cx.listener(move |this, _, _window, cx| {
+                                    this.pages
+                                        .update(cx, |pages, cx| pages.scroll_to_page(page, cx));
+                                }))
+                        })
+                        .child(item.title.clone()),
+                ),
             );
+            expanded
         });
+
+        v_flex()
+            .id("pdf-outline")
+            .w(px(240.))
+            .h_full()
+            .flex_none()
+            .gap_1()
+            .p_2()
+            .overflow_y_scroll()
+            .border_r_1()
+            .border_color(cx.theme().border)
+            .child(div().font_semibold().child("Outline"))
+            .when(self.outline.is_empty(), |this| {
+                this.child(
+                    div()
+                        .text_color(cx.theme().muted_foreground)
+                        .child("This PDF has no outline"),
+                )
+            })
+            .children(rows)
     }
-    fn check_window_size(&mut self, window: &Window, cx: &mut Context<Self>) {
-        let mut latest_window_size = window.viewport_size();
-        if self.assumed_viewport_size == Size::default() {
-            return; // resize already being monitored.
-        }
-        if latest_window_size == self.assumed_viewport_size {
-            return; // no resize
-        }
-        self.assumed_viewport_size = Size::default();
-        let this = cx.weak_entity();
 
-        window
-            .spawn(cx, async move |window: &mut AsyncWindowContext| {
-                loop {
-                    window
-                        .background_executor()
-                        .timer(Duration::from_millis(250))
-                        .await;
-                    let keep_checking = window.update(|window, cx| {
-                        let new_size = window.viewport_size();
-                        if new_size != latest_window_size {
-                            // still resizing
-                            latest_window_size = new_size;
-                            true
-                        } else {
-                            _ = this.update(cx, |this, cx| {
-                                this.active_pdf_changed(window, cx);
-                            });
-                            false
-                        }
-                    });
-                    if !matches!(keep_checking, Ok(true)) {
-                        break;
-                    }
-                }
+    fn render_attachments(&self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let parent_path = self
+            .tabs
+            .read(cx)
+            .active_tab_data()
+            .map(|tab_data| tab_data.path.clone());
+
+        v_flex()
+            .id("pdf-attachments")
+            .w(px(240.))
+            .h_full()
+            .flex_none()
+            .gap_1()
+            .p_2()
+            .overflow_y_scroll()
+            .border_l_1()
+            .border_color(cx.theme().border)
+            .child(div().font_semibold().child("Attachments"))
+            .when(self.attachments.is_empty(), |this| {
+                this.child(
+                    div()
+                        .text_color(cx.theme().muted_foreground)
+                        .child("This PDF has no attachments"),
+                )
             })
-            .detach();
+            .children(
+                self.attachments
+                    .iter()
+                    .enumerate()
+                    .map(|(index, attachment)| {
+                        let attachment = attachment.clone();
+                        let parent_path = parent_path.clone();
+                        h_flex()
+                            .gap_2()
+                            .child(
+                                div()
+                                    .flex_1()
+                                    .overflow_hidden()
+                                    .whitespace_nowrap()
+                                    .child(attachment.name.clone()),
+                            )
+                            .child(if attachment.is_pdf() {
+                                Button::new(("attachment-open", index))
+                                    .label("Open")
+                                    .on_click(cx.listener(move |this, _, window, cx| {
+                                        // Show embedded files as if they were inside a folder named after the parent file:
+                                        let path = parent_path
+                                            .as_deref()
+                                            .map(|parent| parent.join(&attachment.name))
+                                            .unwrap_or_else(|| PathBuf::from(&attachment.name));
+                                        Update::update(
+                                            this,
+                                            window,
+                                            cx,
+                                            PdfCommand::OpenInNewTab(path, attachment.data.clone()),
+                                        );
+                                    }))
+                            } else {
+                                Button::new(("attachment-save", index))
+                                    .label("Save")
+                                    .on_click(move |_, window, cx| {
+                                        let prompt = prompt_save_file(
+                                            Some(&NoDisplayHandle(window)),
+                                            "Save attachment",
+                                            &attachment.name,
+                                        );
+                                        let data = attachment.data.clone();
+                                        cx.spawn(async move |_cx| {
+                                            if let Some(file) = prompt.await
+                                                && let Err(e) = file.write(&data).await
+                                            {
+                                                log::error!("Failed to save attachment: {e}");
+                                            }
+                                        })
+                                        .detach();
+                                    })
+                            })
+                    }),
+            )
     }
 }
 const CONTEXT: &str = "pdf-reader";
+/// Context of the [`PdfPages`] element, for keys that text inputs would otherwise receive.
+const PAGES_CONTEXT: &str = "pdf-pages";
+/// Added to [`CONTEXT`] and [`PAGES_CONTEXT`] while [`PdfPages::is_presenting`].
+const PRESENTATION_CONTEXT: &str = "presenting";
 impl Render for PdfReader {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         self.check_window_size(window, cx);
+        self.sync_page_input(window, cx);
+        let content = if let Some(tab_data) = self.tabs.read(cx).active_tab_data() {
+            match &tab_data.pdf {
+                None => v_flex()
+                    .size_full()
+                    .gap_2()
+                    .items_center()
+                    .justify_center()
+                    .child(Indicator::new().large().color(cx.theme().muted_foreground))
+                    .child(format!(
+                        "{} {}",
+                        match self.load_stage(&tab_data.path) {
+                            Some(LoadStage::Parsing) => "Parsing",
+                            _ => "Opening",
+                        },
+                        tab_data
+                            .path
+                            .file_name()
+                            .unwrap_or_default()
+                            .to_string_lossy()
+                    ))
+                    .into_any_element(),
+                Some(Ok(pdf)) if pdf.pages().is_empty() => v_flex()
+                    .size_full()
+                    .items_center()
+                    .justify_center()
+                    .child("This PDF has no pages")
+                    .into_any_element(),
+                Some(Ok(_)) => match &self.compare_view {
+                    Some(compare_view) if !self.pages.read(cx).is_presenting() => h_flex()
+                        .size_full()
+                        .child(
+                            div()
+                                .flex_1()
+                                .h_full()
+                                .overflow_hidden()
+                                .child(self.pages.clone()),
+                        )
+                        .child(div().w(px(1.)).h_full().bg(cx.theme().border))
+                        .child(self.render_compare_view(compare_view, cx))
+                        .into_any_element(),
+                    _ => self.pages.clone().into_any_element(),
+                },
+                Some(Err(e)) => v_flex()
+                    .size_full()
+                    .items_center()
+                    .justify_center()
+                    .child(e.to_string())
+                    .into_any_element(),
+            }
+        } else {
+            div()
+                .v_flex()
+                .gap_2()
+                .size_full()
+                .items_center()
+                .justify_center()
+                .child(
+                    Button::new("ok")
+                        .primary()
+                        .label("Select a PDF file")
+                        .on_click({
+                            let sender = MsgSender::from_cx(window, cx);
+                            move |_, window, _cx| {
+                                let prompt = prompt_load_pdf_file(Some(&NoDisplayHandle(window)));
+                                sender
+                                    .spawn(async move |window, mut sender| {
+                                        let Some(file) = prompt.await else {
+                                            return;
+                                        };
+                                        let mut files = read_files(
+                                            window,
+                                            &mut sender,
+                                            vec![file.path().to_owned()],
+                                        )
+                                        .await;
+                                        if let Some((path, data)) = files.pop() {
+                                            sender.send_ignoring_errors(PdfCommand::LoadedData(
+                                                path, data,
+                                            ))
+                                        }
+                                    })
+                                    .detach();
+                            }
+                        }),
+                )
+                .when(
+                    (self.at_startup
+                        || self.settings.last_tab_closed == LastTabClosed::RecentFiles)
+                        && !self.recent_files.is_empty(),
+                    |this| {
+                        this.child(
+                            div()
+                                .pt_4()
+                                .text_color(cx.theme().muted_foreground)
+                                .child("Recent files"),
+                        )
+                        .children(self.recent_file_buttons("empty-tab-recent-file", cx))
+                    },
+                )
+                .into_any_element()
+        };
+
+        let presenting = self.pages.read(cx).is_presenting();
         v_flex()
             .size_full()
             .id("pdf-reader")
-            .key_context(CONTEXT)
+            .key_context({
+                let mut context = KeyContext::default();
+                context.add(CONTEXT);
+                if presenting {
+                    context.add(PRESENTATION_CONTEXT);
+                }
+                context
+            })
+            .when(presenting, |this| this.bg(gpui::black()))
             .track_focus(&self.focus_handle)
             .on_action(window.listener_for(&self.tabs, TabsView::on_action_close_tab))
             .on_action(window.listener_for(&self.tabs, TabsView::on_action_create_tab))
+            .on_action(window.listener_for(&self.tabs, TabsView::on_action_reopen_tab))
             .on_action(window.listener_for(&self.tabs, TabsView::on_action_next_tab))
             .on_action(window.listener_for(&self.tabs, TabsView::on_action_prev_tab))
+            .on_action(window.listener_for(&self.tabs, TabsView::on_action_activate_tab))
+            .on_action(window.listener_for(&self.tabs, TabsView::on_action_activate_last_tab))
+            .on_action(window.listener_for(&self.tabs, TabsView::on_action_copy_file_path))
+            .on_action(window.listener_for(&self.tabs, TabsView::on_action_reveal_in_file_manager))
+            .on_action(cx.listener(Self::on_action_toggle_attachments))
+            .on_action(cx.listener(Self::on_action_toggle_log_viewer))
+            .on_action(cx.listener(Self::on_action_toggle_outline))
+            .on_action(cx.listener(Self::on_action_open_files))
+            .on_action(cx.listener(Self::on_action_cycle_log_level))
+            .on_action(cx.listener(Self::on_action_clear_recent_files))
+            .on_action(cx.listener(Self::on_action_toggle_presentation))
+            .on_action(cx.listener(Self::on_action_exit_presentation))
+            .on_action(cx.listener(Self::on_action_toggle_high_contrast))
+            .on_action(cx.listener(Self::on_action_toggle_invert_colors))
+            .on_action(cx.listener(Self::on_action_toggle_document_invert_colors))
+            .on_action(cx.listener(Self::on_action_toggle_status_bar))
+            .on_action(cx.listener(Self::on_action_open_preferences))
+            .on_action(cx.listener(Self::on_action_focus_search))
+            .on_action(cx.listener(Self::on_action_export_text))
+            .on_action(cx.listener(Self::on_action_export_images))
+            .on_action(cx.listener(Self::on_action_export_pages))
+            .on_action(cx.listener(Self::on_action_toggle_sync_scroll))
+            .on_action(cx.listener(Self::on_action_print))
+            .on_drop(cx.listener(Self::on_drop_paths))
+            .drag_over::<ExternalPaths>(|style, _, _window, cx| style.bg(cx.theme().drop_target))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_auto_deskew))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_deskew_clockwise))
+            .on_action(
+                window.listener_for(&self.pages, PdfPages::on_action_deskew_counterclockwise),
+            )
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_reset_deskew))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_export_tables))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_select_table_region))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_copy_image_region))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_save_image_region))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_toggle_measure_tool))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_toggle_hand_tool))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_cycle_measure_unit))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_zoom_in))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_zoom_out))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_reset_zoom))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_find_next))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_find_previous))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_first_page))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_next_page))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_prev_page))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_cycle_view_mode))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_toggle_thumbnails))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_toggle_cache_stats))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_fit_width))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_fit_height))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_toggle_per_page_fit))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_fit_page))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_actual_size))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_rotate_clockwise))
+            .on_action(
+                window.listener_for(&self.pages, PdfPages::on_action_rotate_counterclockwise),
+            )
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_rotate_page_clockwise))
+            .on_action(window.listener_for(
+                &self.pages,
+                PdfPages::on_action_rotate_page_counterclockwise,
+            ))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_toggle_cover_page))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_last_page))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_scroll_to_top))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_scroll_to_bottom))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_next_screen))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_prev_screen))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_scroll_down))
+            .on_action(window.listener_for(&self.pages, PdfPages::on_action_scroll_up))
             // Tab bar:
-            .child(self.tabs.clone())
+            .when(!presenting, |this| {
+                this.child(self.tabs.clone()).child(self.render_toolbar(cx))
+            })
             // Content:
             .child(
-                if let Some(tab_data) = self.tabs.read(cx).active_tab_data() {
-                    match Pdf::new(tab_data.pdf_data.clone()) {
-                        Ok(_) => self.pages.clone().into_any_element(),
-                        Err(e) => v_flex()
-                            .size_full()
-                            .items_center()
-                            .justify_center()
-                            .child(format!("Failed to load PDF:\n{e:?}"))
-                            .into_any_element(),
-                    }
-                } else {
-                    div()
-                        .v_flex()
-                        .gap_2()
-                        .size_full()
-                        .items_center()
-                        .justify_center()
-                        .child(
-                            Button::new("ok")
-                                .primary()
-                                .label("Select a PDF file")
-                                .on_click({
-                                    let sender = MsgSender::from_cx(window, cx);
-                                    move |_, window, _cx| {
-                                        let prompt =
-                                            prompt_load_pdf_file(Some(&NoDisplayHandle(window)));
-                                        sender
-                                            .spawn(async move |_window, mut sender| {
-                                                if let Some(data) = prompt.await {
-                                                    sender.send(PdfCommand::LoadedData(
-                                                        data.path().to_owned(),
-                                                        data.read().await,
-                                                    ))
-                                                }
-                                            })
-                                            .detach();
-                                    }
-                                }),
-                        )
-                        .into_any_element()
-                },
+                h_flex()
+                    .flex_1()
+                    .w_full()
+                    .overflow_hidden()
+                    .when(self.show_outline && !presenting, |this| {
+                        this.child(self.render_outline(cx))
+                    })
+                    .child(
+                        div()
+                            .relative()
+                            .flex_1()
+                            .h_full()
+                            .child(content)
+                            .children(self.render_load_progress(cx)),
+                    )
+                    .when(self.show_attachments && !presenting, |this| {
+                        this.child(self.render_attachments(window, cx))
+                    }),
             )
+            .when(self.show_log_viewer && !presenting, |this| {
+                this.child(self.render_log_viewer(cx))
+            })
+            .when(self.settings.show_status_bar && !presenting, |this| {
+                this.child(self.render_status_bar(cx))
+            })
+            .children(Root::render_notification_layer(window, cx))
     }
 }
 
+/// Visit outline items depth first together with their traversal index and depth, the children of
+/// an item are only visited if `visit` returns `true`.
+fn visit_outline(items: &[OutlineItem], visit: &mut dyn FnMut(usize, usize, &OutlineItem) -> bool) {
+    fn inner(
+        items: &[OutlineItem],
+        depth: usize,
+        next_id: &mut usize,
+        visit: &mut dyn FnMut(usize, usize, &OutlineItem) -> bool,
+    ) {
+        for item in items {
+            let id = *next_id;
+            *next_id += 1;
+            if visit(id, depth, item) {
+                inner(&item.children, depth + 1, next_id, visit);
+            } else {
+                // Skipped items still need ids so that ids don't depend on what is expanded:
+                *next_id += count_outline_items(&item.children);
+            }
+        }
+    }
+    inner(items, 0, &mut 0, visit);
+}
+fn count_outline_items(items: &[OutlineItem]) -> usize {
+    items
+        .iter()
+        .map(|item| 1 + count_outline_items(&item.children))
+        .sum()
+}
+
+fn log_level_label(level: Option<log::LevelFilter>) -> String {
+    match level {
+        Some(level) => level.to_string(),
+        None => "RUST_LOG".to_owned(),
+    }
+}
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ToggleAttachments;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ToggleOutline;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ToggleThumbnails;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ToggleCacheStats;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ToggleLogViewer;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct CycleLogLevel;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ClearRecentFiles;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ToggleHighContrast;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct TogglePresentation;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ExitPresentation;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ToggleInvertColors;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ToggleDocumentInvertColors;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct ToggleStatusBar;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct OpenPreferences;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct FocusSearch;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = pdf)]
+pub struct OpenFiles;
+
 pub enum PdfCommand {
     LoadedData(PathBuf, Vec<u8>),
+    /// Open PDF data that is already in memory (for example an attachment) in a new tab.
+    OpenInNewTab(PathBuf, Arc<Vec<u8>>),
+    /// Read a file from disk and open it in a new tab.
+    OpenFileInNewTab(PathBuf),
+    /// Open several files that were read from disk, each in its own tab.
+    LoadedMany(Vec<(PathBuf, Vec<u8>)>),
+    /// A file changed on disk, replace the data of every tab that shows it. The data was already
+    /// parsed while checking that the file was completely written.
+    Reloaded(PathBuf, Arc<Vec<u8>>, Arc<Pdf>),
+    /// Open the tabs of a previous run, with the data that was read for each of them.
+    RestoredSession(Session, Vec<(PathBuf, Vec<u8>)>),
     ChangedTab,
+    /// The only open tab was closed.
+    LastTabClosed,
+    /// Show a message to the user, see [`notify_user`].
+    Notify(Severity, String),
+    /// A file that is being opened got further, `None` once it is no longer being loaded.
+    LoadProgress(PathBuf, Option<LoadStage>),
+    /// Data of a tab from [`PdfReader::new_tab_data`] was parsed in the background.
+    Parsed(PathBuf, Arc<Vec<u8>>, Result<Arc<Pdf>, LoadError>),
+}
+
+/// How far opening a file has gotten, see [`PdfCommand::LoadProgress`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LoadStage {
+    /// `read` of the file's `total` bytes have been read.
+    Reading { read: u64, total: u64 },
+    /// The file was read and is being parsed.
+    Parsing,
+}
+
+/// How a message from [`notify_user`] is shown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Error,
+}
+
+/// Show a message in the corner of the window that disappears after a few seconds, it is also
+/// logged.
+fn notify_user(
+    severity: Severity,
+    message: impl Into<SharedString>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let message = message.into();
+    let notification = match severity {
+        Severity::Info => {
+            log::info!("{message}");
+            Notification::info(message)
+        }
+        Severity::Error => {
+            log::error!("{message}");
+            Notification::error(message)
+        }
+    };
+    window.push_notification(notification, cx);
+}
+impl PdfReader {
+    /// Data for a tab that shows `pdf_data`, which is parsed in the background and then sent back
+    /// with [`PdfCommand::Parsed`].
+    fn new_tab_data(
+        &self,
+        path: PathBuf,
+        pdf_data: Arc<Vec<u8>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> PdfTabData {
+        let parse = cx.background_spawn({
+            let pdf_data = pdf_data.clone();
+            async move { pdf::load_pdf(pdf_data) }
+        });
+        MsgSender::from_cx(window, cx)
+            .spawn({
+                let pdf_data = pdf_data.clone();
+                let path = path.clone();
+                async move |_window, mut sender| {
+                    let pdf = parse.await;
+                    sender.send_ignoring_errors(PdfCommand::Parsed(path, pdf_data, pdf));
+                }
+            })
+            .detach();
+        PdfTabData::new(path, pdf_data, self.initial_zoom(cx))
+    }
+    /// Show a document in a new tab, or in the active tab if it is empty.
+    fn open_tab(&mut self, tab_data: PdfTabData, window: &mut Window, cx: &mut Context<Self>) {
+        let reused = self.tabs.update(cx, |tabs, cx| {
+            if let Some(active @ None) = tabs.active_tab_data_mut() {
+                // Reuse the empty tab instead of leaving it behind:
+                *active = Some(tab_data);
+                return true;
+            }
+            tabs.create_tab(Some(tab_data), window, cx);
+            tabs.scroll_to_active_tab(window, cx);
+            cx.notify();
+            false
+        });
+        if reused {
+            self.active_pdf_changed(window, cx);
+        }
+    }
 }
 impl Update<PdfCommand> for PdfReader {
     fn update(&mut self, window: &mut Window, cx: &mut Context<Self>, msg: PdfCommand) {
         match msg {
             PdfCommand::LoadedData(path, pdf_data) => {
+                self.recent_files.add(&path);
+                self.recent_files.save();
+                let new_tab_data = self.new_tab_data(path, Arc::new(pdf_data), window, cx);
                 if let Some(tab_data) = self.tabs.as_mut(cx).active_tab_data_mut() {
-                    *tab_data = Some(PdfTabData {
-                        path: Arc::new(path),
-                        pdf_data: Arc::new(pdf_data),
-                        scroll: Arc::new(Mutex::new(VirtualListScrollHandle::from(
-                            ScrollHandle::new(),
-                        ))),
-                    });
+                    *tab_data = Some(new_tab_data);
                 }
                 self.active_pdf_changed(window, cx);
             }
+            PdfCommand::OpenInNewTab(path, pdf_data) => {
+                let tab_data = self.new_tab_data(path, pdf_data, window, cx);
+                self.open_tab(tab_data, window, cx);
+            }
+            PdfCommand::OpenFileInNewTab(path) => {
+                self.recent_files.add(&path);
+                self.recent_files.save();
+                MsgSender::from_cx(window, cx)
+                    .spawn(async move |window, mut sender| {
+                        let files = read_files(window, &mut sender, vec![path]).await;
+                        for (path, data) in files {
+                            sender.send_ignoring_errors(PdfCommand::OpenInNewTab(
+                                path,
+                                Arc::new(data),
+                            ));
+                        }
+                    })
+                    .detach();
+                cx.notify();
+            }
+            PdfCommand::LoadedMany(files) => {
+                for (path, _) in &files {
+                    self.recent_files.add(path);
+                }
+                self.recent_files.save();
+                // The last file's tab ends up active since every new tab is activated:
+                for (path, pdf_data) in files {
+                    Update::update(
+                        self,
+                        window,
+                        cx,
+                        PdfCommand::OpenInNewTab(path, Arc::new(pdf_data)),
+                    );
+                }
+            }
+            PdfCommand::RestoredSession(session, mut files) => {
+                let mut active_tab = None;
+                for (index, tab) in session.tabs.into_iter().enumerate() {
+                    // Files that couldn't be read were left out by `read_files`:
+                    let Some(position) = files.iter().position(|(path, _)| *path == tab.path)
+                    else {
+                        continue;
+                    };
+                    let (path, pdf_data) = files.remove(position);
+                    let tab_data = self.new_tab_data(path, Arc::new(pdf_data), window, cx);
+                    *tab_data.scroll.lock().unwrap() = ScrollPosition {
+                        page_index: tab.page_index,
+                        offset: tab.offset,
+                    };
+                    self.open_tab(tab_data, window, cx);
+                    if index == session.active_tab {
+                        active_tab = Some(self.tabs.read(cx).active_tab());
+                    }
+                }
+                if let Some(active_tab) = active_tab {
+                    self.tabs.update(cx, |tabs, cx| {
+                        tabs.set_active_tab(active_tab, window, cx);
+                        tabs.scroll_to_active_tab(window, cx);
+                        cx.notify();
+                    });
+                }
+            }
+            PdfCommand::Reloaded(path, pdf_data, pdf) => {
+                let active_changed = self.tabs.update(cx, |tabs, _cx| {
+                    let active = tabs.active_tab();
+                    let mut active_changed = false;
+                    for (index, tab_data) in tabs.tabs_data_mut().iter_mut().enumerate() {
+                        if let Some(tab_data) = tab_data
+                            && *tab_data.path == path
+                        {
+                            // Keep the scroll position and rotation:
+                            tab_data.reload(pdf_data.clone(), pdf.clone());
+                            active_changed |= index == active;
+                        }
+                    }
+                    active_changed
+                });
+                if active_changed {
+                    self.active_pdf_changed(window, cx);
+                }
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                notify_user(Severity::Info, format!("Reloaded {name}"), window, cx);
+            }
             PdfCommand::ChangedTab => {
                 self.active_pdf_changed(window, cx);
             }
+            PdfCommand::Notify(severity, message) => notify_user(severity, message, window, cx),
+            PdfCommand::LoadProgress(path, stage) => {
+                let position = self
+                    .loading
+                    .iter()
+                    .position(|(loading, _)| *loading == path);
+                match (position, stage) {
+                    (Some(position), Some(stage)) => self.loading[position].1 = stage,
+                    (Some(position), None) => {
+                        self.loading.remove(position);
+                    }
+                    (None, Some(stage)) => self.loading.push((path, stage)),
+                    (None, None) => {}
+                }
+                cx.notify();
+            }
+            PdfCommand::Parsed(path, pdf_data, pdf) => {
+                self.loading.retain(|(loading, _)| *loading != path);
+                let active_changed = self.tabs.update(cx, |tabs, _cx| {
+                    let active = tabs.active_tab();
+                    let mut active_changed = false;
+                    for (index, tab_data) in tabs.tabs_data_mut().iter_mut().enumerate() {
+                        if let Some(tab_data) = tab_data
+                            && Arc::ptr_eq(&tab_data.pdf_data, &pdf_data)
+                            && tab_data.pdf.is_none()
+                        {
+                            tab_data.pdf = Some(pdf.clone());
+                            active_changed |= index == active;
+                        }
+                    }
+                    active_changed
+                });
+                if active_changed {
+                    self.active_pdf_changed(window, cx);
+                }
+                cx.notify();
+            }
+            PdfCommand::LastTabClosed => match self.settings.last_tab_closed {
+                LastTabClosed::EmptyTab | LastTabClosed::RecentFiles => {}
+                LastTabClosed::Quit => {
+                    // Settings and recent files are written as soon as they change and the session
+                    // is saved when the app quits.
+                    cx.quit();
+                }
+            },
         }
     }
 }
@@ -793,7 +7256,14 @@ pub fn start_gui() {
             unsafe { std::env::set_var("RUST_LOG", "trace") };
         }
     }
-    env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
+    logging::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
+
+    // Files to open at startup, relative to the current directory:
+    let paths = std::env::args_os()
+        .skip(1)
+        .map(PathBuf::from)
+        .map(|path| std::path::absolute(&path).unwrap_or(path))
+        .collect::<Vec<_>>();
 
     // let rt = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
     // let _rt_guard = rt.enter();
@@ -817,6 +7287,7 @@ pub fn start_gui() {
                     // gpui_component::Theme::change(gpui_component::ThemeMode::Light, Some(window), cx);
 
                     let main_ui = cx.new(|cx: &mut Context<'_, _>| PdfReader::new(window, cx));
+                    main_ui.update(cx, |reader, cx| reader.restore_session(paths, window, cx));
                     cx.new(|cx| Root::new(main_ui.into(), window, cx))
                 },
             )
@@ -824,3 +7295,82 @@ pub fn start_gui() {
         });
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Frame, RgbaImage};
+
+    /// A blank image that uses `width * height * 4` bytes.
+    fn blank_image(width: u32, height: u32) -> Arc<RenderImage> {
+        Arc::new(RenderImage::new([Frame::new(RgbaImage::new(
+            width, height,
+        ))]))
+    }
+
+    fn state_with_pages(page_count: usize) -> PdfPageCacheMutableState {
+        let mut state = PdfPageCacheMutableState::new(1);
+        state.images.resize_with(page_count, || None);
+        state
+    }
+
+    #[test]
+    fn identical_pages_are_counted_once() {
+        let mut state = state_with_pages(3);
+        let first = state.deduplicate(1, blank_image(10, 10));
+        let second = state.deduplicate(1, blank_image(10, 10));
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(state.deduplicated_bytes, 400);
+        state.images[0] = Some(Ok(first));
+        state.images[1] = Some(Ok(second.clone()));
+        state.placeholders.insert(1, second);
+        assert_eq!(state.cached_bytes(), 400);
+
+        state.retained.set_capacity(4);
+        state.retained.insert(2, blank_image(5, 5));
+        assert_eq!(state.cached_bytes(), 500);
+    }
+
+    #[test]
+    fn requested_pages_cover_two_frames() {
+        let mut requests = FrameRequests::default();
+        assert_eq!(requests.request(2..4), 2..4);
+        assert_eq!(requests.request(4..5), 2..5);
+
+        requests.next_frame();
+        // A stale layout is kept together with the previous frame's pages:
+        assert_eq!(requests.request(0..1), 0..5);
+        // The list's actual layout replaces the stale pages:
+        assert_eq!(requests.request(8..10), 2..10);
+        assert_eq!(requests.this_frame, 8..10);
+
+        requests.next_frame();
+        assert_eq!(requests.request(8..10), 8..10);
+        requests.next_frame();
+        requests.next_frame();
+        assert_eq!(requests.request(0..0), 0..0);
+    }
+
+    #[test]
+    fn retained_images_are_evicted_before_pages() {
+        let mut state = state_with_pages(4);
+        state.images[0] = Some(Ok(blank_image(10, 10)));
+        state.images[1] = Some(Ok(blank_image(10, 10)));
+        state.retained.set_capacity(4);
+        state.retained.insert(3, blank_image(10, 10));
+        state.memory_budget = Some(1000);
+
+        // Rendering page 2 near page 1 fits once the retained image is forgotten:
+        assert!(state.make_room(200, 1, 2));
+        assert!(state.retained.is_empty());
+        assert!(state.images[0].is_some());
+        assert!(state.images[1].is_some());
+
+        // Page 0 is farther from the center than page 1 which is about to be rendered:
+        assert!(state.make_room(600, 2, 1));
+        assert!(state.images[0].is_none());
+        assert!(state.images[1].is_some());
+        // Nothing else may be evicted:
+        assert!(!state.make_room(1000, 1, 1));
+    }
+}
@@ -0,0 +1,128 @@
+//! Find clickable links on a page.
+//!
+//! Links are `/Link` annotations in the page's `/Annots` array. The target is either given by a
+//! `/Dest` entry or by an action dictionary in `/A`.
+
+use hayro::Pdf;
+use hayro_syntax::object::dict::keys;
+use hayro_syntax::object::{Array, Dict, Name, Object, Rect};
+use hayro_syntax::page::Page;
+use serde::{Deserialize, Serialize};
+
+/// What happens when a link to a web page (or other URI) is clicked. Links within the document are
+/// always followed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkOpening {
+    /// Open the URI in the default browser.
+    Open,
+    /// Show the URI and ask before opening it.
+    #[default]
+    Confirm,
+    /// Never open URIs.
+    Block,
+}
+
+/// What happens when a link is clicked.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LinkAction {
+    /// Go to a page in this document.
+    GoTo { page: usize },
+    /// Open a URI, usually a web page.
+    Uri(String),
+    /// An action that could harm the user, like starting a program, running a script or sending
+    /// data somewhere. These are never executed, contains the action's `/S` name or the URI.
+    Suspicious(String),
+    /// An action kind that isn't supported, contains the action's `/S` name.
+    Unsupported(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Link {
+    /// Clickable area in unscaled page coordinates (the coordinate space of
+    /// [`crate::pdf::extract_features`] with default render settings).
+    pub rect: Rect,
+    pub action: LinkAction,
+}
+
+/// All links on a page.
+pub fn page_links(pdf: &Pdf, index: usize) -> Vec<Link> {
+    let Some(page) = pdf.pages().get(index) else {
+        return Vec::new();
+    };
+    let Some(annotations) = page.raw().get::<Array>(keys::ANNOTS) else {
+        return Vec::new();
+    };
+    let transform = page.initial_transform(true);
+    annotations
+        .iter::<Dict>()
+        .filter(|annotation| {
+            annotation
+                .get::<Name>(keys::SUBTYPE)
+                .is_some_and(|kind| &*kind == b"Link")
+        })
+        .filter_map(|annotation| {
+            let rect = annotation.get::<Rect>(keys::RECT)?;
+            let action = match annotation.get::<Dict>(keys::A) {
+                Some(action) => read_action(pdf, &action)?,
+                None => LinkAction::GoTo {
+                    page: resolve_destination(pdf, annotation.get::<Object>(keys::DEST)?)?,
+                },
+            };
+            Some(Link {
+                rect: transform.transform_rect_bbox(rect),
+                action,
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn read_action(pdf: &Pdf, action: &Dict<'_>) -> Option<LinkAction> {
+    let kind = action.get::<Name>(keys::S)?;
+    Some(match &*kind {
+        b"GoTo" => LinkAction::GoTo {
+            page: resolve_destination(pdf, action.get::<Object>(keys::D)?)?,
+        },
+        b"URI" => {
+            let uri = String::from_utf8_lossy(
+                &action.get::<hayro_syntax::object::String>(keys::URI)?.get(),
+            )
+            .into_owned();
+            if is_safe_uri(&uri) {
+                LinkAction::Uri(uri)
+            } else {
+                LinkAction::Suspicious(uri)
+            }
+        }
+        b"Launch" | b"JavaScript" | b"SubmitForm" | b"ImportData" => {
+            LinkAction::Suspicious(String::from_utf8_lossy(&kind).into_owned())
+        }
+        other => LinkAction::Unsupported(String::from_utf8_lossy(other).into_owned()),
+    })
+}
+
+/// `javascript:` and `file:` URIs can be as dangerous as the actions they replace, so only allow
+/// schemes that a browser or mail client is expected to handle.
+fn is_safe_uri(uri: &str) -> bool {
+    let Some((scheme, _)) = uri.split_once(':') else {
+        return false;
+    };
+    ["http", "https", "mailto"]
+        .iter()
+        .any(|safe| scheme.trim().eq_ignore_ascii_case(safe))
+}
+
+/// Find the page index that an explicit destination (`[page /XYZ left top zoom]` or similar)
+/// points to. Named destinations are not supported.
+pub(crate) fn resolve_destination(pdf: &Pdf, destination: Object<'_>) -> Option<usize> {
+    let destination = destination.into_array()?;
+    let target = destination.iter::<Object>().next()?;
+    if let Some(page_number) = target.clone().into_f32() {
+        // Remote destinations use page numbers instead of references:
+        return Some(page_number.max(0.) as usize);
+    }
+    let target = target.into_dict()?.obj_id()?;
+    pdf.pages()
+        .iter()
+        .position(|page: &Page<'_>| page.raw().obj_id() == Some(target))
+}
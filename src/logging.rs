@@ -0,0 +1,130 @@
+//! Logging to stderr (configured by `RUST_LOG`) where the log level can also be changed at runtime.
+//! Recent messages are kept in memory so that they can be shown inside the app.
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+/// Number of messages that are kept in memory for the log viewer.
+const MAX_RECENT_MESSAGES: usize = 500;
+
+/// Levels that [`cycle_level`] steps through. `None` uses the filter from `RUST_LOG`.
+const LEVEL_CYCLE: [Option<LevelFilter>; 7] = [
+    None,
+    Some(LevelFilter::Off),
+    Some(LevelFilter::Error),
+    Some(LevelFilter::Warn),
+    Some(LevelFilter::Info),
+    Some(LevelFilter::Debug),
+    Some(LevelFilter::Trace),
+];
+
+static LOGGER: OnceLock<AppLogger> = OnceLock::new();
+
+/// A message that was logged.
+#[derive(Clone, Debug)]
+pub struct LogMessage {
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+struct AppLogger {
+    /// Only used to decide what to log when there is no runtime override.
+    filtered: env_logger::Logger,
+    /// Writes every message it is given.
+    output: env_logger::Logger,
+    /// Index into [`LEVEL_CYCLE`].
+    level_override: AtomicUsize,
+    recent: Mutex<VecDeque<LogMessage>>,
+    /// Incremented for each new message, allows the UI to check if there is anything new to show.
+    generation: AtomicU64,
+}
+impl AppLogger {
+    fn level_override(&self) -> Option<LevelFilter> {
+        LEVEL_CYCLE[self.level_override.load(Ordering::Relaxed) % LEVEL_CYCLE.len()]
+    }
+}
+impl Log for AppLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        match self.level_override() {
+            Some(level) => metadata.level() <= level,
+            None => self.filtered.enabled(metadata),
+        }
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        {
+            let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+            if recent.len() >= MAX_RECENT_MESSAGES {
+                recent.pop_front();
+            }
+            recent.push_back(LogMessage {
+                level: record.level(),
+                target: record.target().to_owned(),
+                message: record.args().to_string(),
+            });
+        }
+        self.generation.fetch_add(1, Ordering::Relaxed);
+        self.output.log(record);
+    }
+
+    fn flush(&self) {
+        self.output.flush();
+    }
+}
+
+/// Install the logger, this replaces `env_logger::init`.
+pub fn init() {
+    let logger = LOGGER.get_or_init(|| AppLogger {
+        filtered: env_logger::Builder::from_default_env().build(),
+        output: env_logger::Builder::new()
+            .filter_level(LevelFilter::Trace)
+            .build(),
+        level_override: AtomicUsize::new(0),
+        recent: Mutex::new(VecDeque::with_capacity(MAX_RECENT_MESSAGES)),
+        generation: AtomicU64::new(0),
+    });
+    if log::set_logger(logger).is_ok() {
+        log::set_max_level(logger.filtered.filter());
+    }
+}
+
+/// The level set at runtime or `None` if the filter from `RUST_LOG` is used.
+pub fn level_override() -> Option<LevelFilter> {
+    LOGGER.get()?.level_override()
+}
+
+/// Switch to the next log level, returns the new level (`None` means that `RUST_LOG` is used).
+pub fn cycle_level() -> Option<LevelFilter> {
+    let logger = LOGGER.get()?;
+    let next = (logger.level_override.load(Ordering::Relaxed) + 1) % LEVEL_CYCLE.len();
+    logger.level_override.store(next, Ordering::Relaxed);
+    let level = LEVEL_CYCLE[next];
+    log::set_max_level(level.unwrap_or_else(|| logger.filtered.filter()));
+    level
+}
+
+/// Changes each time a message is logged.
+pub fn generation() -> u64 {
+    LOGGER
+        .get()
+        .map_or(0, |logger| logger.generation.load(Ordering::Relaxed))
+}
+
+/// Up to `count` of the most recently logged messages, oldest first.
+pub fn recent_messages(count: usize) -> Vec<LogMessage> {
+    let Some(logger) = LOGGER.get() else {
+        return Vec::new();
+    };
+    let recent = logger.recent.lock().unwrap_or_else(|e| e.into_inner());
+    recent
+        .iter()
+        .skip(recent.len().saturating_sub(count))
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,82 @@
+//! Measure distances and areas on a page, useful for technical drawings.
+
+use kurbo::Point;
+
+/// PDF units per inch (without a `/UserUnit` entry a PDF unit is 1/72 inch).
+const POINTS_PER_INCH: f64 = 72.;
+const MILLIMETERS_PER_INCH: f64 = 25.4;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MeasureUnit {
+    /// PDF units (typographic points for most documents).
+    #[default]
+    Points,
+    Millimeters,
+    Inches,
+}
+impl MeasureUnit {
+    pub fn next(self) -> Self {
+        match self {
+            MeasureUnit::Points => MeasureUnit::Millimeters,
+            MeasureUnit::Millimeters => MeasureUnit::Inches,
+            MeasureUnit::Inches => MeasureUnit::Points,
+        }
+    }
+    pub fn suffix(self) -> &'static str {
+        match self {
+            MeasureUnit::Points => "pt",
+            MeasureUnit::Millimeters => "mm",
+            MeasureUnit::Inches => "in",
+        }
+    }
+    /// How many of this unit one PDF unit corresponds to.
+    fn per_pdf_unit(self, user_unit: f64) -> f64 {
+        match self {
+            MeasureUnit::Points => 1.,
+            MeasureUnit::Millimeters => user_unit * MILLIMETERS_PER_INCH / POINTS_PER_INCH,
+            MeasureUnit::Inches => user_unit / POINTS_PER_INCH,
+        }
+    }
+}
+
+/// Points clicked on a page, in unscaled page coordinates.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Measurement {
+    pub page: usize,
+    pub points: Vec<Point>,
+}
+impl Measurement {
+    /// Total length of the line through all points, in PDF units.
+    pub fn length(&self) -> f64 {
+        self.points
+            .windows(2)
+            .map(|pair| pair[0].distance(pair[1]))
+            .sum()
+    }
+    /// Area of the polygon formed by the points, in square PDF units. `None` for fewer than three
+    /// points.
+    pub fn area(&self) -> Option<f64> {
+        if self.points.len() < 3 {
+            return None;
+        }
+        // Shoelace formula:
+        let doubled: f64 = self
+            .points
+            .iter()
+            .zip(self.points.iter().cycle().skip(1))
+            .map(|(a, b)| a.x * b.y - b.x * a.y)
+            .sum();
+        Some(doubled.abs() / 2.)
+    }
+
+    /// Describe the measurement, `scale` is the drawing scale (`50` for a 1:50 drawing).
+    pub fn label(&self, unit: MeasureUnit, user_unit: f64, scale: f64) -> String {
+        let factor = unit.per_pdf_unit(user_unit) * scale;
+        let suffix = unit.suffix();
+        let mut label = format!("{:.2} {suffix}", self.length() * factor);
+        if let Some(area) = self.area() {
+            label.push_str(&format!(", area {:.2} {suffix}²", area * factor * factor));
+        }
+        label
+    }
+}
@@ -0,0 +1,95 @@
+//! Read the document outline, also known as bookmarks or the table of contents.
+//!
+//! The outline is a tree of dictionaries starting at the `/Outlines` entry of the document
+//! catalog. Each item links to its first child with `/First` and to its next sibling with `/Next`.
+
+use crate::attachments::find_catalog;
+use crate::links::{LinkAction, read_action, resolve_destination};
+use crate::pdf::decode_text_string;
+use hayro::Pdf;
+use hayro_syntax::object::dict::keys;
+use hayro_syntax::object::{Dict, Object, ObjectIdentifier};
+use std::collections::HashSet;
+
+/// Guard against malformed (cyclic or absurdly deep) outlines.
+const MAX_OUTLINE_DEPTH: usize = 32;
+const MAX_OUTLINE_ITEMS: usize = 10_000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutlineItem {
+    pub title: String,
+    /// The page the item links to, `None` if the destination isn't supported (for example named
+    /// destinations) or points outside the document.
+    pub page: Option<usize>,
+    /// `true` if the document wants the item's children to be shown initially.
+    pub open: bool,
+    pub children: Vec<OutlineItem>,
+}
+
+/// The top level items of the document's outline, empty if it doesn't have one.
+pub fn read_outline(pdf: &Pdf) -> Vec<OutlineItem> {
+    let Some(first) = find_catalog(pdf)
+        .and_then(|catalog| catalog.get::<Dict>(keys::OUTLINES))
+        .and_then(|outlines| outlines.get::<Dict>(keys::FIRST))
+    else {
+        return Vec::new();
+    };
+    let mut visited = HashSet::new();
+    read_siblings(pdf, first, 0, &mut visited)
+}
+
+/// Read an item and all siblings that follow it.
+fn read_siblings(
+    pdf: &Pdf,
+    first: Dict<'_>,
+    depth: usize,
+    visited: &mut HashSet<ObjectIdentifier>,
+) -> Vec<OutlineItem> {
+    if depth > MAX_OUTLINE_DEPTH {
+        log::warn!("Outline is too deep, ignoring remaining items");
+        return Vec::new();
+    }
+    let page_count = pdf.pages().len();
+    let mut items = Vec::new();
+    let mut next = Some(first);
+    while let Some(item) = next {
+        if let Some(id) = item.obj_id() {
+            // Items are always indirect objects, a repeated one means the outline has a cycle:
+            if !visited.insert(id) {
+                log::warn!("Outline contains a cycle, ignoring remaining items");
+                break;
+            }
+        }
+        if visited.len() > MAX_OUTLINE_ITEMS {
+            log::warn!("Outline has too many items, ignoring remaining items");
+            break;
+        }
+        let title = item
+            .get::<hayro_syntax::object::String>(keys::TITLE)
+            .map(|title| decode_text_string(&title.get()))
+            .unwrap_or_default();
+        let page = match item.get::<Dict>(keys::A) {
+            Some(action) => match read_action(pdf, &action) {
+                Some(LinkAction::GoTo { page }) => Some(page),
+                _ => None,
+            },
+            None => item
+                .get::<Object>(keys::DEST)
+                .and_then(|destination| resolve_destination(pdf, destination)),
+        }
+        .filter(|&page| page < page_count);
+        let children = item
+            .get::<Dict>(keys::FIRST)
+            .map(|child| read_siblings(pdf, child, depth + 1, visited))
+            .unwrap_or_default();
+        items.push(OutlineItem {
+            title,
+            page,
+            // A negative count means that the item is closed:
+            open: item.get::<i32>(keys::COUNT).is_some_and(|count| count > 0),
+            children,
+        });
+        next = item.get::<Dict>(keys::NEXT);
+    }
+    items
+}
@@ -1,37 +1,274 @@
+use crate::compare;
+use crate::filters::FilterChain;
+use crate::progress::{CancellationToken, Cancelled, Progress, for_each_page};
 use gpui::RenderImage;
-use hayro::{Pixmap, RenderSettings, render};
+use hayro::{Pdf, Pixmap, RenderSettings, render};
 use hayro_interpret::font::Glyph;
 use hayro_interpret::{
     ClipPath, Context, Device, FillRule, GlyphDrawMode, Image, InterpreterSettings, Paint,
     PathDrawMode, SoftMask, interpret,
 };
+use hayro_syntax::LoadPdfError;
 use hayro_syntax::content::ops::TypedInstruction;
 use hayro_syntax::object::{Object, Rect};
 use hayro_syntax::page::Page;
 use image::{Frame, RgbaImage};
 use kurbo::{Affine, BezPath, Point, Shape};
 use std::borrow::Cow;
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::fmt;
 use std::fmt::Formatter;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ops::Range;
 use std::sync::Arc;
 
+/// Largest page side in PDF units that is rendered as is. The PDF specification limits pages to
+/// 14 400 units (200 inches), larger values usually come from malformed files.
+pub const MAX_PAGE_DIMENSION: f32 = 14_400.;
+
+/// Largest width or height in pixels that a page is rasterized at. This is also the largest texture
+/// that GPUI's renderer can upload on every system, larger images would leave their page blank.
+/// Pages are rendered at a lower resolution instead and stretched when shown.
+pub const MAX_PIXMAP_DIMENSION: f32 = 8192.;
+
+/// Text that is rotated more than this isn't considered when estimating how crooked a page is.
+const MAX_SKEW_DEGREES: f64 = 10.;
+
+/// Shrink page dimensions (in PDF units) that are implausibly large while keeping the aspect ratio.
+/// Values that are not finite or less than one unit are replaced by `1`.
+///
+/// ```
+/// use pdf_reader_gpui::pdf::{MAX_PAGE_DIMENSION, clamp_page_dimensions};
+///
+/// // A normal letter sized page is kept as is:
+/// assert_eq!(clamp_page_dimensions(612., 792.), (612., 792.));
+///
+/// // Huge pages are scaled down to fit:
+/// let (width, height) = clamp_page_dimensions(1e9, 5e8);
+/// assert_eq!(width, MAX_PAGE_DIMENSION);
+/// assert!((height - MAX_PAGE_DIMENSION / 2.).abs() < 0.01);
+///
+/// // Broken values don't propagate:
+/// assert_eq!(clamp_page_dimensions(f32::NAN, 0.), (1., 1.));
+/// assert_eq!(clamp_page_dimensions(f32::INFINITY, 100.), (1., 100.));
+/// ```
+pub fn clamp_page_dimensions(width: f32, height: f32) -> (f32, f32) {
+    let sanitize = |value: f32| {
+        if value.is_finite() && value >= 1. {
+            value
+        } else {
+            1.
+        }
+    };
+    let (width, height) = (sanitize(width), sanitize(height));
+    if width.max(height) <= MAX_PAGE_DIMENSION {
+        (width, height)
+    } else if width >= height {
+        (
+            MAX_PAGE_DIMENSION,
+            (height * (MAX_PAGE_DIMENSION / width)).max(1.),
+        )
+    } else {
+        (
+            (width * (MAX_PAGE_DIMENSION / height)).max(1.),
+            MAX_PAGE_DIMENSION,
+        )
+    }
+}
+
+/// The page's [`Page::render_dimensions`] limited by [`clamp_page_dimensions`].
+pub fn page_dimensions(page: &Page) -> (f32, f32) {
+    let (width, height) = page.render_dimensions();
+    clamp_page_dimensions(width, height)
+}
+
+/// How much a page's content must be scaled so that it is rendered at the size given by
+/// [`page_dimensions`], this is `1` for pages with plausible dimensions.
+pub fn page_dimension_scale(page: &Page) -> f32 {
+    let (raw_width, raw_height) = page.render_dimensions();
+    let (width, height) = page_dimensions(page);
+    if !(raw_width.is_finite() && raw_width >= 1. && raw_height.is_finite() && raw_height >= 1.) {
+        return 1.;
+    }
+    (width / raw_width).min(height / raw_height)
+}
+
+/// Factor that an image of `width` by `height` pixels must be scaled by to fit within
+/// [`MAX_PIXMAP_DIMENSION`], `1` if it already fits.
+///
+/// ```
+/// use pdf_reader_gpui::pdf::{MAX_PIXMAP_DIMENSION, pixmap_downscale};
+///
+/// assert_eq!(pixmap_downscale(1920., 1080.), 1.);
+/// assert_eq!(pixmap_downscale(MAX_PIXMAP_DIMENSION * 4., 100.), 0.25);
+/// assert_eq!(pixmap_downscale(100., MAX_PIXMAP_DIMENSION * 2.), 0.5);
+/// ```
+pub fn pixmap_downscale(width: f32, height: f32) -> f32 {
+    let largest = width.max(height);
+    if largest > MAX_PIXMAP_DIMENSION {
+        MAX_PIXMAP_DIMENSION / largest
+    } else {
+        1.
+    }
+}
+
+/// Lower the resolution of `render_settings` if the page would be rendered larger than
+/// [`MAX_PIXMAP_DIMENSION`].
+fn clamp_render_settings(page: &Page, render_settings: &RenderSettings) -> RenderSettings {
+    let (page_width, page_height) = page.render_dimensions();
+    let width = render_settings
+        .width
+        .map_or(page_width * render_settings.x_scale, f32::from);
+    let height = render_settings
+        .height
+        .map_or(page_height * render_settings.y_scale, f32::from);
+    let factor = pixmap_downscale(width, height);
+    if factor < 1. {
+        log::warn!(
+            "Page would be rendered at {width:.0}x{height:.0} pixels, reducing the resolution to {:.0}x{:.0}",
+            width * factor,
+            height * factor
+        );
+    }
+    let shrink = |size: u16| ((f32::from(size) * factor) as u16).max(1);
+    RenderSettings {
+        x_scale: render_settings.x_scale * factor,
+        y_scale: render_settings.y_scale * factor,
+        width: render_settings.width.map(shrink),
+        height: render_settings.height.map(shrink),
+    }
+}
+
+/// Clockwise rotation of a page in steps of 90 degrees.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PageRotation(u8);
+impl PageRotation {
+    /// Number of clockwise quarter turns in `0..4`.
+    pub fn quarter_turns(self) -> u8 {
+        self.0
+    }
+    pub fn clockwise(self) -> Self {
+        Self((self.0 + 1) % 4)
+    }
+    pub fn counterclockwise(self) -> Self {
+        Self((self.0 + 3) % 4)
+    }
+    /// Rotate by `self` and then by `other`.
+    pub fn then(self, other: Self) -> Self {
+        Self((self.0 + other.0) % 4)
+    }
+    /// `true` if the width and height of the page are swapped.
+    pub fn is_sideways(self) -> bool {
+        self.0 % 2 == 1
+    }
+    /// Map a position on the unrotated page, given as fractions of its width and height, to the
+    /// same kind of position on the rotated page.
+    pub fn rotate_fraction(self, (x, y): (f64, f64)) -> (f64, f64) {
+        match self.0 {
+            1 => (1. - y, x),
+            2 => (1. - x, 1. - y),
+            3 => (y, 1. - x),
+            _ => (x, y),
+        }
+    }
+    /// The inverse of [`Self::rotate_fraction`].
+    pub fn unrotate_fraction(self, position: (f64, f64)) -> (f64, f64) {
+        Self((4 - self.0) % 4).rotate_fraction(position)
+    }
+}
+
 /// Rasterize a PDF page and convert the result from a [`hayro::Pixmap`] to a [`gpui::RenderImage`].
+///
+/// If `compare_with` is a page from another version of the document then the differences between the
+/// pages are shown instead, see [`crate::compare`]. The page is rotated clockwise by
+/// `deskew_degrees` around its center, this is used to straighten crooked scans. Then the image is
+/// turned by `rotation` (swapping its width and height for sideways rotations) and afterwards the
+/// `filters` are applied to the pixels. The resolution is reduced if the image would be larger than
+/// [`MAX_PIXMAP_DIMENSION`].
 #[cfg_attr(feature = "hotpath", hotpath::measure)]
+#[allow(clippy::too_many_arguments)]
 pub fn rasterize_pdf_page(
     page: &Page,
     interpreter_settings: &InterpreterSettings,
     render_settings: &RenderSettings,
-) -> Arc<RenderImage> {
-    let pixmap = render(page, interpreter_settings, render_settings);
+    compare_with: Option<&Page>,
+    deskew_degrees: f32,
+    rotation: PageRotation,
+    filters: &FilterChain,
+    is_cancelled: &dyn Fn() -> bool,
+) -> Result<Arc<RenderImage>, RasterizeError> {
+    let render_settings = clamp_render_settings(page, render_settings);
+    // hayro can't be interrupted while it renders, so only check between the steps:
+    let pixmap = render(page, interpreter_settings, &render_settings);
+    if is_cancelled() {
+        return Err(RasterizeError::Cancelled);
+    }
     // extract_features(page, interpreter_settings, render_settings, &mut |feature| eprintln!("{feature:?}"));
-    Arc::new(pixmap_to_gpui_image(pixmap))
+    if compare_with.is_none() && deskew_degrees == 0. && rotation == PageRotation::default() {
+        return pixmap_to_gpui_image(pixmap, filters).map(Arc::new);
+    }
+    let (pixel_width, pixel_height) = (pixmap.width(), pixmap.height());
+    let mut width = u32::from(pixel_width);
+    let mut height = u32::from(pixel_height);
+    let mut data = pixmap.take_u8();
+    if let Some(other) = compare_with {
+        let other =
+            compare::render_matching(other, interpreter_settings, pixel_width, pixel_height);
+        let changed = compare::highlight_differences(&mut data, &other);
+        log::trace!("Compared page, {changed} pixels differ");
+        if is_cancelled() {
+            return Err(RasterizeError::Cancelled);
+        }
+    }
+    if deskew_degrees != 0. {
+        data = rotate_rgba(&data, width, height, deskew_degrees);
+    }
+    if rotation != PageRotation::default() {
+        let len = data.len();
+        let image = RgbaImage::from_raw(width, height, data)
+            .ok_or(RasterizeError::InvalidDimensions { width, height, len })?;
+        let rotated = match rotation.quarter_turns() {
+            1 => image::imageops::rotate90(&image),
+            2 => image::imageops::rotate180(&image),
+            _ => image::imageops::rotate270(&image),
+        };
+        (width, height) = rotated.dimensions();
+        data = rotated.into_raw();
+    }
+    rgba_to_gpui_image(width, height, data, filters).map(Arc::new)
 }
 
+/// A page could not be turned into an image.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RasterizeError {
+    /// The rendered image has no pixels, for example because the page's media box is empty.
+    EmptyImage,
+    /// The amount of pixel data doesn't match the image's dimensions.
+    InvalidDimensions { width: u32, height: u32, len: usize },
+    /// The image was no longer needed, for example because the zoom changed while rendering.
+    Cancelled,
+}
+impl fmt::Display for RasterizeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RasterizeError::EmptyImage => f.write_str("the page has no visible area"),
+            RasterizeError::InvalidDimensions { width, height, len } => write!(
+                f,
+                "rendered {len} bytes of pixel data which doesn't fit a {width}x{height} image"
+            ),
+            RasterizeError::Cancelled => f.write_str("rendering was cancelled"),
+        }
+    }
+}
+impl std::error::Error for RasterizeError {}
+
 /// Convert a rendered PDF in the form of a [`Pixmap`] into a GPUI [`RenderImage`]. This conversion
 /// doesn't allocate but does need to traverse the whole image data buffer to convert colors from
-/// `RGBA` to `BGRA`.
-pub fn pixmap_to_gpui_image(pixmap: Pixmap) -> RenderImage {
+/// `RGBA` to `BGRA`, the `filters` are applied during the same traversal.
+pub fn pixmap_to_gpui_image(
+    pixmap: Pixmap,
+    filters: &FilterChain,
+) -> Result<RenderImage, RasterizeError> {
     // The code below that converts to RenderImage was inspired by code from:
     // <gpui::ImageDecoder as Asset>::load
     //
@@ -40,53 +277,551 @@ pub fn pixmap_to_gpui_image(pixmap: Pixmap) -> RenderImage {
 
     let width = u32::from(pixmap.width());
     let height = u32::from(pixmap.height());
-    let mut data = pixmap.take_u8();
+    rgba_to_gpui_image(width, height, pixmap.take_u8(), filters)
+}
 
-    // Convert from RGBA to BGRA.
-    for pixel in data.chunks_exact_mut(4) {
+/// Apply `filters` to `RGBA` pixel data and convert it into a GPUI [`RenderImage`].
+///
+/// No color conversion is needed here: `hayro-interpret` converts every color space in the
+/// document (including `ICCBased` ones, using `moxcms`) to sRGB and GPUI treats the bytes of
+/// uploaded images as sRGB as well. Only the channel order differs.
+fn rgba_to_gpui_image(
+    width: u32,
+    height: u32,
+    mut data: Vec<u8>,
+    filters: &FilterChain,
+) -> Result<RenderImage, RasterizeError> {
+    if width == 0 || height == 0 {
+        return Err(RasterizeError::EmptyImage);
+    }
+    // Convert from RGBA to BGRA, filtering in the same loop to avoid another pass over the pixels:
+    for pixel in data.as_chunks_mut::<4>().0 {
+        filters.apply(pixel);
         pixel.swap(0, 2);
     }
 
-    let image_data = RgbaImage::from_raw(width, height, data).expect("incorrect image dimensions");
-    RenderImage::new([Frame::new(image_data)])
+    let len = data.len();
+    let image_data = RgbaImage::from_raw(width, height, data)
+        .ok_or(RasterizeError::InvalidDimensions { width, height, len })?;
+    Ok(RenderImage::new([Frame::new(image_data)]))
+}
+
+/// Render part of a page and encode it as a PNG file. `region` is in unscaled page coordinates and
+/// `scale` is the number of pixels per PDF unit, it is lowered the same way as for
+/// [`render_page_png`] if the whole page would be larger than [`MAX_PIXMAP_DIMENSION`]. Returns
+/// `None` if the region is empty.
+pub fn render_region_png(
+    page: &Page,
+    interpreter_settings: &InterpreterSettings,
+    scale: f32,
+    region: Rect,
+) -> Option<Vec<u8>> {
+    let render_settings = clamp_render_settings(
+        page,
+        &RenderSettings {
+            x_scale: scale,
+            y_scale: scale,
+            ..Default::default()
+        },
+    );
+    // hayro always renders from the page's top left corner, so render the page and then crop:
+    let pixmap = render(page, interpreter_settings, &render_settings);
+    let (width, height) = (u32::from(pixmap.width()), u32::from(pixmap.height()));
+    let scale = f64::from(render_settings.x_scale);
+    let x0 = ((region.x0 * scale).floor().max(0.) as u32).min(width);
+    let y0 = ((region.y0 * scale).floor().max(0.) as u32).min(height);
+    let x1 = ((region.x1 * scale).ceil().max(0.) as u32).min(width);
+    let y1 = ((region.y1 * scale).ceil().max(0.) as u32).min(height);
+    if x1 <= x0 || y1 <= y0 {
+        return None;
+    }
+    let image = RgbaImage::from_raw(width, height, pixmap.take_u8())?;
+    let cropped = image::imageops::crop_imm(&image, x0, y0, x1 - x0, y1 - y0).to_image();
+    let mut png = Vec::new();
+    if let Err(e) = cropped.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png) {
+        log::error!("Failed to encode PNG: {e}");
+        return None;
+    }
+    Some(png)
+}
+
+/// Render a whole page as a PNG image, `scale` is pixels per PDF unit (so `dpi / 72`).
+pub fn render_page_png(
+    page: &Page,
+    interpreter_settings: &InterpreterSettings,
+    scale: f32,
+) -> Vec<u8> {
+    let render_settings = clamp_render_settings(
+        page,
+        &RenderSettings {
+            x_scale: scale,
+            y_scale: scale,
+            ..Default::default()
+        },
+    );
+    render(page, interpreter_settings, &render_settings).take_png()
+}
+
+/// Render a page of a PDF file without any GUI, for example in tests or scripts. `scale` is
+/// pixels per PDF unit (so `dpi / 72`) and the resolution is reduced if the image would be larger
+/// than [`MAX_PIXMAP_DIMENSION`].
+///
+/// ```
+/// use pdf_reader_gpui::pdf::{RenderPageError, render_page_image};
+/// use std::sync::Arc;
+///
+/// let result = render_page_image(Arc::new(b"not a PDF".to_vec()), 0, 1.);
+/// assert!(matches!(result, Err(RenderPageError::Load(_))));
+/// ```
+pub fn render_page_image(
+    pdf_data: Arc<Vec<u8>>,
+    page_index: usize,
+    scale: f32,
+) -> Result<RgbaImage, RenderPageError> {
+    let pdf = load_pdf(pdf_data).map_err(RenderPageError::Load)?;
+    let pages = pdf.pages();
+    let page = pages.get(page_index).ok_or(RenderPageError::NoSuchPage {
+        index: page_index,
+        page_count: pages.len(),
+    })?;
+    let render_settings = clamp_render_settings(
+        page,
+        &RenderSettings {
+            x_scale: scale,
+            y_scale: scale,
+            ..Default::default()
+        },
+    );
+    let pixmap = render(page, &InterpreterSettings::default(), &render_settings);
+    let (width, height) = (u32::from(pixmap.width()), u32::from(pixmap.height()));
+    if width == 0 || height == 0 {
+        return Err(RenderPageError::Rasterize(RasterizeError::EmptyImage));
+    }
+    let data = pixmap.take_u8();
+    let len = data.len();
+    RgbaImage::from_raw(width, height, data).ok_or(RenderPageError::Rasterize(
+        RasterizeError::InvalidDimensions { width, height, len },
+    ))
+}
+
+/// Why [`render_page_image`] failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RenderPageError {
+    Load(LoadError),
+    /// The document has fewer pages than the requested index.
+    NoSuchPage {
+        index: usize,
+        page_count: usize,
+    },
+    Rasterize(RasterizeError),
+}
+impl fmt::Display for RenderPageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderPageError::Load(e) => e.fmt(f),
+            RenderPageError::NoSuchPage { index, page_count } => write!(
+                f,
+                "page {} was requested but the document has {page_count} pages",
+                index + 1
+            ),
+            RenderPageError::Rasterize(e) => e.fmt(f),
+        }
+    }
+}
+impl std::error::Error for RenderPageError {}
+
+/// Parse a 1-based page number like `3` or an inclusive range like `2-5` into 0-based page
+/// indexes. The end of a range is limited to `page_count`, `None` if no page would be included.
+///
+/// ```
+/// use pdf_reader_gpui::pdf::parse_page_range;
+///
+/// assert_eq!(parse_page_range("3", 10), Some(2..3));
+/// assert_eq!(parse_page_range(" 2 - 5 ", 10), Some(1..5));
+/// assert_eq!(parse_page_range("8-20", 10), Some(7..10));
+/// assert_eq!(parse_page_range("5-2", 10), None);
+/// assert_eq!(parse_page_range("0", 10), None);
+/// assert_eq!(parse_page_range("11", 10), None);
+/// assert_eq!(parse_page_range("all", 10), None);
+/// ```
+pub fn parse_page_range(text: &str, page_count: usize) -> Option<Range<usize>> {
+    let (first, last) = text.split_once('-').unwrap_or((text, text));
+    let first = first.trim().parse::<usize>().ok()?.checked_sub(1)?;
+    let last = last.trim().parse::<usize>().ok()?.min(page_count);
+    (first < last).then_some(first..last)
+}
+
+/// Rotate `RGBA` pixel data clockwise around its center while keeping the image size. Corners
+/// that are rotated out of the image are cut off and areas that come into view are white.
+fn rotate_rgba(data: &[u8], width: u32, height: u32, degrees: f32) -> Vec<u8> {
+    let (width, height) = (width as usize, height as usize);
+    let mut rotated = vec![u8::MAX; data.len()];
+    if width == 0 || height == 0 {
+        return rotated;
+    }
+    // Map each output pixel back to where it came from (so rotate in the opposite direction):
+    let (sin, cos) = (-degrees.to_radians()).sin_cos();
+    let (center_x, center_y) = (width as f32 / 2., height as f32 / 2.);
+    let (max_x, max_y) = ((width - 1) as f32, (height - 1) as f32);
+
+    for y in 0..height {
+        for x in 0..width {
+            let (dx, dy) = (x as f32 + 0.5 - center_x, y as f32 + 0.5 - center_y);
+            let source_x = cos * dx - sin * dy + center_x - 0.5;
+            let source_y = sin * dx + cos * dy + center_y - 0.5;
+            if !(0.0..=max_x).contains(&source_x) || !(0.0..=max_y).contains(&source_y) {
+                continue;
+            }
+            // Bilinear interpolation between the 4 closest source pixels:
+            let (x0, y0) = (source_x.floor() as usize, source_y.floor() as usize);
+            let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+            let (fx, fy) = (source_x - x0 as f32, source_y - y0 as f32);
+            for channel in 0..4 {
+                let sample = |x: usize, y: usize| f32::from(data[(y * width + x) * 4 + channel]);
+                let top = sample(x0, y0) * (1. - fx) + sample(x1, y0) * fx;
+                let bottom = sample(x0, y1) * (1. - fx) + sample(x1, y1) * fx;
+                rotated[(y * width + x) * 4 + channel] =
+                    (top * (1. - fy) + bottom * fy).round() as u8;
+            }
+        }
+    }
+    rotated
+}
+
+/// Estimate how many degrees the text on a page is rotated clockwise, rotating the page by the
+/// negated angle straightens it. This relies on the page having a text layer, which is the case
+/// for scans that have been processed by OCR. Returns `None` if no text was found.
+pub fn estimate_skew_degrees(
+    page: &Page,
+    interpreter_settings: &InterpreterSettings,
+) -> Option<f32> {
+    let mut angles = Vec::new();
+    extract_features(
+        page,
+        interpreter_settings,
+        &RenderSettings::default(),
+        &mut |feature| match feature {
+            PdfFeature::Text { angle, .. } => {
+                let degrees = angle.to_degrees();
+                if degrees.abs() <= MAX_SKEW_DEGREES {
+                    angles.push(degrees);
+                }
+            }
+            PdfFeature::Path(_) | PdfFeature::Image { .. } => {}
+        },
+    );
+    if angles.is_empty() {
+        return None;
+    }
+    // The median ignores a few lines of intentionally rotated text:
+    angles.sort_by(f64::total_cmp);
+    Some(angles[angles.len() / 2] as f32)
+}
+
+/// Parse a document, this reads the cross-reference table but not the pages' content.
+pub fn load_pdf(data: Arc<Vec<u8>>) -> Result<Arc<Pdf>, LoadError> {
+    Pdf::new(data).map(Arc::new).map_err(LoadError::from)
+}
+
+/// A document couldn't be loaded, the [`Display`](fmt::Display) implementation explains why.
+///
+/// Documents that are encrypted with an empty user password are decrypted when loaded, but there
+/// is no way to give `hayro` a password so other encrypted documents can't be opened.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoadError {
+    /// Protected by a password or an unsupported encryption.
+    Encrypted(String),
+    /// Not a PDF file or too damaged to be read.
+    Invalid(String),
+}
+impl From<LoadPdfError> for LoadError {
+    fn from(error: LoadPdfError) -> Self {
+        match error {
+            LoadPdfError::Decryption(e) => LoadError::Encrypted(format!("{e:?}")),
+            LoadPdfError::Invalid => LoadError::Invalid(format!("{error:?}")),
+        }
+    }
+}
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Encrypted(details) => write!(
+                f,
+                "This PDF is protected by a password or an unsupported encryption and can't be opened.\n({details})"
+            ),
+            LoadError::Invalid(details) => write!(f, "Failed to load PDF:\n{details}"),
+        }
+    }
+}
+impl std::error::Error for LoadError {}
+
+/// Hash the pixels of an image's first frame, used to find pages that look identical.
+pub fn image_content_hash(image: &RenderImage) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    let size = image.size(0);
+    (size.width.0, size.height.0).hash(&mut hasher);
+    image.as_bytes(0).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Decode a PDF "text string" (for example a file name or an outline title). These are either
+/// `UTF-16BE` with a byte order mark or use `PDFDocEncoding` which is close enough to `Latin-1`
+/// that we treat it as such.
+pub fn decode_text_string(bytes: &[u8]) -> String {
+    if let Some(utf16) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        String::from_utf16_lossy(
+            &utf16
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect::<Vec<_>>(),
+        )
+    } else if let Some(utf8) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        String::from_utf8_lossy(utf8).into_owned()
+    } else {
+        bytes.iter().map(|&byte| char::from(byte)).collect()
+    }
+}
+
+/// Decode the bytes shown by a text operation (see [`PdfFeature::Text`]). These are in the font's
+/// encoding which this doesn't know, so guess: fonts with two byte codes often use `UTF-16BE`
+/// without a byte order mark, which shows up as `NUL` bytes before ASCII letters. Otherwise the
+/// bytes are decoded like [`decode_text_string`].
+///
+/// ```
+/// use pdf_reader_gpui::pdf::decode_shown_text;
+///
+/// assert_eq!(decode_shown_text(b"Hello"), "Hello");
+/// assert_eq!(decode_shown_text(b"\0H\0i"), "Hi");
+/// assert_eq!(decode_shown_text(b"\n\0H\0i"), "\nHi");
+/// assert_eq!(decode_shown_text(b"caf\xE9"), "café");
+/// ```
+pub fn decode_shown_text(bytes: &[u8]) -> String {
+    // Line breaks are added as a single byte in front of the text:
+    if let Some(rest) = bytes.strip_prefix(b"\n") {
+        return format!("\n{}", decode_shown_text(rest));
+    }
+    if bytes.contains(&0)
+        && let Some(utf16) = decode_utf16_be(bytes)
+    {
+        return utf16;
+    }
+    decode_text_string(bytes)
+}
+
+/// Decode text with two bytes per character as `UTF-16BE` without a byte order mark. Returns
+/// `None` if the bytes don't decode to printable text.
+///
+/// ```
+/// use pdf_reader_gpui::pdf::decode_utf16_be;
+///
+/// assert_eq!(decode_utf16_be(b"\0H\0i").as_deref(), Some("Hi"));
+/// assert_eq!(decode_utf16_be(b"odd"), None);
+/// // Control characters mean that the text uses some other encoding:
+/// assert_eq!(decode_utf16_be(b"\0\x01"), None);
+/// ```
+pub fn decode_utf16_be(bytes: &[u8]) -> Option<String> {
+    if !bytes.len().is_multiple_of(2) || bytes.starts_with(&[0xFE, 0xFF]) {
+        return None;
+    }
+    let utf16 = String::from_utf16(
+        &bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect::<Vec<_>>(),
+    )
+    .ok()?;
+    (!utf16.chars().any(|c| c.is_control() && !c.is_whitespace())).then_some(utf16)
 }
 
 #[derive(Clone, PartialEq)]
 pub enum PdfFeature<'a> {
-    Text { text: Cow<'a, [u8]>, rect: Rect },
+    Text {
+        text: Cow<'a, [u8]>,
+        rect: Rect,
+        /// Clockwise rotation of the text's baseline in radians, `0` for horizontal text.
+        angle: f64,
+    },
+    /// A filled or stroked shape, for example a line or a rectangle.
+    Path(PagePath),
+    /// A raster image, only reported by [`extract_images`] since decoding images is slow.
+    Image {
+        /// Non-premultiplied `RGBA` pixels at the image's own resolution.
+        data: RgbaImage,
+        /// Area that the image covers on the page, it might be rotated or flipped within it.
+        rect: Rect,
+    },
 }
 impl PdfFeature<'_> {
     pub fn into_owned(self) -> PdfFeature<'static> {
         match self {
-            PdfFeature::Text { text, rect } => PdfFeature::Text {
+            PdfFeature::Text { text, rect, angle } => PdfFeature::Text {
                 text: Cow::Owned(text.into_owned()),
                 rect,
+                angle,
             },
+            PdfFeature::Path(path) => PdfFeature::Path(path),
+            PdfFeature::Image { data, rect } => PdfFeature::Image { data, rect },
         }
     }
 }
+
+/// A path that was drawn on a page, coordinates are in the same pixel space as the rendered page.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PagePath {
+    pub path: BezPath,
+    pub paint: PathPaint,
+    pub draw_mode: PathMode,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PathPaint {
+    /// Non-premultiplied RGBA color with components in `0..=1`.
+    Color([f32; 4]),
+    /// Painted with a pattern or shading (for example a gradient).
+    Pattern,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PathMode {
+    Fill,
+    Stroke {
+        /// Width of the line in pixels.
+        line_width: f64,
+    },
+}
+
+/// The text on a page in content stream order, which is usually but not always reading order.
+pub fn extract_text(page: &Page, interpreter_settings: &InterpreterSettings) -> String {
+    let mut text = String::new();
+    extract_features(
+        page,
+        interpreter_settings,
+        &RenderSettings::default(),
+        &mut |feature| {
+            if let PdfFeature::Text { text: part, .. } = feature {
+                text.push_str(&decode_shown_text(&part));
+            }
+        },
+    );
+    text
+}
+
+/// The text of every page, see [`extract_text`].
+pub fn extract_document_text(
+    pdf: &Pdf,
+    interpreter_settings: &InterpreterSettings,
+    cancel: &CancellationToken,
+    on_progress: &mut dyn FnMut(Progress),
+) -> Result<Vec<String>, Cancelled> {
+    for_each_page(pdf, cancel, on_progress, |_index, page| {
+        extract_text(page, interpreter_settings)
+    })
+}
+
+/// Collect all paths that are drawn on a page, in drawing order.
+pub fn extract_paths(
+    page: &Page,
+    interpreter_settings: &InterpreterSettings,
+    render_settings: &RenderSettings,
+) -> Vec<PagePath> {
+    let mut paths = Vec::new();
+    extract_features(
+        page,
+        interpreter_settings,
+        render_settings,
+        &mut |feature| {
+            if let PdfFeature::Path(path) = feature {
+                paths.push(path);
+            }
+        },
+    );
+    paths
+}
 impl fmt::Debug for PdfFeature<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            PdfFeature::Text { text, rect } => f
+            PdfFeature::Text { text, rect, angle } => f
                 .debug_struct("PdfFeature::Text")
                 .field(
                     "text",
                     //&String::from_utf16_lossy(&text.chunks_exact(2).map(|bytes| u16::from_be_bytes(bytes.try_into().unwrap())).collect::<Vec<_>>()),
-                    &String::from_utf8_lossy(&*text)
+                    &String::from_utf8_lossy(text),
                 )
                 .field("rect", rect)
+                .field("angle", angle)
+                .finish(),
+            PdfFeature::Path(path) => f.debug_tuple("PdfFeature::Path").field(path).finish(),
+            PdfFeature::Image { data, rect } => f
+                .debug_struct("PdfFeature::Image")
+                .field("dimensions", &data.dimensions())
+                .field("rect", rect)
                 .finish(),
         }
     }
 }
 
+/// An image that was drawn on a page.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExtractedImage {
+    /// Non-premultiplied `RGBA` pixels at the image's own resolution.
+    pub data: RgbaImage,
+    /// Area that the image covers in unscaled page coordinates.
+    pub rect: Rect,
+}
+impl ExtractedImage {
+    /// Encode the image as a PNG file.
+    pub fn to_png(&self) -> Result<Vec<u8>, image::ImageError> {
+        let mut png = Vec::new();
+        self.data
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)?;
+        Ok(png)
+    }
+}
+
+/// Collect the raster images on a page in drawing order. Stencil masks (single color shapes that
+/// are stored as images) are skipped.
+pub fn extract_images(
+    page: &Page,
+    interpreter_settings: &InterpreterSettings,
+) -> Vec<ExtractedImage> {
+    let mut images = Vec::new();
+    extract_features_inner(
+        page,
+        interpreter_settings,
+        &RenderSettings::default(),
+        true,
+        &mut |feature| {
+            if let PdfFeature::Image { data, rect } = feature {
+                images.push(ExtractedImage { data, rect });
+            }
+        },
+    );
+    images
+}
+
+/// Report the text and paths on a page. Images are not decoded, use [`extract_images`] for those.
 #[cfg_attr(feature = "hotpath", hotpath::measure)]
 pub fn extract_features(
     page: &Page,
     interpreter_settings: &InterpreterSettings,
     render_settings: &RenderSettings,
     handle_feature: &mut dyn FnMut(PdfFeature<'_>),
+) {
+    extract_features_inner(
+        page,
+        interpreter_settings,
+        render_settings,
+        false,
+        handle_feature,
+    );
+}
+
+fn extract_features_inner(
+    page: &Page,
+    interpreter_settings: &InterpreterSettings,
+    render_settings: &RenderSettings,
+    decode_images: bool,
+    handle_feature: &mut dyn FnMut(PdfFeature<'_>),
 ) {
     // Adapted from `hayro::render` but the device was changed to `FeatureExtractor` and some rendering code was removed.
 
@@ -112,6 +847,10 @@ pub fn extract_features(
     let shared = FeatureExtractorState {
         current_op: Cell::new(None),
         text_region: Cell::new(None),
+        text_angle: Cell::new(None),
+        paths: RefCell::new(Vec::new()),
+        decode_images,
+        images: RefCell::new(Vec::new()),
     };
     let mut device = FeatureExtractor { shared: &shared };
 
@@ -127,6 +866,7 @@ pub fn extract_features(
         std::iter::from_fn(|| {
             let op = ops.next();
             let prev = shared.current_op.replace(op.clone());
+            let angle = shared.text_angle.take().unwrap_or(0.);
             if let (Some(rect), Some(prev)) = (shared.text_region.take(), prev) {
                 data.clear();
                 match prev {
@@ -136,21 +876,21 @@ pub fn extract_features(
                         data.push(b'\n');
                     }
                     TypedInstruction::ShowText(text) => {
-                        data.extend_from_slice(&*text.0.get());
+                        data.extend_from_slice(&text.0.get());
                     }
                     TypedInstruction::NextLineAndShowText(text) => {
                         data.push(b'\n');
-                        data.extend_from_slice(&*text.0.get());
+                        data.extend_from_slice(&text.0.get());
                     }
                     TypedInstruction::ShowTextWithParameters(text) => {
                         data.push(b'\n');
-                        data.extend_from_slice(&*text.2.get());
+                        data.extend_from_slice(&text.2.get());
                     }
                     TypedInstruction::ShowTexts(texts) => {
                         for obj in texts.0.iter::<Object>() {
                             if let Some(_adjustment) = obj.clone().into_f32() {
                             } else if let Some(text) = obj.into_string() {
-                                data.extend_from_slice(&*text.get());
+                                data.extend_from_slice(&text.get());
                             }
                         }
                     }
@@ -163,8 +903,15 @@ pub fn extract_features(
                 handle_feature(PdfFeature::Text {
                     rect,
                     text: Cow::Borrowed(data.as_slice()),
+                    angle,
                 });
             }
+            for path in shared.paths.borrow_mut().drain(..) {
+                handle_feature(PdfFeature::Path(path));
+            }
+            for (data, rect) in shared.images.borrow_mut().drain(..) {
+                handle_feature(PdfFeature::Image { data, rect });
+            }
 
             op
         }),
@@ -174,11 +921,24 @@ pub fn extract_features(
     );
 
     device.pop_clip_path();
+    for path in shared.paths.take() {
+        handle_feature(PdfFeature::Path(path));
+    }
+    for (data, rect) in shared.images.take() {
+        handle_feature(PdfFeature::Image { data, rect });
+    }
 }
 
 struct FeatureExtractorState<'pdf> {
     current_op: Cell<Option<TypedInstruction<'pdf>>>,
     text_region: Cell<Option<Rect>>,
+    /// Baseline angle of the first glyph in [`Self::text_region`].
+    text_angle: Cell<Option<f64>>,
+    /// Paths drawn by the current operation, they are reported once the next operation is read.
+    paths: RefCell<Vec<PagePath>>,
+    decode_images: bool,
+    /// Images drawn by the current operation, reported together with [`Self::paths`].
+    images: RefCell<Vec<(RgbaImage, Rect)>>,
 }
 
 /// A [`hayro_interpret::Device`] that is used as an "output" for PDF rendering.
@@ -194,11 +954,27 @@ impl<'a, 'out, 'pdf> Device<'a> for FeatureExtractor<'out, 'pdf> {
 
     fn draw_path(
         &mut self,
-        _path: &BezPath,
-        _transform: Affine,
-        _paint: &Paint<'a>,
-        _draw_mode: &PathDrawMode,
+        path: &BezPath,
+        transform: Affine,
+        paint: &Paint<'a>,
+        draw_mode: &PathDrawMode,
     ) {
+        let paint = match paint {
+            Paint::Color(color) => PathPaint::Color(color.to_rgba().components()),
+            _ => PathPaint::Pattern,
+        };
+        let draw_mode = match draw_mode {
+            PathDrawMode::Fill(_) => PathMode::Fill,
+            PathDrawMode::Stroke(stroke) => PathMode::Stroke {
+                // Approximate the scaling for non-uniform transforms:
+                line_width: stroke.line_width as f64 * transform.determinant().abs().sqrt(),
+            },
+        };
+        self.shared.paths.borrow_mut().push(PagePath {
+            path: transform * path.clone(),
+            paint,
+            draw_mode,
+        });
     }
 
     fn push_clip_path(&mut self, _clip_path: &ClipPath) {}
@@ -220,6 +996,11 @@ impl<'a, 'out, 'pdf> Device<'a> for FeatureExtractor<'out, 'pdf> {
         let top_left = transform * glyph_transform * Point::new(0., 0.);
         let bottom_right = transform * glyph_transform * Point::new(1., 1.);
         let rect = Rect::from_points(top_left, bottom_right);
+        if self.shared.text_angle.get().is_none() {
+            // Direction of the glyph's x-axis in device space:
+            let [a, b, ..] = (transform * glyph_transform).as_coeffs();
+            self.shared.text_angle.set(Some(b.atan2(a)));
+        }
         self.shared
             .text_region
             .set(Some(if let Some(prev) = self.shared.text_region.get() {
@@ -229,8 +1010,35 @@ impl<'a, 'out, 'pdf> Device<'a> for FeatureExtractor<'out, 'pdf> {
             }));
     }
 
-    fn draw_image(&mut self, _image: Image<'a, '_>, _transform: Affine) {
-        // TODO: extract image (see example linked in struct's doc-comment above)
+    fn draw_image(&mut self, image: Image<'a, '_>, transform: Affine) {
+        if !self.shared.decode_images {
+            return;
+        }
+        // Stencils are masks that are filled with the current color, not pictures:
+        let Image::Raster(raster) = image else {
+            return;
+        };
+        raster.with_rgba(|rgb, alpha| {
+            let (width, height) = (rgb.width, rgb.height);
+            let alpha = alpha.filter(|alpha| (alpha.width, alpha.height) == (width, height));
+            let mut data = Vec::with_capacity(width as usize * height as usize * 4);
+            for (index, pixel) in rgb.data.chunks_exact(3).enumerate() {
+                data.extend_from_slice(pixel);
+                data.push(alpha.as_ref().map_or(u8::MAX, |alpha| alpha.data[index]));
+            }
+            let Some(data) = RgbaImage::from_raw(width, height, data) else {
+                log::warn!("Skipped image with invalid dimensions {width}x{height}");
+                return;
+            };
+            // The transform maps the image's pixel grid to the page:
+            let rect = transform.transform_rect_bbox(Rect::new(
+                0.,
+                0.,
+                f64::from(width),
+                f64::from(height),
+            ));
+            self.shared.images.borrow_mut().push((data, rect));
+        });
     }
 
     fn pop_clip_path(&mut self) {}
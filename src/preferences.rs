@@ -0,0 +1,474 @@
+//! A window that shows every user preference in one place.
+//!
+//! The window edits the [`Settings`] owned by the [`PdfReader`] that opened it, so changes are saved
+//! and applied as soon as they are made.
+
+use crate::PdfReader;
+use crate::filters::{ColorFilter, FilterKind, HighContrastPalette, InvertMode};
+use crate::image_cache::ImageCacheStrategy;
+use crate::links::LinkOpening;
+use crate::settings::{CanvasColor, InitialZoom, LastTabClosed, Settings};
+use crate::tabs::Easing;
+use gpui::prelude::FluentBuilder;
+use gpui::{
+    App, Context, IntoElement, ParentElement, Render, SharedString, Styled, Subscription,
+    WeakEntity, Window, div, px,
+};
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::checkbox::Checkbox;
+use gpui_component::{ActiveTheme, Disableable, StyledExt, h_flex, v_flex};
+use std::num::NonZeroUsize;
+use std::rc::Rc;
+
+pub struct PreferencesView {
+    reader: WeakEntity<PdfReader>,
+    /// Re-render when the settings are changed from the main window.
+    _observe_reader: Option<Subscription>,
+}
+impl PreferencesView {
+    pub fn new(reader: WeakEntity<PdfReader>, cx: &mut Context<Self>) -> Self {
+        let _observe_reader = reader
+            .upgrade()
+            .map(|entity| cx.observe(&entity, |_this, _reader, cx| cx.notify()));
+        Self {
+            reader,
+            _observe_reader,
+        }
+    }
+
+    /// Get a callback that changes the settings of the main window.
+    fn updater(
+        &self,
+        change: impl Fn(&mut Settings) + 'static,
+    ) -> impl Fn(&mut Window, &mut App) + 'static {
+        let reader = self.reader.clone();
+        move |_window, cx| {
+            _ = reader.update(cx, |reader, cx| reader.update_settings(cx, &change));
+        }
+    }
+
+    fn section(title: &'static str) -> impl IntoElement {
+        div().pt_3().font_semibold().child(title)
+    }
+
+    /// A row of buttons where the button for the current value is highlighted.
+    fn choice<T: Copy + PartialEq + 'static>(
+        &self,
+        id: &'static str,
+        current: T,
+        options: &[(T, &'static str)],
+        set: fn(&mut Settings, T),
+    ) -> impl IntoElement {
+        h_flex()
+            .gap_1()
+            .flex_wrap()
+            .children(options.iter().enumerate().map(|(index, &(value, label))| {
+                let update = self.updater(move |settings| set(settings, value));
+                Button::new((id, index))
+                    .label(label)
+                    .when(value == current, |this| this.primary())
+                    .when(value != current, |this| this.ghost())
+                    .on_click(move |_, window, cx| update(window, cx))
+            }))
+    }
+
+    /// A numeric setting with buttons that call `step` with `-1` or `1` to change its value.
+    fn stepper(
+        &self,
+        id: &'static str,
+        label: &'static str,
+        value: String,
+        step: impl Fn(&mut Settings, isize) + 'static,
+    ) -> impl IntoElement {
+        let step = Rc::new(step);
+        let decrease = self.updater({
+            let step = step.clone();
+            move |settings| step(settings, -1)
+        });
+        let increase = self.updater(move |settings| step(settings, 1));
+        h_flex()
+            .gap_1()
+            .child(div().flex_1().child(label))
+            .child(
+                Button::new((id, 0usize))
+                    .ghost()
+                    .label("-")
+                    .on_click(move |_, window, cx| decrease(window, cx)),
+            )
+            .child(div().min_w(px(56.)).text_center().child(value))
+            .child(
+                Button::new((id, 1usize))
+                    .ghost()
+                    .label("+")
+                    .on_click(move |_, window, cx| increase(window, cx)),
+            )
+    }
+
+    /// The page filters in the order they are applied, with buttons to move them.
+    fn filter_order(&self, order: Vec<FilterKind>) -> impl IntoElement {
+        v_flex()
+            .gap_1()
+            .children(order.iter().enumerate().map(|(index, &kind)| {
+                let earlier = self.updater(move |settings| settings.move_filter(kind, -1));
+                let later = self.updater(move |settings| settings.move_filter(kind, 1));
+                h_flex()
+                    .gap_1()
+                    .child(
+                        div()
+                            .flex_1()
+                            .child(format!("{}. {}", index + 1, kind.label())),
+                    )
+                    .child(
+                        Button::new(("filter-earlier", index))
+                            .ghost()
+                            .label("Earlier")
+                            .disabled(index == 0)
+                            .on_click(move |_, window, cx| earlier(window, cx)),
+                    )
+                    .child(
+                        Button::new(("filter-later", index))
+                            .ghost()
+                            .label("Later")
+                            .disabled(index + 1 == order.len())
+                            .on_click(move |_, window, cx| later(window, cx)),
+                    )
+            }))
+    }
+
+    fn checkbox(
+        &self,
+        id: &'static str,
+        label: impl Into<SharedString>,
+        checked: bool,
+        set: fn(&mut Settings, bool),
+    ) -> impl IntoElement {
+        let reader = self.reader.clone();
+        let label: SharedString = label.into();
+        Checkbox::new(id)
+            .label(label)
+            .checked(checked)
+            .on_click(move |&checked, _window, cx| {
+                _ = reader.update(cx, |reader, cx| {
+                    reader.update_settings(cx, |settings| set(settings, checked))
+                });
+            })
+    }
+}
+impl Render for PreferencesView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let Some(reader) = self.reader.upgrade() else {
+            return v_flex()
+                .size_full()
+                .items_center()
+                .justify_center()
+                .child("The main window was closed");
+        };
+        let settings = reader.read(cx).settings().clone();
+        let performance = settings.performance;
+        let max_threads = std::thread::available_parallelism().map_or(1, NonZeroUsize::get);
+
+        v_flex()
+            .size_full()
+            .gap_1()
+            .p_3()
+            .bg(cx.theme().background)
+            .text_color(cx.theme().foreground)
+            .child(Self::section("When the last tab is closed"))
+            .child(self.choice(
+                "last-tab-closed",
+                settings.last_tab_closed,
+                &[
+                    (LastTabClosed::EmptyTab, "Keep an empty tab"),
+                    (LastTabClosed::RecentFiles, "Show recent files"),
+                    (LastTabClosed::Quit, "Quit"),
+                ],
+                |settings, value| settings.last_tab_closed = value,
+            ))
+            .child(Self::section("Links to web pages"))
+            .child(self.choice(
+                "link-opening",
+                settings.link_opening,
+                &[
+                    (LinkOpening::Open, "Open"),
+                    (LinkOpening::Confirm, "Ask first"),
+                    (LinkOpening::Block, "Block"),
+                ],
+                |settings, value| settings.link_opening = value,
+            ))
+            .child(Self::section("Files"))
+            .child(self.checkbox(
+                "auto-reload",
+                "Reload files when they change on disk",
+                settings.auto_reload,
+                |settings, checked| settings.auto_reload = checked,
+            ))
+            .child(Self::section("Appearance"))
+            .child(self.checkbox(
+                "show-status-bar",
+                "Show status bar",
+                settings.show_status_bar,
+                |settings, checked| settings.show_status_bar = checked,
+            ))
+            .child(self.checkbox(
+                "invert-colors",
+                "Invert page colors (dark mode)",
+                settings.invert_colors.enabled,
+                |settings, checked| settings.invert_colors.enabled = checked,
+            ))
+            .child(self.checkbox(
+                "invert-colors-follow-system",
+                "Invert page colors when the system uses a dark theme",
+                settings.invert_colors.follow_system,
+                |settings, checked| settings.invert_colors.follow_system = checked,
+            ))
+            .when(
+                settings.invert_colors.enabled || settings.invert_colors.follow_system,
+                |this| {
+                    this.child(self.choice(
+                        "invert-mode",
+                        settings.invert_colors.mode,
+                        &[
+                            (InvertMode::KeepHue, "Keep colors natural"),
+                            (InvertMode::Simple, "Invert every color"),
+                        ],
+                        |settings, value| settings.invert_colors.mode = value,
+                    ))
+                },
+            )
+            .child(div().child("Zoom of opened files"))
+            .child(self.choice(
+                "initial-zoom",
+                settings.initial_zoom,
+                &InitialZoom::ALL.map(|zoom| (zoom, zoom.label())),
+                |settings, value| settings.initial_zoom = value,
+            ))
+            .child(self.stepper(
+                "page-gap",
+                "Space between pages",
+                format!("{} px", settings.layout.page_gap),
+                |settings, step| {
+                    let layout = &mut settings.layout;
+                    layout.page_gap = (layout.page_gap + step as f32 * 4.).clamp(0., 64.);
+                },
+            ))
+            .child(div().child("Background behind pages"))
+            .child(self.choice(
+                "canvas-color",
+                settings.layout.canvas_color,
+                &CanvasColor::ALL.map(|color| (color, color.label())),
+                |settings, value| settings.layout.canvas_color = value,
+            ))
+            .child(self.checkbox(
+                "right-to-left",
+                "Read from right to left",
+                settings.right_to_left,
+                |settings, checked| settings.right_to_left = checked,
+            ))
+            .child(self.choice(
+                "color-filter",
+                settings.color_filter,
+                &ColorFilter::ALL.map(|filter| (filter, filter.label())),
+                |settings, value| settings.color_filter = value,
+            ))
+            .child(self.checkbox(
+                "high-contrast",
+                "High contrast pages",
+                settings.high_contrast.enabled,
+                |settings, checked| settings.high_contrast.enabled = checked,
+            ))
+            .when(settings.high_contrast.enabled, |this| {
+                this.child(self.checkbox(
+                    "high-contrast-limited-palette",
+                    "Only use two colors",
+                    settings.high_contrast.limited_palette,
+                    |settings, checked| settings.high_contrast.limited_palette = checked,
+                ))
+                .when(settings.high_contrast.limited_palette, |this| {
+                    this.child(self.choice(
+                        "high-contrast-palette",
+                        settings.high_contrast.palette,
+                        &[
+                            (HighContrastPalette::BlackOnWhite, "Black on white"),
+                            (HighContrastPalette::WhiteOnBlack, "White on black"),
+                            (HighContrastPalette::YellowOnBlack, "Yellow on black"),
+                        ],
+                        |settings, value| settings.high_contrast.palette = value,
+                    ))
+                })
+            })
+            .child(div().child("Order that page filters are applied in"))
+            .child(self.filter_order(settings.ordered_filters()))
+            .child(Self::section("Scrolling"))
+            .child(self.checkbox(
+                "smooth-scroll",
+                "Animate scrolling to pages and tabs",
+                settings.smooth_scroll.enabled,
+                |settings, checked| settings.smooth_scroll.enabled = checked,
+            ))
+            .when(settings.smooth_scroll.enabled, |this| {
+                this.child(self.stepper(
+                    "smooth-scroll-duration",
+                    "Animation duration",
+                    format!("{} ms", settings.smooth_scroll.duration_ms),
+                    |settings, step| {
+                        let smooth_scroll = &mut settings.smooth_scroll;
+                        smooth_scroll.duration_ms = smooth_scroll
+                            .duration_ms
+                            .saturating_add_signed(step as i64 * 50)
+                            .clamp(50, 2000);
+                    },
+                ))
+                .child(self.choice(
+                    "smooth-scroll-easing",
+                    settings.smooth_scroll.easing,
+                    &[
+                        (Easing::EaseInOut, "Ease in and out"),
+                        (Easing::EaseOut, "Ease out"),
+                        (Easing::Linear, "Linear"),
+                    ],
+                    |settings, value| settings.smooth_scroll.easing = value,
+                ))
+            })
+            .child(self.checkbox(
+                "reduce-motion",
+                "Reduce motion (stop scrolling when a flick ends)",
+                settings.smooth_scroll.reduce_motion,
+                |settings, checked| settings.smooth_scroll.reduce_motion = checked,
+            ))
+            .when(!settings.smooth_scroll.reduce_motion, |this| {
+                this.child(self.stepper(
+                    "friction",
+                    "Speed kept after a second of flick scrolling",
+                    format!("{:.0} %", settings.smooth_scroll.friction * 100.),
+                    |settings, step| {
+                        let smooth_scroll = &mut settings.smooth_scroll;
+                        let percent = (smooth_scroll.friction * 100.).round() + step as f32 * 5.;
+                        smooth_scroll.friction = percent.clamp(0., 90.) / 100.;
+                    },
+                ))
+            })
+            .child(self.stepper(
+                "arrow-step",
+                "Arrow key scroll distance",
+                format!("{} px", settings.smooth_scroll.arrow_step),
+                |settings, step| {
+                    let smooth_scroll = &mut settings.smooth_scroll;
+                    smooth_scroll.arrow_step =
+                        (smooth_scroll.arrow_step + step as f32 * 20.).clamp(20., 400.);
+                },
+            ))
+            .child(Self::section("Performance"))
+            .child(self.choice(
+                "image-cache",
+                performance.image_cache,
+                &[
+                    (
+                        ImageCacheStrategy::Bypass,
+                        "Free pages after scrolling past them",
+                    ),
+                    (
+                        ImageCacheStrategy::Bounded {
+                            capacity: match performance.image_cache {
+                                ImageCacheStrategy::Bounded { capacity } => capacity,
+                                ImageCacheStrategy::Bypass => ImageCacheStrategy::DEFAULT_CAPACITY,
+                            },
+                        },
+                        "Keep recent pages",
+                    ),
+                ],
+                |settings, value| settings.performance.image_cache = value,
+            ))
+            .when_some(
+                match performance.image_cache {
+                    ImageCacheStrategy::Bounded { capacity } => Some(capacity),
+                    ImageCacheStrategy::Bypass => None,
+                },
+                |this, capacity| {
+                    this.child(self.stepper(
+                        "retained-pages",
+                        "Kept pages",
+                        capacity.to_string(),
+                        |settings, step| {
+                            if let ImageCacheStrategy::Bounded { capacity } =
+                                &mut settings.performance.image_cache
+                            {
+                                *capacity = capacity.saturating_add_signed(step).clamp(1, 256);
+                            }
+                        },
+                    ))
+                },
+            )
+            .child(self.stepper(
+                "cache-ahead",
+                "Pages rendered ahead",
+                performance.cache_ahead.to_string(),
+                |settings, step| {
+                    let performance = &mut settings.performance;
+                    performance.cache_ahead =
+                        performance.cache_ahead.saturating_add_signed(step).min(16);
+                },
+            ))
+            .child(
+                self.stepper(
+                    "render-threads",
+                    "Render threads",
+                    performance
+                        .render_threads
+                        .map_or_else(|| "Auto".to_owned(), |threads| threads.to_string()),
+                    move |settings, step| {
+                        let performance = &mut settings.performance;
+                        let threads = performance
+                            .render_threads
+                            .unwrap_or_else(|| crate::default_render_threads(max_threads));
+                        performance.render_threads =
+                            Some(threads.saturating_add_signed(step).clamp(1, max_threads));
+                    },
+                ),
+            )
+            .child(self.stepper(
+                "supersampling",
+                "Supersampling",
+                format!("{}x", performance.supersampling),
+                |settings, step| {
+                    let performance = &mut settings.performance;
+                    performance.supersampling =
+                        (performance.supersampling + step as f32 * 0.5).clamp(1., 4.);
+                },
+            ))
+            .child(self.stepper(
+                "memory-budget",
+                "Page memory",
+                performance.memory_budget_mb.map_or_else(
+                    || "Unlimited".to_owned(),
+                    |megabytes| format!("{megabytes} MB"),
+                ),
+                |settings, step| {
+                    let performance = &mut settings.performance;
+                    let megabytes = performance
+                        .memory_budget_mb
+                        .unwrap_or(0)
+                        .saturating_add_signed(step * 128);
+                    // Decreasing below the smallest budget removes the limit:
+                    performance.memory_budget_mb = (megabytes > 0).then_some(megabytes.min(16384));
+                },
+            ))
+            .child(self.checkbox(
+                "live-resize",
+                "Resize pages along with the window",
+                performance.live_resize,
+                |settings, checked| settings.performance.live_resize = checked,
+            ))
+            .child(div().flex_1())
+            .child(
+                h_flex().justify_end().child(
+                    Button::new("reset-preferences")
+                        .ghost()
+                        .label("Reset to defaults")
+                        .on_click({
+                            let reset = self.updater(|settings| *settings = Settings::default());
+                            move |_, window, cx| reset(window, cx)
+                        }),
+                ),
+            )
+    }
+}
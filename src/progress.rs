@@ -0,0 +1,74 @@
+//! Progress reporting and cancellation for operations that process every page of a document, like
+//! searching or exporting text.
+
+use hayro::Pdf;
+use hayro_syntax::page::Page;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Shared flag that is checked between pages, cloning it gives another handle to the same flag.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The operation was aborted using a [`CancellationToken`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cancelled;
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the operation was cancelled")
+    }
+}
+impl std::error::Error for Cancelled {}
+
+/// How many pages have been processed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Progress {
+    pub done: usize,
+    pub total: usize,
+}
+impl Progress {
+    /// Progress in `0..=1`, suitable for a determinate progress bar.
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            1.
+        } else {
+            self.done as f32 / self.total as f32
+        }
+    }
+}
+
+/// Call `f` for every page in order. `on_progress` is called after each page and the loop stops
+/// early with [`Cancelled`] once `cancel` is triggered.
+pub fn for_each_page<T>(
+    pdf: &Pdf,
+    cancel: &CancellationToken,
+    on_progress: &mut dyn FnMut(Progress),
+    mut f: impl FnMut(usize, &Page<'_>) -> T,
+) -> Result<Vec<T>, Cancelled> {
+    let pages = pdf.pages();
+    let total = pages.len();
+    let mut results = Vec::with_capacity(total);
+    for (index, page) in pages.iter().enumerate() {
+        if cancel.is_cancelled() {
+            return Err(Cancelled);
+        }
+        results.push(f(index, page));
+        on_progress(Progress {
+            done: index + 1,
+            total,
+        });
+    }
+    Ok(results)
+}
@@ -1,3 +1,7 @@
+use gpui::App;
+use std::path::Path;
+use std::process::Command;
+
 /// Wrap a type that provides a [`raw_window_handle::WindowHandle`] but doesn't
 /// provide a [`raw_window_handle::DisplayHandle`] and makes it usable with
 /// [`prompt_load_file`] and [`prompt_save_file`].
@@ -38,7 +42,7 @@ impl<W> DialogParent for W where
 
 pub fn prompt_load_pdf_file(
     parent: Option<&dyn DialogParent>,
-) -> impl Future<Output = Option<rfd::FileHandle>> + 'static {
+) -> impl Future<Output = Option<rfd::FileHandle>> + 'static + use<> {
     let mut builder = ::rfd::AsyncFileDialog::new()
         .add_filter("PDF file", &["pdf"])
         .add_filter("All files", &["*"])
@@ -50,3 +54,114 @@ pub fn prompt_load_pdf_file(
 
     builder.pick_file()
 }
+
+/// Like [`prompt_load_pdf_file`] but several files can be selected at once.
+pub fn prompt_load_pdf_files(
+    parent: Option<&dyn DialogParent>,
+) -> impl Future<Output = Option<Vec<rfd::FileHandle>>> + 'static + use<> {
+    let mut builder = ::rfd::AsyncFileDialog::new()
+        .add_filter("PDF file", &["pdf"])
+        .add_filter("All files", &["*"])
+        .set_title("Open PDF files");
+
+    if let Some(parent) = parent {
+        builder = builder.set_parent(&parent);
+    }
+
+    builder.pick_files()
+}
+
+pub fn prompt_save_file(
+    parent: Option<&dyn DialogParent>,
+    title: &str,
+    file_name: &str,
+) -> impl Future<Output = Option<rfd::FileHandle>> + 'static + use<> {
+    let mut builder = ::rfd::AsyncFileDialog::new()
+        .add_filter("All files", &["*"])
+        .set_title(title)
+        .set_file_name(file_name);
+
+    if let Some(parent) = parent {
+        builder = builder.set_parent(&parent);
+    }
+
+    builder.save_file()
+}
+
+pub fn prompt_save_text_file(
+    parent: Option<&dyn DialogParent>,
+    file_name: &str,
+) -> impl Future<Output = Option<rfd::FileHandle>> + 'static + use<> {
+    let mut builder = ::rfd::AsyncFileDialog::new()
+        .add_filter("Text file", &["txt"])
+        .add_filter("All files", &["*"])
+        .set_title("Export text")
+        .set_file_name(file_name);
+
+    if let Some(parent) = parent {
+        builder = builder.set_parent(&parent);
+    }
+
+    builder.save_file()
+}
+
+pub fn prompt_pick_folder(
+    parent: Option<&dyn DialogParent>,
+    title: &str,
+) -> impl Future<Output = Option<rfd::FileHandle>> + 'static + use<> {
+    let mut builder = ::rfd::AsyncFileDialog::new().set_title(title);
+
+    if let Some(parent) = parent {
+        builder = builder.set_parent(&parent);
+    }
+
+    builder.pick_folder()
+}
+
+/// Show a file in the system's file manager. If the file no longer exists then the folder that
+/// contained it is opened instead. Returns `false` if neither exists.
+pub fn reveal_in_file_manager(path: &Path, cx: &App) -> bool {
+    if path.exists() {
+        cx.reveal_path(path);
+        true
+    } else if let Some(folder) = path.parent().filter(|folder| folder.is_dir()) {
+        cx.open_with_system(folder);
+        true
+    } else {
+        false
+    }
+}
+
+/// Send a file to the default printer using the platform's print command. This doesn't show a
+/// print dialog on every platform.
+pub fn print_file(path: &Path) -> std::io::Result<()> {
+    #[cfg(windows)]
+    let mut command = {
+        // Use the "print" verb of the program that is registered for the file type:
+        let literal = path.display().to_string().replace('\'', "''");
+        let mut command = Command::new("powershell");
+        command.args([
+            "-NoProfile",
+            "-Command",
+            &format!("Start-Process -LiteralPath '{literal}' -Verb Print"),
+        ]);
+        command
+    };
+    #[cfg(not(windows))]
+    let mut command = {
+        let mut command = Command::new("lp");
+        command.arg("--").arg(path);
+        command
+    };
+    let output = command.output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!(
+            "{:?} failed with {}: {}",
+            command.get_program(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
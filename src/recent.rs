@@ -0,0 +1,55 @@
+//! Remember which files were opened recently so that they are easy to open again.
+
+use crate::config;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const FILE_NAME: &str = "recent_files.json";
+
+/// The number of files that are remembered.
+pub const MAX_RECENT_FILES: usize = 10;
+
+/// Recently opened files, most recent first.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RecentFiles {
+    paths: Vec<PathBuf>,
+}
+impl RecentFiles {
+    /// Load the list that was saved by a previous run, files that no longer exist are skipped.
+    pub fn load() -> Self {
+        let mut this: Self = config::load_json(FILE_NAME).unwrap_or_default();
+        if this.remove_missing() {
+            this.save();
+        }
+        this
+    }
+    pub fn save(&self) {
+        config::save_json(FILE_NAME, self);
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Move `path` to the front of the list.
+    pub fn add(&mut self, path: &Path) {
+        self.paths.retain(|existing| existing != path);
+        self.paths.insert(0, path.to_owned());
+        self.paths.truncate(MAX_RECENT_FILES);
+    }
+    pub fn remove(&mut self, path: &Path) {
+        self.paths.retain(|existing| existing != path);
+    }
+    /// Forget files that were deleted or moved, returns `true` if any were removed.
+    pub fn remove_missing(&mut self) -> bool {
+        let count = self.paths.len();
+        self.paths.retain(|path| path.exists());
+        self.paths.len() != count
+    }
+    pub fn clear(&mut self) {
+        self.paths.clear();
+    }
+}
@@ -0,0 +1,133 @@
+//! Find text in a document.
+//!
+//! The text of every page is extracted once into a [`TextIndex`] which can then be searched any
+//! number of times. Matches are reported with the area of the page they cover so that they can be
+//! highlighted.
+
+use crate::pdf::{self, PdfFeature};
+use crate::progress::{CancellationToken, Cancelled, Progress, for_each_page};
+use hayro::{InterpreterSettings, Pdf, RenderSettings};
+use hayro_syntax::object::Rect;
+
+/// Text that is rotated less than this is narrowed down to the matched characters.
+const MAX_HORIZONTAL_RADIANS: f64 = 0.02;
+
+/// A match for a search query.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchHit {
+    pub page: usize,
+    /// Area of the match in unscaled page coordinates where `y` grows downwards, the same space as
+    /// [`crate::pdf::extract_features`] with default render settings.
+    pub rect: Rect,
+}
+
+/// A piece of text that was drawn by a single text operation.
+#[derive(Clone, Debug)]
+struct TextRun {
+    /// Lower cased interpretations of the run's bytes, see [`decode_candidates`].
+    candidates: Vec<String>,
+    rect: Rect,
+    angle: f64,
+}
+
+/// The extracted text of every page in a document.
+#[derive(Clone, Debug, Default)]
+pub struct TextIndex {
+    pages: Vec<Vec<TextRun>>,
+}
+impl TextIndex {
+    /// Extract the text of every page, this can take a while for a large document.
+    pub fn build(
+        pdf: &Pdf,
+        interpreter_settings: &InterpreterSettings,
+        cancel: &CancellationToken,
+        on_progress: &mut dyn FnMut(Progress),
+    ) -> Result<Self, Cancelled> {
+        let pages = for_each_page(pdf, cancel, on_progress, |_index, page| {
+            let mut runs = Vec::new();
+            pdf::extract_features(
+                page,
+                interpreter_settings,
+                &RenderSettings::default(),
+                &mut |feature| {
+                    if let PdfFeature::Text { text, rect, angle } = feature {
+                        // Line breaks are added in front of text that starts a new line:
+                        let text = text.strip_prefix(b"\n").unwrap_or(&text[..]);
+                        if !text.is_empty() {
+                            runs.push(TextRun {
+                                candidates: decode_candidates(text),
+                                rect,
+                                angle,
+                            });
+                        }
+                    }
+                },
+            );
+            runs
+        })?;
+        Ok(Self { pages })
+    }
+
+    /// Find every case insensitive occurrence of `query`, in page order.
+    pub fn search(&self, query: &str) -> Vec<SearchHit> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let mut hits = Vec::new();
+        for (page, runs) in self.pages.iter().enumerate() {
+            for run in runs {
+                // Use the first interpretation that matches, the others are likely garbage:
+                let Some(text) = run.candidates.iter().find(|text| text.contains(&query)) else {
+                    continue;
+                };
+                let char_count = text.chars().count().max(1);
+                for (byte_start, _) in text.match_indices(&query) {
+                    let start = text[..byte_start].chars().count();
+                    let end = start + query.chars().count();
+                    hits.push(SearchHit {
+                        page,
+                        rect: run.narrow(
+                            start as f64 / char_count as f64,
+                            end as f64 / char_count as f64,
+                        ),
+                    });
+                }
+            }
+        }
+        hits
+    }
+}
+impl TextRun {
+    /// Guess the area covered by the characters from `start` to `end` (fractions of the text's
+    /// length) by assuming that all characters are equally wide.
+    fn narrow(&self, start: f64, end: f64) -> Rect {
+        if self.angle.abs() > MAX_HORIZONTAL_RADIANS {
+            return self.rect;
+        }
+        let width = self.rect.width();
+        Rect::new(
+            self.rect.x0 + width * start,
+            self.rect.y0,
+            self.rect.x0 + width * end,
+            self.rect.y1,
+        )
+    }
+}
+
+/// The bytes shown by a text operation are in the font's encoding, which is usually a single
+/// byte encoding close to `Latin-1` but can also be two bytes per character (often `UTF-16BE`
+/// without a byte order mark). Return every plausible interpretation, lower cased.
+fn decode_candidates(bytes: &[u8]) -> Vec<String> {
+    let mut candidates = vec![pdf::decode_text_string(bytes)];
+    candidates.extend(pdf::decode_utf16_be(bytes));
+    if let Ok(utf8) = std::str::from_utf8(bytes)
+        && !utf8.is_ascii()
+    {
+        candidates.push(utf8.to_owned());
+    }
+    candidates
+        .into_iter()
+        .map(|text| text.to_lowercase())
+        .collect()
+}
@@ -0,0 +1,40 @@
+//! Remember the open tabs so that they can be restored the next time the program starts.
+
+use crate::config;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const FILE_NAME: &str = "session.json";
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    /// Open tabs in order, empty tabs are not stored.
+    pub tabs: Vec<SessionTab>,
+    /// Index into [`Self::tabs`] of the tab that was active.
+    pub active_tab: usize,
+}
+
+/// A tab that showed a file, see [`crate::ScrollPosition`] for the scroll fields.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SessionTab {
+    pub path: PathBuf,
+    pub page_index: usize,
+    pub offset: f32,
+}
+
+impl Session {
+    /// Load the session that was saved by a previous run, files that no longer exist are skipped.
+    pub fn load() -> Self {
+        let mut this: Self = config::load_json(FILE_NAME).unwrap_or_default();
+        let active = this.tabs.get(this.active_tab).map(|tab| tab.path.clone());
+        this.tabs.retain(|tab| tab.path.exists());
+        this.active_tab = active
+            .and_then(|active| this.tabs.iter().position(|tab| tab.path == active))
+            .unwrap_or(0);
+        this
+    }
+    pub fn save(&self) {
+        config::save_json(FILE_NAME, self);
+    }
+}
@@ -0,0 +1,234 @@
+//! User preferences that are persisted between runs.
+
+use crate::config;
+use crate::filters::{ColorFilter, FilterChain, FilterKind, HighContrast, InvertColors};
+use crate::image_cache::ImageCacheStrategy;
+use crate::links::LinkOpening;
+use crate::tabs::SmoothScrollSettings;
+use crate::{FitMode, TabZoom};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+const FILE_NAME: &str = "settings.json";
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// What happens when the last open tab is closed.
+    pub last_tab_closed: LastTabClosed,
+    /// Accessibility rendering mode.
+    pub high_contrast: HighContrast,
+    /// Show pages with inverted colors, documents can override [`InvertColors::enabled`].
+    pub invert_colors: InvertColors,
+    /// Tint applied to pages.
+    pub color_filter: ColorFilter,
+    /// Order that the page filters are applied in, see [`Self::ordered_filters`].
+    pub filter_order: Vec<FilterKind>,
+    /// Show a bar with the current page, zoom and other status at the bottom of the window.
+    pub show_status_bar: bool,
+    /// How links to web pages are handled.
+    pub link_opening: LinkOpening,
+    /// Reload open files when they are changed on disk by another program.
+    pub auto_reload: bool,
+    /// Animation used when scrolling to a page or tab.
+    pub smooth_scroll: SmoothScrollSettings,
+    /// How pages are arranged.
+    pub layout: LayoutSettings,
+    /// Order tabs and the pages of two-page spreads from right to left.
+    pub right_to_left: bool,
+    /// Memory and CPU usage of the page cache.
+    pub performance: PerformanceSettings,
+    /// How pages are scaled when a file is opened.
+    pub initial_zoom: InitialZoom,
+}
+impl Settings {
+    /// Load the settings file, missing fields use their default values.
+    pub fn load() -> Self {
+        config::load_json(FILE_NAME).unwrap_or_default()
+    }
+    pub fn save(&self) {
+        config::save_json(FILE_NAME, self);
+    }
+
+    /// How rendered pages should be post-processed. `invert_override` replaces
+    /// [`InvertColors::enabled`] for a document that for example already has dark pages.
+    pub fn filters(&self, invert_override: Option<bool>) -> FilterChain {
+        let mut filters = FilterChain::default();
+        for kind in self.ordered_filters() {
+            match kind {
+                FilterKind::HighContrast if self.high_contrast.enabled => {
+                    filters.push(Arc::new(self.high_contrast));
+                }
+                FilterKind::InvertColors
+                    if invert_override.unwrap_or(self.invert_colors.enabled) =>
+                {
+                    filters.push(Arc::new(self.invert_colors));
+                }
+                FilterKind::ColorFilter if self.color_filter != ColorFilter::None => {
+                    filters.push(Arc::new(self.color_filter));
+                }
+                _ => {}
+            }
+        }
+        filters
+    }
+
+    /// Every filter kind once, in the saved order followed by kinds missing from the settings
+    /// file in their default order.
+    pub fn ordered_filters(&self) -> Vec<FilterKind> {
+        let mut order = Vec::with_capacity(FilterKind::ALL.len());
+        for &kind in self.filter_order.iter().chain(&FilterKind::ALL) {
+            if !order.contains(&kind) {
+                order.push(kind);
+            }
+        }
+        order
+    }
+    /// Apply the filter of `kind` one step earlier (`-1`) or later (`1`).
+    pub fn move_filter(&mut self, kind: FilterKind, step: isize) {
+        let mut order = self.ordered_filters();
+        if let Some(from) = order.iter().position(|&other| other == kind) {
+            let to = from.saturating_add_signed(step).min(order.len() - 1);
+            order.swap(from, to);
+        }
+        self.filter_order = order;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayoutSettings {
+    /// Empty space in logical pixels between rows of pages.
+    pub page_gap: f32,
+    /// Shown around and between pages.
+    pub canvas_color: CanvasColor,
+}
+impl Default for LayoutSettings {
+    fn default() -> Self {
+        Self {
+            page_gap: 12.,
+            canvas_color: CanvasColor::default(),
+        }
+    }
+}
+
+/// Background color behind the pages, usually darker than the pages so that their edges are
+/// visible.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CanvasColor {
+    /// The theme's muted background, which follows light and dark themes.
+    #[default]
+    Theme,
+    LightGray,
+    Gray,
+    DarkGray,
+}
+impl CanvasColor {
+    pub const ALL: [CanvasColor; 4] = [
+        CanvasColor::Theme,
+        CanvasColor::LightGray,
+        CanvasColor::Gray,
+        CanvasColor::DarkGray,
+    ];
+    pub fn label(self) -> &'static str {
+        match self {
+            CanvasColor::Theme => "Theme",
+            CanvasColor::LightGray => "Light gray",
+            CanvasColor::Gray => "Gray",
+            CanvasColor::DarkGray => "Dark gray",
+        }
+    }
+    /// The color as `0xRRGGBB`, `None` to use the theme.
+    pub fn rgb(self) -> Option<u32> {
+        match self {
+            CanvasColor::Theme => None,
+            CanvasColor::LightGray => Some(0xd4d4d4),
+            CanvasColor::Gray => Some(0x8a8a8a),
+            CanvasColor::DarkGray => Some(0x3a3a3a),
+        }
+    }
+}
+
+/// Scale of newly opened documents, each tab then remembers its own zoom.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InitialZoom {
+    #[default]
+    FitWidth,
+    FitPage,
+    ActualSize,
+    /// The zoom of the document that was shown last.
+    LastUsed,
+}
+impl InitialZoom {
+    pub const ALL: [InitialZoom; 4] = [
+        InitialZoom::FitWidth,
+        InitialZoom::FitPage,
+        InitialZoom::ActualSize,
+        InitialZoom::LastUsed,
+    ];
+    pub fn label(self) -> &'static str {
+        match self {
+            InitialZoom::FitWidth => "Fit width",
+            InitialZoom::FitPage => "Fit page",
+            InitialZoom::ActualSize => "Actual size",
+            InitialZoom::LastUsed => "Last used",
+        }
+    }
+    /// `None` for [`InitialZoom::LastUsed`] which depends on what was shown before.
+    pub fn zoom(self) -> Option<TabZoom> {
+        let fit_mode = match self {
+            InitialZoom::FitWidth => FitMode::Width,
+            InitialZoom::FitPage => FitMode::Page,
+            InitialZoom::ActualSize => FitMode::Custom(1.),
+            InitialZoom::LastUsed => return None,
+        };
+        Some(TabZoom { fit_mode, zoom: 1. })
+    }
+}
+
+/// Trade memory and CPU usage for responsiveness.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PerformanceSettings {
+    /// What happens to page images after scrolling past them.
+    pub image_cache: ImageCacheStrategy,
+    /// Number of pages before and after the visible pages that are rendered ahead of time.
+    pub cache_ahead: usize,
+    /// Maximum number of pages that are rendered in parallel, `None` uses all CPU cores except one.
+    pub render_threads: Option<usize>,
+    /// Render pages at this many times the display resolution. Makes thin lines smoother at the
+    /// cost of memory.
+    pub supersampling: f32,
+    /// Maximum memory in megabytes used by rendered pages, `None` for no limit. Pages far from the
+    /// visible ones are forgotten first.
+    pub memory_budget_mb: Option<usize>,
+    /// Lay out the pages again at every frame while the window is resized. They are only rendered
+    /// at their new size once the resize ends.
+    pub live_resize: bool,
+}
+impl Default for PerformanceSettings {
+    fn default() -> Self {
+        Self {
+            image_cache: ImageCacheStrategy::default(),
+            cache_ahead: 1,
+            render_threads: None,
+            supersampling: 1.,
+            memory_budget_mb: None,
+            live_resize: true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LastTabClosed {
+    /// Keep an empty tab that shows the "Select a PDF file" screen.
+    #[default]
+    EmptyTab,
+    /// Keep an empty tab that also lists recently opened files.
+    RecentFiles,
+    /// Quit the program.
+    Quit,
+}
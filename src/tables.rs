@@ -0,0 +1,386 @@
+//! Find ruled tables on a page and export them as CSV.
+//!
+//! A ruled table is a grid of horizontal and vertical lines. The lines are found among the
+//! page's vector paths and text is assigned to the grid cell that contains its center. Tables
+//! without visible borders are not detected.
+
+use crate::pdf::{PagePath, PathMode, PdfFeature, decode_text_string, extract_features};
+use hayro::{InterpreterSettings, RenderSettings};
+use hayro_syntax::page::Page;
+use kurbo::{PathSeg, Point, Rect, Shape};
+
+/// Filled shapes that are thinner than this are treated as lines.
+const MAX_RULE_THICKNESS: f64 = 3.;
+/// Ignore lines that are shorter than this, they are likely part of a drawing or glyph.
+const MIN_RULE_LENGTH: f64 = 5.;
+/// Lines closer than this are considered to be at the same position or to be touching.
+const SNAP_TOLERANCE: f64 = 2.;
+
+/// A table found on a page.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Table {
+    /// Area covered by the table in unscaled page coordinates.
+    pub bounds: Rect,
+    /// Text in each cell, `rows[row][column]`.
+    pub rows: Vec<Vec<String>>,
+}
+impl Table {
+    /// Format the table as comma separated values.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        for row in &self.rows {
+            for (index, cell) in row.iter().enumerate() {
+                if index > 0 {
+                    csv.push(',');
+                }
+                if cell.contains([',', '"', '\n', '\r']) {
+                    csv.push('"');
+                    csv.push_str(&cell.replace('"', "\"\""));
+                    csv.push('"');
+                } else {
+                    csv.push_str(cell);
+                }
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Rule {
+    horizontal: bool,
+    /// The y coordinate of horizontal lines or the x coordinate of vertical lines.
+    position: f64,
+    start: f64,
+    end: f64,
+}
+impl Rule {
+    fn crosses(&self, other: &Rule) -> bool {
+        self.horizontal != other.horizontal
+            && (self.start - SNAP_TOLERANCE..=self.end + SNAP_TOLERANCE).contains(&other.position)
+            && (other.start - SNAP_TOLERANCE..=other.end + SNAP_TOLERANCE).contains(&self.position)
+    }
+    /// Shorten the line so that it is inside `region`, returns `None` if too little remains.
+    fn clip(self, region: Rect) -> Option<Self> {
+        let (min, max, position_range) = if self.horizontal {
+            (region.x0, region.x1, region.y0..=region.y1)
+        } else {
+            (region.y0, region.y1, region.x0..=region.x1)
+        };
+        let rule = Rule {
+            start: self.start.max(min),
+            end: self.end.min(max),
+            ..self
+        };
+        (position_range.contains(&rule.position) && rule.end - rule.start >= MIN_RULE_LENGTH)
+            .then_some(rule)
+    }
+}
+
+/// Find the horizontal and vertical lines that a path draws.
+fn rules_from_path(path: &PagePath, rules: &mut Vec<Rule>) {
+    let bounds = path.path.bounding_box();
+    if path.draw_mode == PathMode::Fill && bounds.width().min(bounds.height()) <= MAX_RULE_THICKNESS
+    {
+        // Thin filled rectangles are often used instead of stroked lines:
+        let center = bounds.center();
+        let rule = if bounds.width() >= bounds.height() {
+            Rule {
+                horizontal: true,
+                position: center.y,
+                start: bounds.x0,
+                end: bounds.x1,
+            }
+        } else {
+            Rule {
+                horizontal: false,
+                position: center.x,
+                start: bounds.y0,
+                end: bounds.y1,
+            }
+        };
+        if rule.end - rule.start >= MIN_RULE_LENGTH {
+            rules.push(rule);
+        }
+        return;
+    }
+    for segment in path.path.segments() {
+        let PathSeg::Line(line) = segment else {
+            continue;
+        };
+        let (dx, dy) = ((line.p1.x - line.p0.x).abs(), (line.p1.y - line.p0.y).abs());
+        if dy <= SNAP_TOLERANCE && dx >= MIN_RULE_LENGTH {
+            rules.push(Rule {
+                horizontal: true,
+                position: (line.p0.y + line.p1.y) / 2.,
+                start: line.p0.x.min(line.p1.x),
+                end: line.p0.x.max(line.p1.x),
+            });
+        } else if dx <= SNAP_TOLERANCE && dy >= MIN_RULE_LENGTH {
+            rules.push(Rule {
+                horizontal: false,
+                position: (line.p0.x + line.p1.x) / 2.,
+                start: line.p0.y.min(line.p1.y),
+                end: line.p0.y.max(line.p1.y),
+            });
+        }
+    }
+}
+
+/// Sorted positions where positions that are close together are merged.
+fn cluster_positions(mut positions: Vec<f64>) -> Vec<f64> {
+    positions.sort_by(f64::total_cmp);
+    let mut clustered: Vec<f64> = Vec::with_capacity(positions.len());
+    for position in positions {
+        match clustered.last() {
+            Some(&last) if position - last <= SNAP_TOLERANCE => {}
+            _ => clustered.push(position),
+        }
+    }
+    clustered
+}
+
+fn find_root(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+    index
+}
+
+/// Find ruled tables on a page. If `region` is specified (in unscaled page coordinates) then only
+/// lines and text inside it are considered.
+pub fn detect_tables(
+    page: &Page,
+    interpreter_settings: &InterpreterSettings,
+    region: Option<Rect>,
+) -> Vec<Table> {
+    let mut rules = Vec::new();
+    let mut texts = Vec::new();
+    extract_features(
+        page,
+        interpreter_settings,
+        &RenderSettings::default(),
+        &mut |feature| match feature {
+            PdfFeature::Text { text, rect, .. } => {
+                let text = decode_text_string(&text);
+                let text = text.trim();
+                if !text.is_empty() {
+                    texts.push((rect.center(), text.to_owned()));
+                }
+            }
+            PdfFeature::Path(path) => rules_from_path(&path, &mut rules),
+            PdfFeature::Image { .. } => {}
+        },
+    );
+    find_tables(rules, texts, region)
+}
+
+/// Build tables from the lines and text (with the center of its area) found on a page.
+fn find_tables(
+    mut rules: Vec<Rule>,
+    mut texts: Vec<(Point, String)>,
+    region: Option<Rect>,
+) -> Vec<Table> {
+    if let Some(region) = region {
+        rules = rules
+            .into_iter()
+            .filter_map(|rule| rule.clip(region))
+            .collect();
+        texts.retain(|(center, _)| region.contains(*center));
+    }
+
+    // Lines that cross each other belong to the same table:
+    let mut parents = (0..rules.len()).collect::<Vec<_>>();
+    for a in 0..rules.len() {
+        for b in a + 1..rules.len() {
+            if rules[a].crosses(&rules[b]) {
+                let (root_a, root_b) = (find_root(&mut parents, a), find_root(&mut parents, b));
+                parents[root_a] = root_b;
+            }
+        }
+    }
+    let mut groups = std::collections::HashMap::<usize, Vec<Rule>>::new();
+    for (index, rule) in rules.iter().enumerate() {
+        groups
+            .entry(find_root(&mut parents, index))
+            .or_default()
+            .push(*rule);
+    }
+
+    let mut tables = groups
+        .into_values()
+        .filter_map(|group| {
+            let (horizontal, vertical): (Vec<Rule>, Vec<Rule>) =
+                group.into_iter().partition(|rule| rule.horizontal);
+            let ys = cluster_positions(horizontal.iter().map(|rule| rule.position).collect());
+            let xs = cluster_positions(vertical.iter().map(|rule| rule.position).collect());
+            if ys.len() < 2 || xs.len() < 2 {
+                return None;
+            }
+            let bounds = Rect::new(xs[0], ys[0], xs[xs.len() - 1], ys[ys.len() - 1]);
+            let mut rows = vec![vec![String::new(); xs.len() - 1]; ys.len() - 1];
+            for (center, text) in &texts {
+                if !bounds.contains(*center) {
+                    continue;
+                }
+                let column = xs
+                    .partition_point(|&x| x <= center.x)
+                    .clamp(1, xs.len() - 1)
+                    - 1;
+                let row = ys
+                    .partition_point(|&y| y <= center.y)
+                    .clamp(1, ys.len() - 1)
+                    - 1;
+                let cell = &mut rows[row][column];
+                if !cell.is_empty() {
+                    cell.push(' ');
+                }
+                cell.push_str(text);
+            }
+            Some(Table { bounds, rows })
+        })
+        .collect::<Vec<_>>();
+    // Reading order:
+    tables.sort_by(|a, b| {
+        a.bounds
+            .y0
+            .total_cmp(&b.bounds.y0)
+            .then(a.bounds.x0.total_cmp(&b.bounds.x0))
+    });
+    tables
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf::PathPaint;
+    use kurbo::BezPath;
+
+    fn line(from: (f64, f64), to: (f64, f64)) -> PagePath {
+        let mut path = BezPath::new();
+        path.move_to(from);
+        path.line_to(to);
+        PagePath {
+            path,
+            paint: PathPaint::Color([0., 0., 0., 1.]),
+            draw_mode: PathMode::Stroke { line_width: 1. },
+        }
+    }
+
+    /// Lines of a grid with the given column and row positions.
+    fn grid(xs: &[f64], ys: &[f64]) -> Vec<Rule> {
+        let mut rules = Vec::new();
+        for &y in ys {
+            rules_from_path(&line((xs[0], y), (xs[xs.len() - 1], y)), &mut rules);
+        }
+        for &x in xs {
+            rules_from_path(&line((x, ys[0]), (x, ys[ys.len() - 1])), &mut rules);
+        }
+        rules
+    }
+
+    fn text(x: f64, y: f64, text: &str) -> (Point, String) {
+        (Point::new(x, y), text.to_owned())
+    }
+
+    #[test]
+    fn assigns_text_to_cells() {
+        let tables = find_tables(
+            grid(&[0., 50., 100.], &[0., 20., 40.]),
+            vec![
+                text(25., 10., "a"),
+                text(75., 10., "b"),
+                text(25., 30., "c"),
+                text(60., 30., "d1"),
+                text(90., 30., "d2"),
+                text(200., 30., "outside"),
+            ],
+            None,
+        );
+        assert_eq!(
+            tables,
+            [Table {
+                bounds: Rect::new(0., 0., 100., 40.),
+                rows: vec![
+                    vec!["a".into(), "b".into()],
+                    vec!["c".into(), "d1 d2".into()]
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn thin_filled_rectangles_are_lines() {
+        let mut rules = Vec::new();
+        for rect in [
+            Rect::new(0., -0.5, 100., 0.5),
+            Rect::new(0., 19.5, 100., 20.5),
+            Rect::new(-0.5, 0., 0.5, 20.),
+            Rect::new(99.5, 0., 100.5, 20.),
+        ] {
+            let path = PagePath {
+                path: rect.to_path(0.1),
+                paint: PathPaint::Color([0., 0., 0., 1.]),
+                draw_mode: PathMode::Fill,
+            };
+            rules_from_path(&path, &mut rules);
+        }
+        let tables = find_tables(rules, vec![text(50., 10., "cell")], None);
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].rows, [["cell"]]);
+    }
+
+    #[test]
+    fn ignores_lines_that_dont_form_a_grid() {
+        let mut rules = Vec::new();
+        rules_from_path(&line((0., 0.), (100., 0.)), &mut rules);
+        rules_from_path(&line((0., 20.), (100., 20.)), &mut rules);
+        // Too short:
+        rules_from_path(&line((50., 0.), (50., 3.)), &mut rules);
+        // Diagonal:
+        rules_from_path(&line((0., 0.), (100., 20.)), &mut rules);
+        assert!(find_tables(rules, vec![text(50., 10., "a")], None).is_empty());
+    }
+
+    #[test]
+    fn separate_grids_are_separate_tables_in_reading_order() {
+        let mut rules = grid(&[0., 100.], &[200., 240.]);
+        rules.extend(grid(&[0., 50., 100.], &[0., 20.]));
+        let tables = find_tables(
+            rules,
+            vec![text(75., 10., "first"), text(50., 220., "second")],
+            None,
+        );
+        assert_eq!(tables.len(), 2);
+        assert_eq!(tables[0].rows, [["", "first"]]);
+        assert_eq!(tables[1].rows, [["second"]]);
+    }
+
+    #[test]
+    fn region_limits_lines_and_text() {
+        let rules = grid(&[0., 50., 100., 150.], &[0., 20., 40.]);
+        let tables = find_tables(
+            rules,
+            vec![
+                text(25., 10., "a"),
+                text(75., 10., "b"),
+                text(125., 10., "c"),
+            ],
+            Some(Rect::new(-1., -1., 101., 41.)),
+        );
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].bounds, Rect::new(0., 0., 100., 40.));
+        assert_eq!(tables[0].rows, [["a", "b"], ["", ""]]);
+    }
+
+    #[test]
+    fn csv_quotes_special_characters() {
+        let table = Table {
+            bounds: Rect::ZERO,
+            rows: vec![vec!["plain".into(), "a, b".into(), "say \"hi\"".into()]],
+        };
+        assert_eq!(table.to_csv(), "plain,\"a, b\",\"say \"\"hi\"\"\"\n");
+    }
+}
@@ -1,13 +1,17 @@
+use crate::prompt;
 use gpui::prelude::FluentBuilder;
 use gpui::{
-    AlignItems, AppContext, Context, Empty, InteractiveElement, IntoElement, MouseButton,
-    MouseDownEvent, ParentElement, Pixels, Point, Render, ScrollHandle, ScrollWheelEvent,
-    SharedString, StatefulInteractiveElement, StyleRefinement, Styled, Window, div, point, px,
+    AlignItems, AppContext, ClipboardItem, Context, Empty, InteractiveElement, IntoElement,
+    MouseButton, MouseDownEvent, ParentElement, Pixels, Point, Render, ScrollHandle,
+    ScrollWheelEvent, SharedString, StatefulInteractiveElement, StyleRefinement, Styled,
+    TouchPhase, Window, anchored, deferred, div, point, px,
 };
-use gpui_component::button::Button;
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::notification::Notification;
 use gpui_component::tab::{Tab, TabBar};
 use gpui_component::tooltip::Tooltip;
-use gpui_component::{ActiveTheme, Icon, IconName, StyledExt};
+use gpui_component::{ActiveTheme, ContextModal, Disableable, Icon, IconName, StyledExt, v_flex};
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::ops::Sub;
 use std::path::PathBuf;
@@ -22,15 +26,120 @@ pub struct CloseTab;
 #[action(namespace = tabs)]
 pub struct CreateTab;
 
+/// Activate the tab after the active one, which is to its left when tabs are shown right to left.
 #[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
 #[action(namespace = tabs)]
 pub struct NextTab;
 
+/// Activate the tab before the active one.
 #[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
 #[action(namespace = tabs)]
 pub struct PrevTab;
 
+/// Copy the path of the active tab's file to the clipboard.
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = tabs)]
+pub struct CopyFilePath;
+
+/// Show the active tab's file in the system's file manager.
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = tabs)]
+pub struct RevealInFileManager;
+
+/// Activate the tab at this index, does nothing if there is no such tab.
+#[derive(Clone, PartialEq, Debug, gpui::Action)]
+#[action(namespace = tabs, no_json)]
+pub struct ActivateTab(pub usize);
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = tabs)]
+pub struct ActivateLastTab;
+
+#[derive(Clone, PartialEq, Default, Debug, gpui::Action)]
+#[action(namespace = tabs)]
+pub struct ReopenTab;
+
+/// How the progress of a smooth scroll animation maps to the distance scrolled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    Linear,
+    /// Start slowly, speed up and then slow down at the end.
+    #[default]
+    EaseInOut,
+    /// Start fast and slow down at the end.
+    EaseOut,
+}
+impl Easing {
+    /// Map the fraction of the animation's duration that has passed to the fraction of the
+    /// distance that should have been scrolled.
+    ///
+    /// ```
+    /// use pdf_reader_gpui::tabs::Easing;
+    ///
+    /// for easing in [Easing::Linear, Easing::EaseInOut, Easing::EaseOut] {
+    ///     assert_eq!(easing.apply(0.), 0.);
+    ///     assert_eq!(easing.apply(1.), 1.);
+    /// }
+    /// assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+    /// assert!(Easing::EaseOut.apply(0.5) > 0.5);
+    /// ```
+    pub fn apply(self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    -1.0 + (4.0 - 2.0 * t) * t
+                }
+            }
+            Easing::EaseOut => 1. - (1. - t) * (1. - t),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmoothScrollSettings {
+    /// If `false` then scrolling jumps directly to the target offset.
+    pub enabled: bool,
+    /// Length of a scroll animation in milliseconds.
+    pub duration_ms: u64,
+    pub easing: Easing,
+    /// Distance in logical pixels that the up and down arrow keys scroll.
+    pub arrow_step: f32,
+    /// Fraction of the speed that remains after one second of momentum scrolling after a flick,
+    /// lower values stop sooner.
+    pub friction: f32,
+    /// Don't keep scrolling after a flick, for users that are sensitive to motion.
+    pub reduce_motion: bool,
+}
+impl Default for SmoothScrollSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            duration_ms: 300,
+            easing: Easing::default(),
+            arrow_step: 60.,
+            friction: 0.05,
+            reduce_motion: false,
+        }
+    }
+}
+impl SmoothScrollSettings {
+    /// Length of a scroll animation, zero if animations are disabled.
+    pub fn duration(&self) -> Duration {
+        if self.enabled {
+            Duration::from_millis(self.duration_ms)
+        } else {
+            Duration::ZERO
+        }
+    }
+}
+
 pub struct SmoothScrollState {
+    settings: SmoothScrollSettings,
     /// Animation state
     animating: bool,
     /// The scroll offset where the animation started.
@@ -49,30 +158,62 @@ pub struct SmoothScrollState {
     /// requested so we need to request a new update then to get and override that new offset.
     requested_async_scroll: u32,
     requested_scroll_to_item: Option<usize>,
+    /// Speed of the latest scroll gesture in pixels per second.
+    velocity: Point<Pixels>,
+    /// When the latest scroll gesture event was noticed, used to calculate the velocity.
+    last_gesture_event: Option<Instant>,
+    /// Time of the previous momentum scroll step, `None` if momentum scrolling isn't active.
+    momentum_step: Option<Instant>,
+}
+impl Default for SmoothScrollState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 impl SmoothScrollState {
+    /// Flick gestures that are slower than this (in pixels per second) don't cause momentum.
+    const MIN_MOMENTUM_SPEED: f32 = 60.;
+
     pub fn new() -> Self {
+        Self::with_settings(SmoothScrollSettings::default())
+    }
+    pub fn with_settings(settings: SmoothScrollSettings) -> Self {
         Self {
+            settings,
             animating: false,
             start_offset: point(px(0.), px(0.)),
             last_set_offset: point(px(0.), px(0.)),
             target_offset: point(px(0.), px(0.)),
             start_time: Instant::now(),
-            duration: Duration::from_millis(1500),
+            duration: settings.duration(),
             requested_async_scroll: 0,
             requested_scroll_to_item: None,
+            velocity: point(px(0.), px(0.)),
+            last_gesture_event: None,
+            momentum_step: None,
         }
     }
 
-    // Easing function (ease-in-out)
-    fn ease_in_out(t: f32) -> f32 {
-        if t < 0.5 {
-            2.0 * t * t
-        } else {
-            -1.0 + (4.0 - 2.0 * t) * t
+    /// Stop all animations and forget the scroll position while keeping the settings.
+    pub fn reset(&mut self) {
+        *self = Self::with_settings(self.settings);
+    }
+
+    pub fn settings(&self) -> SmoothScrollSettings {
+        self.settings
+    }
+    /// Change the animation duration, easing and momentum, this applies from the next animation.
+    pub fn set_settings(&mut self, settings: SmoothScrollSettings) {
+        self.settings = settings;
+        if settings.reduce_motion {
+            self.momentum_step = None;
         }
     }
 
+    fn speed(&self) -> f32 {
+        f32::from(self.velocity.x).hypot(f32::from(self.velocity.y))
+    }
+
     fn bound_scroll(scroll_handle: &ScrollHandle, offset: Point<Pixels>) -> Point<Pixels> {
         let bounds = scroll_handle.max_offset();
         let safe_x_range = (-bounds.width).min(px(0.0))..px(0.);
@@ -150,7 +291,7 @@ impl SmoothScrollState {
         _cx: &mut Context<T>,
         scroll_handle: &ScrollHandle,
     ) {
-        let current_offset = Self::bound_scroll(&scroll_handle, scroll_handle.offset());
+        let current_offset = Self::bound_scroll(scroll_handle, scroll_handle.offset());
 
         if self.last_set_offset != current_offset {
             self.start_offset = self.wanted_offset();
@@ -161,6 +302,90 @@ impl SmoothScrollState {
         }
         self.last_set_offset = current_offset;
     }
+    /// Track the speed of a touchpad scroll gesture so that scrolling can continue with momentum
+    /// after a flick. Mouse wheels don't report a gesture end so they never cause momentum.
+    pub fn noticed_scroll_gesture(
+        &mut self,
+        window: &mut Window,
+        delta: Point<Pixels>,
+        phase: TouchPhase,
+    ) {
+        let now = Instant::now();
+        match phase {
+            TouchPhase::Started => {
+                self.velocity = point(px(0.), px(0.));
+                self.momentum_step = None;
+            }
+            TouchPhase::Moved => {
+                self.momentum_step = None;
+                let elapsed = self
+                    .last_gesture_event
+                    .map(|last| now.duration_since(last).as_secs_f32())
+                    .unwrap_or(f32::INFINITY);
+                if elapsed < 0.1 {
+                    // Smooth the velocity since individual events are noisy:
+                    let elapsed = elapsed.max(0.001);
+                    self.velocity = point(
+                        self.velocity.x * 0.5 + delta.x * (0.5 / elapsed),
+                        self.velocity.y * 0.5 + delta.y * (0.5 / elapsed),
+                    );
+                } else {
+                    self.velocity = point(px(0.), px(0.));
+                }
+            }
+            TouchPhase::Ended => {
+                if !self.settings.reduce_motion && self.speed() > Self::MIN_MOMENTUM_SPEED {
+                    self.animating = false;
+                    self.momentum_step = Some(now);
+                    window.request_animation_frame();
+                }
+            }
+        }
+        self.last_gesture_event = Some(now);
+    }
+
+    /// Smoothly scroll from the current offset to `target_offset`.
+    pub fn animate_to(&mut self, scroll_handle: &ScrollHandle, target_offset: Point<Pixels>) {
+        let current_offset = Self::bound_scroll(scroll_handle, scroll_handle.offset());
+        self.start_offset = if self.animating {
+            self.wanted_offset()
+        } else {
+            current_offset
+        };
+        self.last_set_offset = current_offset;
+        self.start_scroll_to(Self::bound_scroll(scroll_handle, target_offset));
+    }
+
+    /// The user scrolled while an animation was running, so stop animating and keep the user's
+    /// scroll position instead of fighting over the offset.
+    pub fn interrupt(&mut self, scroll_handle: &ScrollHandle) {
+        self.interrupt_at(Self::bound_scroll(scroll_handle, scroll_handle.offset()));
+    }
+    /// Stop any animation or momentum scrolling and treat `offset` as the wanted position.
+    ///
+    /// ```
+    /// use gpui::{point, px};
+    /// use pdf_reader_gpui::tabs::SmoothScrollState;
+    ///
+    /// let mut state = SmoothScrollState::new();
+    /// state.start_scroll_to(point(px(0.), px(-500.)));
+    /// assert!(state.is_animating());
+    ///
+    /// // The user scrolls somewhere else in the middle of the animation:
+    /// state.interrupt_at(point(px(0.), px(-120.)));
+    /// assert!(!state.is_animating());
+    /// assert_eq!(state.wanted_offset(), point(px(0.), px(-120.)));
+    /// ```
+    pub fn interrupt_at(&mut self, offset: Point<Pixels>) {
+        self.animating = false;
+        self.momentum_step = None;
+        self.requested_async_scroll = 0;
+        self.requested_scroll_to_item = None;
+        self.start_offset = offset;
+        self.target_offset = offset;
+        self.last_set_offset = offset;
+    }
+
     /// Start animation
     pub fn start_scroll_to(&mut self, target_offset: Point<Pixels>) {
         if target_offset == self.target_offset {
@@ -168,7 +393,11 @@ impl SmoothScrollState {
         }
         // self.start_offset = start_offset;
         self.target_offset = target_offset;
-        if self.animating {
+        if !self.settings.enabled {
+            // Jump to the target offset the next time the scroll is performed:
+            self.duration = Duration::ZERO;
+            self.start_time = Instant::now();
+        } else if self.animating && !self.duration.is_zero() {
             // Select a start time that gives the same progress percentage in order to not change
             // the animation "speed".
             let elapsed = Instant::now().duration_since(self.start_time);
@@ -178,13 +407,13 @@ impl SmoothScrollState {
             if progress > 0.5 {
                 progress = 1. - progress;
             }
-            self.duration = Duration::from_millis(300);
+            self.duration = self.settings.duration();
             // Ensure at least half the time remains:
             self.start_time = Instant::now().sub(Duration::from_secs_f32(
                 self.duration.as_secs_f32() * progress,
             ));
         } else {
-            self.duration = Duration::from_millis(300);
+            self.duration = self.settings.duration();
             self.start_time = Instant::now();
         }
         self.animating = true;
@@ -195,7 +424,7 @@ impl SmoothScrollState {
     }
 
     pub fn is_animating(&self) -> bool {
-        self.animating
+        self.animating || self.momentum_step.is_some()
     }
 
     /// Gets the desired offset for the current time. If animating then this will calculate an
@@ -209,7 +438,7 @@ impl SmoothScrollState {
             self.target_offset
         } else {
             let progress = (elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.0);
-            let eased = Self::ease_in_out(progress);
+            let eased = self.settings.easing.apply(progress);
 
             point(
                 self.start_offset.x + (self.target_offset.x - self.start_offset.x) * eased,
@@ -237,6 +466,30 @@ impl SmoothScrollState {
             // If we failed to scroll to the item for several frames, then forget about it:
             self.requested_scroll_to_item = None;
         }
+        // Continue a flick gesture:
+        if let Some(last_step) = self.momentum_step {
+            let now = Instant::now();
+            let elapsed = now.duration_since(last_step).as_secs_f32();
+            let current_offset = Self::bound_scroll(scroll_handle, scroll_handle.offset());
+            let next_offset = Self::bound_scroll(
+                scroll_handle,
+                current_offset + point(self.velocity.x * elapsed, self.velocity.y * elapsed),
+            );
+            scroll_handle.set_offset(next_offset);
+            self.last_set_offset = next_offset;
+            self.target_offset = next_offset;
+
+            let decay = self.settings.friction.clamp(0., 1.).powf(elapsed);
+            self.velocity = point(self.velocity.x * decay, self.velocity.y * decay);
+            if next_offset == current_offset || self.speed() < Self::MIN_MOMENTUM_SPEED {
+                // Stopped by friction or reached the end of the scrollable area.
+                self.momentum_step = None;
+            } else {
+                self.momentum_step = Some(now);
+                window.request_animation_frame();
+            }
+            cx.notify();
+        }
         // Update animation if active
         if self.animating {
             let next_offset = self.wanted_offset();
@@ -260,38 +513,102 @@ pub trait TabData: 'static {
     fn full_path(&self) -> Arc<PathBuf>;
 }
 
+/// Number of closed tabs that can be reopened, their data is kept in memory.
+const MAX_CLOSED_TABS: usize = 10;
+
 pub struct TabsView<T: 'static> {
     active_tab: usize,
     tabs: Vec<Option<T>>,
+    /// Recently closed tabs and the index they had, the most recently closed tab is last.
+    closed_tabs: Vec<(usize, T)>,
     scroll_handle: ScrollHandle,
     smooth_scroll: SmoothScrollState,
-    on_tab_changed: Box<dyn Fn(&mut Window, &mut Context<Self>) + 'static>,
+    /// Show the first tab on the right and the new tab button on the left.
+    right_to_left: bool,
+    /// Where the menu for the active tab was opened by right clicking it, `None` if it is closed.
+    context_menu: Option<Point<Pixels>>,
+    on_tab_changed: TabsCallback<T>,
+    on_last_tab_closed: TabsCallback<T>,
 }
+/// Called by [`TabsView`] when its tabs change.
+type TabsCallback<T> = Box<dyn Fn(&mut Window, &mut Context<TabsView<T>>) + 'static>;
 impl<T> TabsView<T> {
-    pub fn new(_window: &mut Window, _cx: &mut Context<Self>) -> Self {
+    pub fn new(
+        smooth_scroll: SmoothScrollSettings,
+        _window: &mut Window,
+        _cx: &mut Context<Self>,
+    ) -> Self {
         Self {
             active_tab: 0,
             tabs: vec![None],
+            closed_tabs: Vec::new(),
             scroll_handle: ScrollHandle::new(),
-            smooth_scroll: SmoothScrollState::new(),
+            smooth_scroll: SmoothScrollState::with_settings(smooth_scroll),
+            right_to_left: false,
+            context_menu: None,
             on_tab_changed: Box::new(|_window, _cx| {}),
+            on_last_tab_closed: Box::new(|_window, _cx| {}),
+        }
+    }
+    pub fn set_smooth_scroll(&mut self, settings: SmoothScrollSettings) {
+        self.smooth_scroll.set_settings(settings);
+    }
+    pub fn set_right_to_left(&mut self, right_to_left: bool, cx: &mut Context<Self>) {
+        if self.right_to_left != right_to_left {
+            self.right_to_left = right_to_left;
+            self.smooth_scroll.reset(); // stop animations
+            self.scroll_handle.set_offset(Point::default());
+            cx.notify();
+        }
+    }
+    /// Position of a tab in the tab bar counted from the left. Also converts a position back to
+    /// the tab's index.
+    fn display_position(&self, index: usize) -> usize {
+        if self.right_to_left {
+            self.tabs.len().saturating_sub(1).saturating_sub(index)
+        } else {
+            index
         }
     }
     pub fn on_tab_changed(&mut self, handler: impl Fn(&mut Window, &mut Context<Self>) + 'static) {
         self.on_tab_changed = Box::new(handler);
     }
+    /// Called after the only remaining tab was closed (it is then replaced by an empty tab).
+    pub fn on_last_tab_closed(
+        &mut self,
+        handler: impl Fn(&mut Window, &mut Context<Self>) + 'static,
+    ) {
+        self.on_last_tab_closed = Box::new(handler);
+    }
 
     pub fn create_tab(&mut self, data: Option<T>, window: &mut Window, cx: &mut Context<Self>) {
-        self.active_tab = self.tabs.len();
-        self.tabs.push(data);
+        self.insert_tab(self.tabs.len(), data, window, cx);
+    }
+    /// Create a tab at `index` (or last if there are fewer tabs) and activate it.
+    pub fn insert_tab(
+        &mut self,
+        index: usize,
+        data: Option<T>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.active_tab = index.min(self.tabs.len());
+        self.tabs.insert(self.active_tab, data);
         (self.on_tab_changed)(window, cx);
     }
     pub fn remove_tab(&mut self, index: usize, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(Some(data)) = self.tabs.get_mut(index).map(Option::take) {
+            if self.closed_tabs.len() >= MAX_CLOSED_TABS {
+                self.closed_tabs.remove(0);
+            }
+            self.closed_tabs.push((index, data));
+        }
         match self.tabs.len() {
             0 => return,
             1 => {
                 self.tabs[0] = None;
                 (self.on_tab_changed)(window, cx);
+                (self.on_last_tab_closed)(window, cx);
             }
             _ => {
                 self.tabs.remove(index);
@@ -305,19 +622,35 @@ impl<T> TabsView<T> {
         }
         cx.notify();
     }
+    /// Open the most recently closed tab again at the position it had.
+    pub fn reopen_tab(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some((index, data)) = self.closed_tabs.pop() else {
+            return;
+        };
+        if let [tab @ None] = self.tabs.as_mut_slice() {
+            // Replace the empty tab that is left when the last tab is closed:
+            *tab = Some(data);
+            self.set_active_tab(0, window, cx);
+        } else {
+            self.insert_tab(index, Some(data), window, cx);
+        }
+        self.scroll_to_active_tab(window, cx);
+        cx.notify();
+    }
     pub fn set_active_tab(&mut self, index: usize, window: &mut Window, cx: &mut Context<Self>) {
         self.active_tab = index.min(self.tabs.len().saturating_sub(1));
         (self.on_tab_changed)(window, cx);
     }
 
     pub fn scroll_to_active_tab(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        if self.active_tab == 0 {
+        let position = self.display_position(self.active_tab);
+        if position == 0 {
             self.scroll_handle.set_offset(Point::default());
             self.smooth_scroll
                 .noticed_scroll(window, cx, &self.scroll_handle);
         } else {
             self.smooth_scroll
-                .scroll_to_item(window, cx, &self.scroll_handle, self.active_tab);
+                .scroll_to_item(window, cx, &self.scroll_handle, position);
 
             // Previously used the inbuilt method:
             // self.scroll_handle.scroll_to_item(self.active_tab); // <- updates the scroll offset later
@@ -370,6 +703,132 @@ impl<T> TabsView<T> {
         self.scroll_to_active_tab(window, cx);
         cx.notify();
     }
+    pub fn on_action_reopen_tab(
+        &mut self,
+        _: &ReopenTab,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.reopen_tab(window, cx);
+    }
+    pub fn on_action_activate_tab(
+        &mut self,
+        action: &ActivateTab,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if action.0 >= self.tabs.len() {
+            return;
+        }
+        self.set_active_tab(action.0, window, cx);
+        self.scroll_to_active_tab(window, cx);
+        cx.notify();
+    }
+    pub fn on_action_activate_last_tab(
+        &mut self,
+        _: &ActivateLastTab,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.set_active_tab(self.tabs.len().saturating_sub(1), window, cx);
+        self.scroll_to_active_tab(window, cx);
+        cx.notify();
+    }
+}
+
+impl<T: TabData> TabsView<T> {
+    pub fn copy_file_path(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(data) = self.active_tab_data() else {
+            return;
+        };
+        let path = data.full_path();
+        cx.write_to_clipboard(ClipboardItem::new_string(path.display().to_string()));
+        window.push_notification(Notification::info(format!("Copied {}", path.display())), cx);
+    }
+    pub fn on_action_copy_file_path(
+        &mut self,
+        _: &CopyFilePath,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.copy_file_path(window, cx);
+    }
+
+    pub fn reveal_in_file_manager(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(data) = self.active_tab_data() else {
+            return;
+        };
+        let path = data.full_path();
+        if !prompt::reveal_in_file_manager(&path, cx) {
+            log::warn!(
+                "Can't show {} since its folder no longer exists",
+                path.display()
+            );
+            window.push_notification(
+                Notification::error(format!("{} no longer exists", path.display())),
+                cx,
+            );
+        }
+    }
+    pub fn on_action_reveal_in_file_manager(
+        &mut self,
+        _: &RevealInFileManager,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.reveal_in_file_manager(window, cx);
+    }
+
+    fn render_context_menu(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let has_file = self.active_tab_data().is_some();
+        v_flex()
+            .id("tab-context-menu")
+            .min_w(px(160.))
+            .gap_1()
+            .p_1()
+            .bg(cx.theme().popover)
+            .text_color(cx.theme().popover_foreground)
+            .border_1()
+            .border_color(cx.theme().border)
+            .rounded(cx.theme().radius)
+            .shadow_md()
+            .on_mouse_down_out(cx.listener(|view, _, _window, cx| {
+                view.context_menu = None;
+                cx.notify();
+            }))
+            .child(
+                Button::new("tab-copy-file-path")
+                    .ghost()
+                    .label("Copy file path")
+                    .disabled(!has_file)
+                    .on_click(cx.listener(|view, _, window, cx| {
+                        view.context_menu = None;
+                        view.copy_file_path(window, cx);
+                        cx.notify();
+                    })),
+            )
+            .child(
+                Button::new("tab-reveal-in-file-manager")
+                    .ghost()
+                    .label("Show in file manager")
+                    .disabled(!has_file)
+                    .on_click(cx.listener(|view, _, window, cx| {
+                        view.context_menu = None;
+                        view.reveal_in_file_manager(window, cx);
+                        cx.notify();
+                    })),
+            )
+            .child(div().h(px(1.)).bg(cx.theme().border))
+            .child(
+                Button::new("tab-close")
+                    .ghost()
+                    .label("Close tab")
+                    .on_click(cx.listener(|view, _, window, cx| {
+                        view.context_menu = None;
+                        view.remove_tab(view.active_tab, window, cx);
+                    })),
+            )
+    }
 }
 
 /// Payload for `on_drag` event.
@@ -424,13 +883,15 @@ impl<T: TabData> Render for TabsView<T> {
         };
         let tab_bar = TabBar::new("dynamic-tabs-with-pdf-files")
             .with_menu(self.tabs.len() > 1)
-            .selected_index(self.active_tab)
+            .selected_index(self.display_position(self.active_tab))
             .track_scroll(&self.scroll_handle)
-            .on_click(cx.listener(|view, index, window, cx| {
-                view.set_active_tab(*index, window, cx);
+            .on_click(cx.listener(|view, position, window, cx| {
+                view.set_active_tab(view.display_position(*position), window, cx);
                 view.scroll_to_active_tab(window, cx);
             }))
-            .children(self.tabs.iter().enumerate().map(|(tab_index, tab_data)| {
+            .children((0..self.tabs.len()).map(|position| {
+                let tab_index = self.display_position(position);
+                let tab_data = &self.tabs[tab_index];
                 let label = if let Some(tab_data) = tab_data {
                     tab_data.label()
                 } else {
@@ -448,8 +909,17 @@ impl<T: TabData> Render for TabsView<T> {
                             cx.new(|_| drag.clone())
                         },
                     )
-                    .drag_over::<DragTab>(|this, _, _, cx| {
-                        this.border_l_2().border_color(cx.theme().drag_border)
+                    .drag_over::<DragTab>({
+                        let right_to_left = self.right_to_left;
+                        move |this, _, _, cx| {
+                            // Mark the side that the dropped tab will be inserted at:
+                            let this = if right_to_left {
+                                this.border_r_2()
+                            } else {
+                                this.border_l_2()
+                            };
+                            this.border_color(cx.theme().drag_border)
+                        }
                     })
                     .on_drop(cx.listener(move |view, drag: &DragTab, _window, cx| {
                         let tab = view.tabs.remove(drag.index);
@@ -472,12 +942,22 @@ impl<T: TabData> Render for TabsView<T> {
                             .right_0()
                             .left_0()
                             .on_any_mouse_down(cx.listener(move |view, event, window, cx| {
-                                if let MouseDownEvent {
-                                    button: MouseButton::Middle,
-                                    ..
-                                } = event
-                                {
-                                    view.remove_tab(tab_index, window, cx);
+                                match event {
+                                    MouseDownEvent {
+                                        button: MouseButton::Middle,
+                                        ..
+                                    } => view.remove_tab(tab_index, window, cx),
+                                    MouseDownEvent {
+                                        button: MouseButton::Right,
+                                        position,
+                                        ..
+                                    } => {
+                                        // The menu's entries act on the active tab:
+                                        view.set_active_tab(tab_index, window, cx);
+                                        view.context_menu = Some(*position);
+                                        cx.notify();
+                                    }
+                                    _ => {}
                                 }
                             })),
                     )
@@ -502,7 +982,8 @@ impl<T: TabData> Render for TabsView<T> {
 
         div()
             .flex()
-            .flex_row_reverse()
+            .when(!self.right_to_left, |this| this.flex_row_reverse())
+            .when(self.right_to_left, |this| this.flex_row())
             .w_full()
             .overflow_hidden()
             .refine_style(&StyleRefinement {
@@ -548,6 +1029,11 @@ impl<T: TabData> Render for TabsView<T> {
                                             }
                                         }
                                     } else {
+                                        view.smooth_scroll.noticed_scroll_gesture(
+                                            window,
+                                            event.delta.pixel_delta(window.line_height()),
+                                            event.touch_phase,
+                                        );
                                         view.smooth_scroll.noticed_scroll_wheel_event(
                                             window,
                                             cx,
@@ -559,5 +1045,16 @@ impl<T: TabData> Render for TabsView<T> {
                     )
                     .child(tab_bar),
             )
+            .when_some(self.context_menu, |this, position| {
+                this.child(
+                    deferred(
+                        anchored()
+                            .position(position)
+                            .snap_to_window_with_margin(px(8.))
+                            .child(self.render_context_menu(cx)),
+                    )
+                    .with_priority(1),
+                )
+            })
     }
 }
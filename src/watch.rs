@@ -0,0 +1,150 @@
+//! Notice when open files are changed on disk so that they can be reloaded.
+//!
+//! The folders that contain the files are watched instead of the files themselves since many
+//! programs save by writing a new file and renaming it over the old one, which would end a watch
+//! on the old file.
+
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Poll, Waker};
+
+#[derive(Default)]
+struct WatcherState {
+    /// Files that should be reported when they change.
+    files: HashSet<PathBuf>,
+    /// Watched files that changed since [`FileWatcher::changes`] last returned.
+    changed: HashSet<PathBuf>,
+    /// Wakes the task waiting for [`FileWatcher::changes`].
+    waker: Option<Waker>,
+    /// Set when the [`FileWatcher`] is dropped.
+    closed: bool,
+}
+
+pub struct FileWatcher {
+    /// `None` if the platform's watcher couldn't be created, then no changes are reported.
+    watcher: Option<notify::RecommendedWatcher>,
+    state: Arc<Mutex<WatcherState>>,
+    /// Watched folders and how many watched files they contain.
+    folders: HashMap<PathBuf, usize>,
+}
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        let mut guard = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        guard.closed = true;
+        if let Some(waker) = guard.waker.take() {
+            waker.wake();
+        }
+    }
+}
+impl Default for FileWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl FileWatcher {
+    pub fn new() -> Self {
+        let state = Arc::new(Mutex::new(WatcherState::default()));
+        let watcher = notify::recommended_watcher({
+            let state = state.clone();
+            move |event: notify::Result<notify::Event>| {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(e) => {
+                        log::warn!("File watcher error: {e}");
+                        return;
+                    }
+                };
+                if matches!(event.kind, EventKind::Access(_)) {
+                    return;
+                }
+                let mut guard = state.lock().unwrap();
+                let state = &mut *guard;
+                let mut changed = false;
+                for path in event.paths {
+                    if state.files.contains(&path) {
+                        changed |= state.changed.insert(path);
+                    }
+                }
+                if changed && let Some(waker) = state.waker.take() {
+                    waker.wake();
+                }
+            }
+        })
+        .inspect_err(|e| log::error!("Failed to create file watcher: {e}"))
+        .ok();
+        Self {
+            watcher,
+            state,
+            folders: HashMap::new(),
+        }
+    }
+
+    /// Only report changes to these files from now on.
+    pub fn set_files<'a>(&mut self, files: impl IntoIterator<Item = &'a Path>) {
+        let files = files
+            .into_iter()
+            .map(Path::to_owned)
+            .collect::<HashSet<_>>();
+        let mut folders = HashMap::<PathBuf, usize>::new();
+        for file in &files {
+            if let Some(folder) = file.parent() {
+                *folders.entry(folder.to_owned()).or_default() += 1;
+            }
+        }
+        if let Some(watcher) = &mut self.watcher {
+            for folder in self.folders.keys() {
+                if !folders.contains_key(folder)
+                    && let Err(e) = watcher.unwatch(folder)
+                {
+                    log::debug!("Failed to stop watching {}: {e}", folder.display());
+                }
+            }
+            for folder in folders.keys() {
+                if !self.folders.contains_key(folder)
+                    && let Err(e) = watcher.watch(folder, RecursiveMode::NonRecursive)
+                {
+                    log::warn!("Failed to watch {} for changes: {e}", folder.display());
+                }
+            }
+        }
+        self.folders = folders;
+
+        let mut guard = self.state.lock().unwrap();
+        guard.changed.retain(|path| files.contains(path));
+        guard.files = files;
+    }
+
+    /// Wait until at least one watched file changes and return all changed files. Returns `None`
+    /// once the watcher has been dropped.
+    pub fn changes(&self) -> impl Future<Output = Option<Vec<PathBuf>>> + use<> {
+        struct WaitForChange {
+            state: Arc<Mutex<WatcherState>>,
+        }
+        impl Future for WaitForChange {
+            type Output = Option<Vec<PathBuf>>;
+
+            fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
+                let mut guard = self.state.lock().unwrap();
+                if guard.closed {
+                    Poll::Ready(None)
+                } else if !guard.changed.is_empty() {
+                    Poll::Ready(Some(guard.changed.drain().collect()))
+                } else {
+                    guard.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+        WaitForChange {
+            state: self.state.clone(),
+        }
+    }
+
+    /// Take changes that happened since [`Self::changes`] last returned without waiting.
+    pub fn take_changes(&self) -> Vec<PathBuf> {
+        self.state.lock().unwrap().changed.drain().collect()
+    }
+}
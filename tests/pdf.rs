@@ -0,0 +1,79 @@
+//! Tests that load the PDF files in `tests/fixtures`.
+
+use hayro_interpret::InterpreterSettings;
+use pdf_reader_gpui::pdf::{self, MAX_PAGE_DIMENSION, MAX_PIXMAP_DIMENSION};
+use std::sync::Arc;
+
+/// A single page whose media box is 2 097 152 by 1 024 units, far larger than the 14 400 units
+/// that the PDF specification allows. The left half of the page is blue.
+const HUGE_MEDIA_BOX: &[u8] = include_bytes!("fixtures/huge-mediabox.pdf");
+/// A single page that is 4 by 2 units with a red left half.
+const TINY: &[u8] = include_bytes!("fixtures/tiny.pdf");
+
+#[test]
+fn renders_tiny_page() {
+    let image = pdf::render_page_image(Arc::new(TINY.to_vec()), 0, 2.).unwrap();
+    assert_eq!(image.dimensions(), (8, 4));
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let expected = if x < 4 {
+            [255, 0, 0, 255]
+        } else {
+            [255, 255, 255, 255]
+        };
+        assert_eq!(pixel.0, expected, "pixel at {x}, {y}");
+    }
+}
+
+#[test]
+fn missing_page_is_an_error() {
+    let result = pdf::render_page_image(Arc::new(TINY.to_vec()), 1, 1.);
+    assert_eq!(
+        result.unwrap_err(),
+        pdf::RenderPageError::NoSuchPage {
+            index: 1,
+            page_count: 1
+        }
+    );
+}
+
+#[test]
+fn huge_media_box_is_clamped() {
+    let pdf = pdf::load_pdf(Arc::new(HUGE_MEDIA_BOX.to_vec())).unwrap();
+    let page = &pdf.pages()[0];
+    assert_eq!(page.render_dimensions(), (2_097_152., 1024.));
+
+    // The page is laid out at a plausible size with the same aspect ratio:
+    let (width, height) = pdf::page_dimensions(page);
+    assert_eq!(width, MAX_PAGE_DIMENSION);
+    assert!((height - MAX_PAGE_DIMENSION / 2048.).abs() < 0.01);
+    assert!((pdf::page_dimension_scale(page) - MAX_PAGE_DIMENSION / 2_097_152.).abs() < 1e-6);
+
+    // Rendering at actual size doesn't allocate a gigantic image:
+    let image = pdf::render_page_image(Arc::new(HUGE_MEDIA_BOX.to_vec()), 0, 1.).unwrap();
+    assert_eq!(image.dimensions(), (MAX_PIXMAP_DIMENSION as u32, 4));
+    assert_eq!(image.get_pixel(0, 0).0, [0, 0, 255, 255]);
+    assert_eq!(
+        image.get_pixel(image.width() - 1, 0).0,
+        [255, 255, 255, 255]
+    );
+}
+
+#[test]
+fn huge_media_box_region_is_clamped() {
+    let pdf = pdf::load_pdf(Arc::new(HUGE_MEDIA_BOX.to_vec())).unwrap();
+    let page = &pdf.pages()[0];
+    // The middle of the page at actual size, which would need a 2 097 152 pixel wide render:
+    let region = kurbo::Rect::new(1_048_576. - 4096., 0., 1_048_576. + 4096., 1024.);
+    let png = pdf::render_region_png(page, &InterpreterSettings::default(), 1., region).unwrap();
+    let image = image::load_from_memory(&png).unwrap().into_rgba8();
+    let factor = MAX_PIXMAP_DIMENSION / 2_097_152.;
+    assert_eq!(
+        image.dimensions(),
+        ((8192. * factor) as u32, (1024. * factor) as u32)
+    );
+    assert_eq!(image.get_pixel(0, 0).0, [0, 0, 255, 255]);
+    assert_eq!(
+        image.get_pixel(image.width() - 1, 0).0,
+        [255, 255, 255, 255]
+    );
+}